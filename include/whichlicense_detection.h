@@ -0,0 +1,189 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+*
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+*
+*   http://www.apache.org/licenses/LICENSE-2.0
+*
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+#ifndef WHICHLICENSE_DETECTION_H
+#define WHICHLICENSE_DETECTION_H
+
+/* Generated by cbindgen from src/ffi/mod.rs, do not edit. Regenerate with `cbindgen --config cbindgen.toml --output include/whichlicense_detection.h`. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The version of the on-disk layout written by this version of the crate.
+//
+// Databases with any other version are rejected and need to be rebuilt.
+#define DATABASE_FORMAT_VERSION 5
+
+// The result of every FFI call.
+typedef enum WldStatus {
+  WLD_STATUS_OK = 0,
+  // A required pointer argument was NULL.
+  WLD_STATUS_NULL_POINTER = 1,
+  // A string argument was not valid UTF-8.
+  WLD_STATUS_INVALID_UTF8 = 2,
+  WLD_STATUS_FILE_NOT_FOUND = 3,
+  WLD_STATUS_CORRUPT_DATABASE = 4,
+  WLD_STATUS_INCOMPATIBLE_FORMAT = 5,
+  WLD_STATUS_IO = 6,
+  WLD_STATUS_INVALID_PARAMETER = 7,
+  WLD_STATUS_MISSING_REFERENCE_TEXT = 8,
+  WLD_STATUS_INVALID_EXPRESSION = 9,
+  // The library panicked, this is a bug.
+  WLD_STATUS_PANIC = 10,
+} WldStatus;
+
+// The algorithm of a detector created with [wld_detector_new], passed as a `uint32_t`.
+typedef enum WldAlgorithm {
+  WLD_ALGORITHM_FUZZY = 0,
+  WLD_ALGORITHM_GAOYA = 1,
+  WLD_ALGORITHM_BLOCK_HASH = 2,
+} WldAlgorithm;
+
+// An opaque handle to a detector, released with [wld_detector_free].
+typedef struct WldDetector WldDetector;
+
+// A single license match, see `LicenseMatch`.
+typedef struct WldMatch {
+  // The NUL terminated name of the license, owned by the surrounding [WldMatches].
+  char *name;
+  float confidence;
+} WldMatch;
+
+// The matches returned by [wld_detector_match], sorted by confidence. Released with [wld_matches_free].
+typedef struct WldMatches {
+  struct WldMatch *matches;
+  size_t len;
+} WldMatches;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns a static, NUL terminated description of the raw value of a [WldStatus].
+//
+// C callers can pass any integer so it is not taken as the enum itself, unknown values are described as such.
+const char *wld_status_description(uint32_t status);
+
+// Returns the message of the last failed call on this thread, or NULL if no call failed yet.
+//
+// The string is owned by the library and valid until the next failing call on the same thread.
+const char *wld_last_error_message(void);
+
+// Creates a detector without any licenses, `algorithm` is one of the [WldAlgorithm] values.
+//
+// # Safety
+// `out` must be NULL or valid for writes.
+enum WldStatus wld_detector_new(uint32_t algorithm, struct WldDetector **out);
+
+#if defined(WLD_EMBEDDED_DATABASE)
+// Creates a detector using the database embedded in the library, `algorithm` is one of the [WldAlgorithm] values.
+//
+// Returns [WldStatus::InvalidParameter] for [WldAlgorithm::BlockHash], there is no embedded block hash database.
+//
+// # Safety
+// `out` must be NULL or valid for writes.
+enum WldStatus wld_detector_with_default_database(uint32_t algorithm,
+                                                  struct WldDetector **out);
+#endif
+
+// Creates a detector from a saved database, the algorithm and its parameters are read from the database's header.
+//
+// # Safety
+// `data` must be NULL or valid for reads of `len` bytes, `out` must be NULL or valid for writes.
+enum WldStatus wld_detector_load(const uint8_t *data,
+                                 size_t len,
+                                 struct WldDetector **out);
+
+// Saves the detector's database, the buffer written to `out_data` is released with [wld_bytes_free].
+//
+// # Safety
+// `detector` must be NULL or a live handle, `out_data` and `out_len` must be NULL or valid for writes.
+enum WldStatus wld_detector_save(const struct WldDetector *detector,
+                                 uint8_t **out_data,
+                                 size_t *out_len);
+
+// Adds (or replaces) a license.
+//
+// # Safety
+// `detector` must be NULL or a live handle, `name` and `text` must be NULL or NUL terminated strings.
+enum WldStatus wld_detector_add_license(struct WldDetector *detector,
+                                        const char *name,
+                                        const char *text);
+
+// Removes a license, unknown licenses are ignored.
+//
+// # Safety
+// `detector` must be NULL or a live handle, `name` must be NULL or a NUL terminated string.
+enum WldStatus wld_detector_remove_license(struct WldDetector *detector, const char *name);
+
+// Sets the confidence (0-100) below which matches are discarded, larger values return [WldStatus::InvalidParameter].
+//
+// # Safety
+// `detector` must be NULL or a live handle.
+enum WldStatus wld_detector_set_min_confidence(struct WldDetector *detector,
+                                               uint8_t min_confidence);
+
+// Matches a license text, the matches written to `out` are released with [wld_matches_free].
+//
+// A detector may be used from multiple threads at once as long as it is not modified at the same time.
+//
+// # Safety
+// `detector` must be NULL or a live handle, `text` must be NULL or a NUL terminated string and `out` must be NULL or valid for writes.
+enum WldStatus wld_detector_match(const struct WldDetector *detector,
+                                  const char *text,
+                                  struct WldMatches *out);
+
+// Releases a detector, NULL is ignored.
+//
+// # Safety
+// `detector` must be NULL or a live handle, it must not be used afterwards.
+void wld_detector_free(struct WldDetector *detector);
+
+// Releases the matches returned by [wld_detector_match] and resets `matches` to an empty list.
+//
+// # Safety
+// `matches` must be NULL or point to matches returned by [wld_detector_match] that have not been freed.
+void wld_matches_free(struct WldMatches *matches);
+
+// Normalizes a license text with the default normalization function, the string written to `out` is released with [wld_string_free].
+//
+// # Safety
+// `text` must be NULL or a NUL terminated string, `out` must be NULL or valid for writes.
+enum WldStatus wld_strip_license(const char *text,
+                                 char **out);
+
+// Releases a string returned by the library, NULL is ignored.
+//
+// # Safety
+// `string` must be NULL or a string returned by this library that has not been freed.
+void wld_string_free(char *string);
+
+// Releases a buffer returned by [wld_detector_save], NULL is ignored.
+//
+// # Safety
+// `data` must be NULL or a buffer returned by this library together with its length, it must not have been freed.
+void wld_bytes_free(uint8_t *data,
+                    size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* WHICHLICENSE_DETECTION_H */
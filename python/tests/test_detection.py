@@ -0,0 +1,71 @@
+#   Copyright (c) 2023 Duart Snel
+#   All rights reserved.
+#
+#   Licensed under the Apache License, Version 2.0 (the "License");
+#   you may not use this file except in compliance with the License.
+#   You may obtain a copy of the License at
+#
+#   http://www.apache.org/licenses/LICENSE-2.0
+#
+#   Unless required by applicable law or agreed to in writing, software
+#   distributed under the License is distributed on an "AS IS" BASIS,
+#   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+#   See the License for the specific language governing permissions and
+#   limitations under the License.
+
+# Run with `maturin develop && python -m unittest discover python/tests`.
+
+import pathlib
+import unittest
+
+from whichlicense_detection import FuzzyDetection, GaoyaDetection, Pipeline, Segment
+
+APACHE = (pathlib.Path(__file__).parents[2] / "LICENSE").read_text()
+
+
+class DetectionTest(unittest.TestCase):
+    def test_it_matches_the_default_database(self):
+        for detector in (FuzzyDetection.with_default_database(), GaoyaDetection.with_default_database()):
+            matches = detector.match_by_plain_text(APACHE)
+            self.assertEqual(matches[0].name, "apache-2.0.LICENSE")
+            self.assertGreater(matches[0].confidence, 90)
+
+    def test_it_round_trips_a_database(self):
+        gaoya = GaoyaDetection(shingle_size=30)
+        gaoya.add_plain("apache-2.0", APACHE)
+        loaded = GaoyaDetection(shingle_size=30)
+        loaded.add_plain("mit", "Permission is hereby granted, free of charge")
+        loaded.load_from_memory(gaoya.save_to_memory())
+        loaded.load_from_memory(gaoya.save_to_memory())
+        self.assertEqual(loaded.license_names(), ["apache-2.0"])
+        self.assertEqual(len(loaded), 1)
+        self.assertEqual(loaded.match_by_plain_text(APACHE)[0].name, "apache-2.0")
+
+    def test_it_rejects_invalid_input(self):
+        with self.assertRaises(ValueError):
+            FuzzyDetection().load_from_memory(b"not a database")
+        with self.assertRaises(ValueError):
+            GaoyaDetection(bands=0)
+        with self.assertRaises(ValueError):
+            Pipeline([Segment.remove(regex="(")])
+        with self.assertRaises(ValueError):
+            Segment.builtin("nope")
+        with self.assertRaises(TypeError):
+            Pipeline([]).run(object(), APACHE, 100)
+
+    def test_it_runs_a_pipeline(self):
+        fuzzy = FuzzyDetection()
+        fuzzy.add_plain("hello", "Hello, world!")
+        pipeline = Pipeline([
+            Segment.remove(text="-"),
+            Segment.batch([Segment.replace("", regex="[xyz]+")]),
+            Segment.builtin("collapse_whitespace"),
+        ])
+        runs = pipeline.run(fuzzy, "Hello, wo-rld!xyz", 100)
+        self.assertEqual(len(runs), 3)
+        self.assertEqual(runs[-1][0].name, "hello")
+        self.assertEqual(runs[-1][0].confidence, 100)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -15,65 +15,70 @@
 *   limitations under the License.
 */
 
+/// The block hashing used by [BlockHashDetection](crate::detecting::block_hash_implementation::block_hash_implementation::BlockHashDetection).
+///
+/// The input is split into blocks of a fixed size and every block is reduced to a single number, two hashes are compared
+/// block by block. Because blocks are compared by position this is fast, but an insertion early in a text shifts every
+/// block after it.
 pub mod custom_fuzzyhash {
+    /// Hashes the first `hash_length` bytes of the buffer.
     pub fn create_hash(buffer: &[u8], hash_length: usize) -> i64 {
-        let mut hash = 0;
+        let mut hash: i64 = 0;
         // TODO: rust has a built in hasher. maybe this is better?
-        for i in 0..std::cmp::min(hash_length, buffer.len()) {
-            hash = (hash << 5) - hash + buffer[i] as i64;
-            hash |= 0;
+        for byte in buffer.iter().take(hash_length) {
+            hash = hash.wrapping_shl(5).wrapping_sub(hash).wrapping_add(*byte as i64);
         }
 
         hash
     }
 
-    pub fn fuzzy_hash(input: &Vec<u8>, block_size: usize, hash_length: usize) -> Vec<i64> {
-        let mut blocks = Vec::new();
-
-        for i in (0..input.len()).step_by(block_size) {
-            let block = &input[i..std::cmp::min(i + block_size, input.len())];
-            let hash = create_hash(block, hash_length);
-            blocks.push(hash);
-        }
-
-        blocks
+    /// Splits the input into blocks of `block_size` bytes and hashes each of them, see [create_hash].
+    ///
+    /// # Panics
+    /// Panics if `block_size` is 0.
+    pub fn fuzzy_hash(input: &[u8], block_size: usize, hash_length: usize) -> Vec<i64> {
+        input
+            .chunks(block_size)
+            .map(|block| create_hash(block, hash_length))
+            .collect()
     }
 
+    /// The number of blocks that may differ for two hashes of `n_blocks` blocks to still reach `min_confidence` (0-1).
     pub fn calc_max_uncommon_blocks(min_confidence: f64, n_blocks: usize) -> usize {
-        (n_blocks as f64 - (min_confidence * n_blocks as f64).ceil()) as usize
+        (n_blocks as f64 - (min_confidence * n_blocks as f64).ceil()).max(0.0) as usize
     }
 
-    pub fn compare_hashes(hash1: Vec<i64>, hash2: Vec<i64>, min_confidence: f64) -> (i32, usize, f64) {
-        let mut common_blocks = 0;
-        let mut uncommon_blocks = 0;
+    /// Compares two hashes block by block, returning the number of common blocks, the number of blocks of the longer hash
+    /// and the ratio between the two.
+    ///
+    /// Stops as soon as `min_confidence` (0-1) can no longer be reached, in which case the common blocks and ratio are -1.
+    pub fn compare_hashes(hash1: &[i64], hash2: &[i64], min_confidence: f64) -> (i32, usize, f64) {
         let max_blocks = std::cmp::max(hash1.len(), hash2.len());
+        let max_uncommon_blocks = calc_max_uncommon_blocks(min_confidence, max_blocks);
 
-        let max_uncommon_blocks = if min_confidence == 0.0 {
-            max_blocks + max_blocks
-        } else {
-            calc_max_uncommon_blocks(min_confidence, max_blocks)
-        };
-
-        uncommon_blocks = (hash1.len() as i32 - hash2.len() as i32).abs() as usize;
+        // the blocks of the longer hash that have no counterpart are never common.
+        let mut uncommon_blocks = hash1.len().abs_diff(hash2.len());
         if uncommon_blocks > max_uncommon_blocks {
-            return (-1, std::cmp::max(hash1.len(), hash2.len()), -1.0);
+            return (-1, max_blocks, -1.0);
         }
 
-        for i in 0..max_blocks {
-            if hash1.get(i) == hash2.get(i) {
+        let mut common_blocks = 0;
+        for (a, b) in hash1.iter().zip(hash2) {
+            if a == b {
                 common_blocks += 1;
             } else {
                 uncommon_blocks += 1;
                 if uncommon_blocks > max_uncommon_blocks {
-                    return (-1, std::cmp::max(hash1.len(), hash2.len()), -1.0);
+                    return (-1, max_blocks, -1.0);
                 }
             }
         }
 
-        (
-            common_blocks as i32,
-            max_blocks,
-            (common_blocks as f64) / std::cmp::max(hash1.len(), hash2.len()) as f64,
-        )
+        let ratio = if max_blocks == 0 {
+            0.0
+        } else {
+            common_blocks as f64 / max_blocks as f64
+        };
+        (common_blocks, max_blocks, ratio)
     }
 }
@@ -0,0 +1,288 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+pub mod block_hash_implementation {
+    use std::sync::Arc;
+
+    use crate::{
+        custom_fuzzy::custom_fuzzyhash::{compare_hashes, fuzzy_hash},
+        detecting::detecting::{sort_matches, LicenseEntry},
+        normalized_digest, AlgorithmParameters, DetectionError, DigestIndex, LicenseListActions, LicenseMatch, LicenseMetadata, Normalizer,
+        DEFAULT_NORMALIZER,
+    };
+
+    /// Detection using the block hashes of [custom_fuzzyhash](crate::custom_fuzzy::custom_fuzzyhash).
+    ///
+    /// Comparisons stop early once a license can no longer reach [min_confidence](BlockHashDetection::min_confidence),
+    /// which makes this the fastest backend, at the cost of larger hashes and a lower tolerance for inserted text.
+    pub struct BlockHashDetection {
+        pub licenses: Vec<LicenseEntry<Vec<i64>>>,
+        pub min_confidence: u8,
+        pub exit_on_exact_match: bool,
+        /// The number of bytes of normalized text that make up a block.
+        pub block_size: usize,
+        /// The number of bytes at the start of each block that are hashed.
+        pub hash_length: usize,
+        /// Whether [add_plain](LicenseListActions::add_plain) keeps the license text, so it is saved in the database and can be diffed against.
+        pub store_reference_texts: bool,
+        /// The digests of the normalized license texts, kept up to date by [add_plain](LicenseListActions::add_plain),
+        /// [remove](LicenseListActions::remove) and loading a database.
+        pub digests: DigestIndex,
+
+        /// Normalizes license texts before they are hashed, see [Normalizer].
+        pub normalizer: Arc<dyn Normalizer>,
+    }
+
+    impl BlockHashDetection {
+        /// Returns a builder to configure a new, empty, [BlockHashDetection].
+        ///
+        /// ```
+        /// use whichlicense_detection::detecting::block_hash_implementation::block_hash_implementation::BlockHashDetection;
+        ///
+        /// let block_hash = BlockHashDetection::builder().block_size(16).hash_length(16).build().unwrap();
+        /// assert_eq!(block_hash.block_size, 16);
+        /// ```
+        pub fn builder() -> BlockHashDetectionBuilder {
+            BlockHashDetectionBuilder::default()
+        }
+
+        fn hash_normalized(&self, normalized_text: &str) -> Vec<i64> {
+            fuzzy_hash(normalized_text.as_bytes(), self.block_size, self.hash_length)
+        }
+    }
+
+    impl Default for BlockHashDetection {
+        fn default() -> Self {
+            Self::builder().build().unwrap()
+        }
+    }
+
+    /// Builder for [BlockHashDetection], see [BlockHashDetection::builder].
+    #[derive(Debug, Clone)]
+    pub struct BlockHashDetectionBuilder {
+        min_confidence: u8,
+        exit_on_exact_match: bool,
+        block_size: usize,
+        hash_length: usize,
+        store_reference_texts: bool,
+        normalizer: Arc<dyn Normalizer>,
+    }
+
+    impl Default for BlockHashDetectionBuilder {
+        fn default() -> Self {
+            Self {
+                min_confidence: 50,
+                exit_on_exact_match: false,
+                block_size: 8,
+                hash_length: 8,
+                store_reference_texts: false,
+                normalizer: Arc::new(DEFAULT_NORMALIZER),
+            }
+        }
+    }
+
+    impl BlockHashDetectionBuilder {
+        /// Matches below this confidence (0-100) are discarded. Defaults to 50.
+        pub fn min_confidence(mut self, min_confidence: u8) -> Self {
+            self.min_confidence = min_confidence;
+            self
+        }
+
+        /// Stops comparing as soon as a license with a confidence of 100 is found. Defaults to false.
+        pub fn exit_on_exact_match(mut self, exit_on_exact_match: bool) -> Self {
+            self.exit_on_exact_match = exit_on_exact_match;
+            self
+        }
+
+        /// The number of bytes of normalized text that make up a block. Defaults to 8.
+        pub fn block_size(mut self, block_size: usize) -> Self {
+            self.block_size = block_size;
+            self
+        }
+
+        /// The number of bytes at the start of each block that are hashed, at most the block size. Defaults to 8.
+        pub fn hash_length(mut self, hash_length: usize) -> Self {
+            self.hash_length = hash_length;
+            self
+        }
+
+        /// Keep the text of added licenses so it is saved in the database and can be diffed against. Defaults to false.
+        pub fn store_reference_texts(mut self, store_reference_texts: bool) -> Self {
+            self.store_reference_texts = store_reference_texts;
+            self
+        }
+
+        /// The normalization function, defaults to [DEFAULT_NORMALIZER].
+        ///
+        /// Functions, the built-in ones included, are all recorded as `"custom"`, so databases built with different
+        /// functions load into each other without an error. Pass a [BuiltinSegment](crate::BuiltinSegment) or a
+        /// [FnNormalizer](crate::FnNormalizer) to [normalizer](Self::normalizer) to record its own id.
+        pub fn normalization_fn(self, func: fn(&str) -> String) -> Self {
+            self.normalizer(func)
+        }
+
+        /// The normalizer, for normalization that is configured at runtime or chains several steps, see [Normalizer].
+        pub fn normalizer(mut self, normalizer: impl Normalizer + 'static) -> Self {
+            self.normalizer = Arc::new(normalizer);
+            self
+        }
+
+        pub fn build(self) -> Result<BlockHashDetection, DetectionError> {
+            if self.min_confidence > 100 {
+                return Err(DetectionError::InvalidParameter(format!(
+                    "min_confidence must be between 0 and 100, got {}",
+                    self.min_confidence
+                )));
+            }
+            if self.block_size == 0 {
+                return Err(DetectionError::InvalidParameter("block_size must be at least 1".to_string()));
+            }
+            if self.hash_length == 0 || self.hash_length > self.block_size {
+                return Err(DetectionError::InvalidParameter(format!(
+                    "hash_length must be between 1 and the block size ({}), got {}",
+                    self.block_size, self.hash_length
+                )));
+            }
+
+            Ok(BlockHashDetection {
+                licenses: vec![],
+                min_confidence: self.min_confidence,
+                exit_on_exact_match: self.exit_on_exact_match,
+                block_size: self.block_size,
+                hash_length: self.hash_length,
+                store_reference_texts: self.store_reference_texts,
+                digests: DigestIndex::default(),
+                normalizer: self.normalizer,
+            })
+        }
+    }
+
+    impl LicenseListActions<Vec<i64>> for BlockHashDetection {
+        fn match_by_plain_text(&self, plain_text: &str) -> Vec<LicenseMatch> {
+            let normalized = self.normalizer.normalize(plain_text);
+            let exact = self.match_exact(&normalized);
+            if !exact.is_empty() {
+                return exact;
+            }
+            self.match_by_hash(self.hash_normalized(&normalized))
+        }
+
+        fn match_by_hash(&self, hash: Vec<i64>) -> Vec<LicenseMatch> {
+            let min_confidence = self.min_confidence as f64 / 100.0;
+            let mut matches: Vec<LicenseMatch> = Vec::new();
+            for license in self.licenses.iter() {
+                let (common_blocks, _, ratio) = compare_hashes(&hash, &license.hash, min_confidence);
+                if common_blocks < 0 {
+                    continue;
+                }
+                let confidence = (ratio * 100.0) as f32;
+                if confidence >= self.min_confidence as f32 {
+                    matches.push(LicenseMatch {
+                        name: license.name.to_string(),
+                        confidence,
+                        metadata: license.metadata.clone(),
+                    });
+                    if self.exit_on_exact_match && confidence >= 100.0 {
+                        break;
+                    }
+                }
+            }
+            sort_matches(&mut matches);
+            matches
+        }
+
+        fn get_license_list(&self) -> Vec<(String, Vec<i64>)> {
+            self.licenses
+                .iter()
+                .map(|l| (l.name.to_string(), l.hash.clone()))
+                .collect()
+        }
+
+        fn get_reference_text(&self, license_name: &str) -> Option<&str> {
+            self.licenses
+                .iter()
+                .find(|l| l.name == license_name)
+                .and_then(|l| l.text.as_deref())
+        }
+
+        fn get_metadata(&self, license_name: &str) -> Option<&LicenseMetadata> {
+            self.licenses
+                .iter()
+                .find(|l| l.name == license_name)
+                .and_then(|l| l.metadata.as_ref())
+        }
+
+        /// Has no effect if the license has not been added.
+        fn set_metadata(&mut self, license_name: &str, metadata: LicenseMetadata) {
+            if let Some(l) = self.licenses.iter_mut().find(|l| l.name == license_name) {
+                l.metadata = Some(metadata);
+            }
+        }
+
+        fn get_parameters(&self) -> AlgorithmParameters {
+            AlgorithmParameters::BlockHash {
+                block_size: self.block_size,
+                hash_length: self.hash_length,
+            }
+        }
+
+        fn get_normalizer(&self) -> &dyn Normalizer {
+            self.normalizer.as_ref()
+        }
+
+        fn get_digest_index(&self) -> &DigestIndex {
+            &self.digests
+        }
+
+        fn try_load_from_memory(&mut self, raw: &[u8]) -> Result<(), DetectionError> {
+            let loaded = self.try_decode_database(raw)?;
+            for l in loaded.licenses.iter() {
+                if let Some(digest) = l.digest {
+                    self.digests.insert(&l.name, digest);
+                }
+            }
+            self.licenses.extend(loaded.licenses);
+            Ok(())
+        }
+
+        fn add_plain(&mut self, license_name: &str, license_text: &str) {
+            let normalized = self.normalizer.normalize(license_text);
+            let digest = normalized_digest(&normalized);
+            self.digests.insert(license_name, digest);
+            self.licenses.push(LicenseEntry {
+                name: license_name.to_string(),
+                hash: self.hash_normalized(&normalized),
+                text: self.store_reference_texts.then(|| license_text.to_string()),
+                metadata: None,
+                digest: Some(digest),
+            });
+        }
+
+        fn hash_from_inline_string(&self, license_text: &str) -> Vec<i64> {
+            self.hash_normalized(&self.normalizer.normalize(license_text))
+        }
+
+        fn remove(&mut self, license_name: &str) {
+            self.licenses.retain(|l| l.name != license_name);
+            self.digests.remove(license_name);
+        }
+
+        fn set_normalizer(&mut self, normalizer: Arc<dyn Normalizer>) {
+            self.normalizer = normalizer;
+        }
+    }
+}
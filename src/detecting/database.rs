@@ -0,0 +1,207 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+pub mod database {
+    use std::{fmt, io::Cursor};
+
+    use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+    use crate::{detecting::detecting::DiskData, DetectionError, Normalizer};
+
+    /// The bytes every database file starts with.
+    pub const DATABASE_MAGIC: [u8; 4] = *b"WLDB";
+
+    /// The version of the on-disk layout written by this version of the crate.
+    ///
+    /// Databases with any other version are rejected and need to be rebuilt.
+    pub const DATABASE_FORMAT_VERSION: u16 = 5;
+
+    /// The prebuilt fuzzy database shipped in `licenses/fuzzy_db`.
+    #[cfg(feature = "embedded-database")]
+    pub static EMBEDDED_FUZZY_DATABASE: &[u8] = include_bytes!("../../licenses/fuzzy_db");
+
+    /// The prebuilt gaoya database shipped in `licenses/gaoya_db`.
+    ///
+    /// Computed with 42 bands of width 3, a shingle size of 50 and [strip_license] as normalization function.
+    #[cfg(feature = "embedded-database")]
+    pub static EMBEDDED_GAOYA_DATABASE: &[u8] = include_bytes!("../../licenses/gaoya_db");
+
+    /// The algorithm a database was computed with.
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum AlgorithmKind {
+        Fuzzy,
+        Gaoya,
+        BlockHash,
+    }
+
+    impl fmt::Display for AlgorithmKind {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Fuzzy => write!(f, "fuzzy"),
+                Self::Gaoya => write!(f, "gaoya"),
+                Self::BlockHash => write!(f, "block-hash"),
+            }
+        }
+    }
+
+    /// What the normalized text is split into before it is shingled by the gaoya algorithm.
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum Shingling {
+        /// Shingles of `shingle_text_size` characters.
+        #[default]
+        Characters,
+        /// Shingles of `shingle_text_size` whitespace separated words, e.g. as produced by [normalize_tokens].
+        Words,
+    }
+
+    impl fmt::Display for Shingling {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Characters => write!(f, "characters"),
+                Self::Words => write!(f, "words"),
+            }
+        }
+    }
+
+    /// The parameters that influence the hashes stored in a database.
+    ///
+    /// Hashes computed with different parameters can not be compared with each other.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub enum AlgorithmParameters {
+        Fuzzy,
+        Gaoya {
+            num_bands: usize,
+            band_width: usize,
+            shingle_text_size: usize,
+            shingling: Shingling,
+        },
+        BlockHash {
+            block_size: usize,
+            hash_length: usize,
+        },
+    }
+
+    impl AlgorithmParameters {
+        pub fn kind(&self) -> AlgorithmKind {
+            match self {
+                Self::Fuzzy => AlgorithmKind::Fuzzy,
+                Self::Gaoya { .. } => AlgorithmKind::Gaoya,
+                Self::BlockHash { .. } => AlgorithmKind::BlockHash,
+            }
+        }
+    }
+
+    /// Self-describing header stored in front of the license list of every database.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct DatabaseHeader {
+        /// Always the first field so that it can be checked before decoding the rest of the header.
+        pub format_version: u16,
+        pub algorithm: AlgorithmKind,
+        pub parameters: AlgorithmParameters,
+        /// Identifies the normalizer the licenses were normalized with, see [Normalizer::id].
+        pub normalization: String,
+        pub license_count: u64,
+    }
+
+    impl DatabaseHeader {
+        pub fn new(parameters: AlgorithmParameters, normalizer: &dyn Normalizer, license_count: usize) -> Self {
+            Self {
+                format_version: DATABASE_FORMAT_VERSION,
+                algorithm: parameters.kind(),
+                parameters,
+                normalization: normalizer.id(),
+                license_count: license_count as u64,
+            }
+        }
+
+        /// Checks whether a database described by this header can be loaded into an algorithm described by `expected`.
+        pub fn check_compatibility(&self, expected: &DatabaseHeader) -> Result<(), DetectionError> {
+            if self.algorithm != expected.algorithm {
+                return Err(DetectionError::IncompatibleFormat(format!(
+                    "database was computed with the {} algorithm but is being loaded into {}",
+                    self.algorithm, expected.algorithm
+                )));
+            }
+            if self.parameters != expected.parameters {
+                return Err(DetectionError::IncompatibleFormat(format!(
+                    "database was computed with {:?} but the algorithm uses {:?}",
+                    self.parameters, expected.parameters
+                )));
+            }
+            if self.normalization != expected.normalization {
+                return Err(DetectionError::IncompatibleFormat(format!(
+                    "database was normalized with '{}' but the algorithm uses '{}'",
+                    self.normalization, expected.normalization
+                )));
+            }
+            Ok(())
+        }
+    }
+
+    /// Serializes the header and license list into the on-disk layout.
+    ///
+    /// The layout is: [DATABASE_MAGIC] followed by the bincode encoded [DatabaseHeader] and [DiskData].
+    pub fn encode_database<K: Serialize>(header: &DatabaseHeader, data: &DiskData<K>) -> Result<Vec<u8>, DetectionError> {
+        let mut raw = DATABASE_MAGIC.to_vec();
+        bincode::serialize_into(&mut raw, header)?;
+        bincode::serialize_into(&mut raw, data)?;
+        Ok(raw)
+    }
+
+    /// Reads and returns only the header of a database.
+    pub fn read_database_header(raw: &[u8]) -> Result<DatabaseHeader, DetectionError> {
+        read_header(&mut Cursor::new(raw))
+    }
+
+    /// Decodes a database, checking the header against the `expected` header of the algorithm it is being loaded into.
+    pub fn decode_database<K: DeserializeOwned>(raw: &[u8], expected: &DatabaseHeader) -> Result<DiskData<K>, DetectionError> {
+        let mut cursor = Cursor::new(raw);
+        let header = read_header(&mut cursor)?;
+        header.check_compatibility(expected)?;
+
+        let data: DiskData<K> = bincode::deserialize_from(&mut cursor)?;
+        if data.licenses.len() as u64 != header.license_count {
+            return Err(DetectionError::CorruptDatabase(format!(
+                "header announces {} licenses but {} were found",
+                header.license_count,
+                data.licenses.len()
+            )));
+        }
+        Ok(data)
+    }
+
+    fn read_header(cursor: &mut Cursor<&[u8]>) -> Result<DatabaseHeader, DetectionError> {
+        let raw = *cursor.get_ref();
+        if raw.len() < DATABASE_MAGIC.len() || raw[..DATABASE_MAGIC.len()] != DATABASE_MAGIC {
+            return Err(DetectionError::IncompatibleFormat(
+                "missing database header, the database was most likely created by an older version and needs to be rebuilt".to_string(),
+            ));
+        }
+        cursor.set_position(DATABASE_MAGIC.len() as u64);
+
+        let version: u16 = bincode::deserialize_from(&mut *cursor)?;
+        if version != DATABASE_FORMAT_VERSION {
+            return Err(DetectionError::IncompatibleFormat(format!(
+                "database format version {} is not supported, expected version {}",
+                version, DATABASE_FORMAT_VERSION
+            )));
+        }
+        cursor.set_position(DATABASE_MAGIC.len() as u64);
+
+        Ok(bincode::deserialize_from(&mut *cursor)?)
+    }
+}
@@ -0,0 +1,187 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+/// A detector whose algorithm is chosen at runtime, shared by the language bindings.
+pub(crate) mod dynamic {
+    use crate::{
+        detecting::{
+            block_hash_implementation::block_hash_implementation::BlockHashDetection,
+            fuzzy_implementation::fuzzy_implementation::FuzzyDetection,
+            gaoya_implementation::gaoya_implementation::GaoyaDetection,
+        },
+        normalizer_by_id, read_database_header, AlgorithmParameters, DetectionError, LicenseListActions,
+        LicenseMatch,
+    };
+    #[cfg(feature = "wasm")]
+    use crate::Pipeline;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(crate) enum Algorithm {
+        Fuzzy,
+        Gaoya,
+        BlockHash,
+    }
+
+    pub(crate) enum AnyDetector {
+        Fuzzy(FuzzyDetection),
+        Gaoya(Box<GaoyaDetection>),
+        BlockHash(BlockHashDetection),
+    }
+
+    impl AnyDetector {
+        /// Creates a detector without any licenses.
+        pub(crate) fn new(algorithm: Algorithm) -> Result<Self, DetectionError> {
+            Ok(match algorithm {
+                Algorithm::Fuzzy => Self::Fuzzy(FuzzyDetection::builder().build()?),
+                Algorithm::Gaoya => Self::Gaoya(Box::new(GaoyaDetection::builder().build()?)),
+                Algorithm::BlockHash => Self::BlockHash(BlockHashDetection::builder().build()?),
+            })
+        }
+
+        /// Fails for [Algorithm::BlockHash], there is no embedded block hash database.
+        #[cfg(feature = "embedded-database")]
+        pub(crate) fn with_default_database(algorithm: Algorithm) -> Result<Self, DetectionError> {
+            match algorithm {
+                Algorithm::Fuzzy => Ok(Self::Fuzzy(FuzzyDetection::with_default_database())),
+                Algorithm::Gaoya => Ok(Self::Gaoya(Box::new(GaoyaDetection::with_default_database()))),
+                Algorithm::BlockHash => Err(DetectionError::InvalidParameter(
+                    "there is no embedded block hash database".to_string(),
+                )),
+            }
+        }
+
+        /// Creates the detector described by the database's header and loads the database into it.
+        pub(crate) fn load(raw: &[u8]) -> Result<Self, DetectionError> {
+            let header = read_database_header(raw)?;
+            let normalizer = normalizer_by_id(&header.normalization).ok_or_else(|| {
+                DetectionError::IncompatibleFormat(format!("unknown normalizer '{}'", header.normalization))
+            })?;
+            let mut detector = match header.parameters {
+                AlgorithmParameters::Fuzzy => {
+                    Self::Fuzzy(FuzzyDetection::builder().normalizer(normalizer).build()?)
+                }
+                AlgorithmParameters::Gaoya {
+                    num_bands,
+                    band_width,
+                    shingle_text_size,
+                    shingling,
+                } => Self::Gaoya(Box::new(
+                    GaoyaDetection::builder()
+                        .bands(num_bands)
+                        .band_width(band_width)
+                        .shingle_size(shingle_text_size)
+                        .shingling(shingling)
+                        .normalizer(normalizer)
+                        .build()?,
+                )),
+                AlgorithmParameters::BlockHash { block_size, hash_length } => Self::BlockHash(
+                    BlockHashDetection::builder()
+                        .block_size(block_size)
+                        .hash_length(hash_length)
+                        .normalizer(normalizer)
+                        .build()?,
+                ),
+            };
+            detector.try_load_from_memory(raw)?;
+            Ok(detector)
+        }
+
+        pub(crate) fn try_load_from_memory(&mut self, raw: &[u8]) -> Result<(), DetectionError> {
+            match self {
+                Self::Fuzzy(fuzzy) => fuzzy.try_load_from_memory(raw),
+                Self::Gaoya(gaoya) => gaoya.try_load_from_memory(raw),
+                Self::BlockHash(block_hash) => block_hash.try_load_from_memory(raw),
+            }
+        }
+
+        pub(crate) fn try_save_to_memory(&self) -> Result<Vec<u8>, DetectionError> {
+            match self {
+                Self::Fuzzy(fuzzy) => fuzzy.try_save_to_memory(),
+                Self::Gaoya(gaoya) => gaoya.try_save_to_memory(),
+                Self::BlockHash(block_hash) => block_hash.try_save_to_memory(),
+            }
+        }
+
+        pub(crate) fn match_by_plain_text(&self, text: &str) -> Vec<LicenseMatch> {
+            match self {
+                Self::Fuzzy(fuzzy) => fuzzy.match_by_plain_text(text),
+                Self::Gaoya(gaoya) => gaoya.match_by_plain_text(text),
+                Self::BlockHash(block_hash) => block_hash.match_by_plain_text(text),
+            }
+        }
+
+        #[cfg(feature = "wasm")]
+        pub(crate) fn run_pipeline(&self, pipeline: &Pipeline, text: &str, desired_confidence: f32) -> Vec<Vec<LicenseMatch>> {
+            match self {
+                Self::Fuzzy(fuzzy) => pipeline.run(fuzzy, text, desired_confidence),
+                Self::Gaoya(gaoya) => pipeline.run(gaoya.as_ref(), text, desired_confidence),
+                Self::BlockHash(block_hash) => pipeline.run(block_hash, text, desired_confidence),
+            }
+        }
+
+        pub(crate) fn add_plain(&mut self, name: &str, text: &str) {
+            match self {
+                Self::Fuzzy(fuzzy) => fuzzy.add_plain(name, text),
+                Self::Gaoya(gaoya) => gaoya.add_plain(name, text),
+                Self::BlockHash(block_hash) => block_hash.add_plain(name, text),
+            }
+        }
+
+        pub(crate) fn remove(&mut self, name: &str) {
+            match self {
+                Self::Fuzzy(fuzzy) => fuzzy.remove(name),
+                Self::Gaoya(gaoya) => gaoya.remove(name),
+                Self::BlockHash(block_hash) => block_hash.remove(name),
+            }
+        }
+
+        /// Sets the minimum confidence, rejecting values above 100 like the builders do.
+        pub(crate) fn set_min_confidence(&mut self, min_confidence: u8) -> Result<(), DetectionError> {
+            if min_confidence > 100 {
+                return Err(DetectionError::InvalidParameter(format!(
+                    "min_confidence must be between 0 and 100, got {}",
+                    min_confidence
+                )));
+            }
+            match self {
+                Self::Fuzzy(fuzzy) => fuzzy.min_confidence = min_confidence,
+                Self::Gaoya(gaoya) => gaoya.min_confidence = min_confidence,
+                Self::BlockHash(block_hash) => block_hash.min_confidence = min_confidence,
+            }
+            Ok(())
+        }
+
+        #[cfg(feature = "wasm")]
+        pub(crate) fn license_names(&self) -> Vec<String> {
+            let mut names: Vec<String> = match self {
+                Self::Fuzzy(fuzzy) => fuzzy.get_license_list().into_iter().map(|(name, _)| name).collect(),
+                Self::Gaoya(gaoya) => gaoya.get_license_list().into_iter().map(|(name, _)| name).collect(),
+                Self::BlockHash(block_hash) => block_hash.get_license_list().into_iter().map(|(name, _)| name).collect(),
+            };
+            names.sort_unstable();
+            names
+        }
+
+        /// Removes every license, the algorithm and its parameters are kept.
+        #[cfg(feature = "wasm")]
+        pub(crate) fn clear(&mut self) {
+            for name in self.license_names() {
+                self.remove(&name);
+            }
+        }
+    }
+}
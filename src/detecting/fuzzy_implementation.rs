@@ -16,25 +16,148 @@
 */
 
 pub mod fuzzy_implementation {
-    use std::{fs::File, io::Read};
+    use std::sync::Arc;
 
     use fuzzyhash::FuzzyHash;
 
     use crate::{
-        detecting::detecting::{DiskData, LicenseEntry},
-        LicenseListActions, LicenseMatch,
+        detecting::detecting::{sort_matches, LicenseEntry},
+        normalized_digest, AlgorithmParameters, DigestIndex, DetectionError, LicenseListActions, LicenseMatch, LicenseMetadata, Normalizer,
+        DEFAULT_NORMALIZER,
     };
 
     pub struct FuzzyDetection {
         pub licenses: Vec<LicenseEntry<String>>,
         pub min_confidence: u8,
         pub exit_on_exact_match: bool,
+        /// Whether [add_plain](LicenseListActions::add_plain) keeps the license text, so it is saved in the database and can be diffed against.
+        pub store_reference_texts: bool,
+        /// The digests of the normalized license texts, kept up to date by [add_plain](LicenseListActions::add_plain),
+        /// [remove](LicenseListActions::remove) and loading a database.
+        pub digests: DigestIndex,
 
-        pub normalization_fn: fn(&str) -> String,
+        /// Normalizes license texts before they are hashed, see [Normalizer].
+        pub normalizer: Arc<dyn Normalizer>,
     }
+
+    impl FuzzyDetection {
+        /// Returns a builder to configure a new, empty, [FuzzyDetection].
+        ///
+        /// ```
+        /// use whichlicense_detection::detecting::fuzzy_implementation::fuzzy_implementation::FuzzyDetection;
+        ///
+        /// let fuzzy = FuzzyDetection::builder().min_confidence(80).build().unwrap();
+        /// assert_eq!(fuzzy.min_confidence, 80);
+        /// ```
+        pub fn builder() -> FuzzyDetectionBuilder {
+            FuzzyDetectionBuilder::default()
+        }
+    }
+
+    #[cfg(feature = "embedded-database")]
+    impl FuzzyDetection {
+        /// Creates a [FuzzyDetection] with default parameters, loaded with the database embedded in the library.
+        ///
+        /// Requires the `embedded-database` feature.
+        pub fn with_default_database() -> Self {
+            let mut fuzzy = Self::builder()
+                .normalizer(crate::BuiltinSegment::StripLicense)
+                .build()
+                .unwrap();
+            fuzzy
+                .try_load_from_memory(crate::EMBEDDED_FUZZY_DATABASE)
+                .expect("the embedded fuzzy database is valid");
+            fuzzy
+        }
+    }
+
+    impl Default for FuzzyDetection {
+        fn default() -> Self {
+            Self::builder().build().unwrap()
+        }
+    }
+
+    /// Builder for [FuzzyDetection], see [FuzzyDetection::builder].
+    #[derive(Debug, Clone)]
+    pub struct FuzzyDetectionBuilder {
+        min_confidence: u8,
+        exit_on_exact_match: bool,
+        store_reference_texts: bool,
+        normalizer: Arc<dyn Normalizer>,
+    }
+
+    impl Default for FuzzyDetectionBuilder {
+        fn default() -> Self {
+            Self {
+                min_confidence: 50,
+                exit_on_exact_match: false,
+                store_reference_texts: false,
+                normalizer: Arc::new(DEFAULT_NORMALIZER),
+            }
+        }
+    }
+
+    impl FuzzyDetectionBuilder {
+        /// Matches below this confidence (0-100) are discarded. Defaults to 50.
+        pub fn min_confidence(mut self, min_confidence: u8) -> Self {
+            self.min_confidence = min_confidence;
+            self
+        }
+
+        /// Stops comparing as soon as a license with a confidence of 100 is found. Defaults to false.
+        pub fn exit_on_exact_match(mut self, exit_on_exact_match: bool) -> Self {
+            self.exit_on_exact_match = exit_on_exact_match;
+            self
+        }
+
+        /// Keep the text of added licenses so it is saved in the database and can be diffed against. Defaults to false.
+        pub fn store_reference_texts(mut self, store_reference_texts: bool) -> Self {
+            self.store_reference_texts = store_reference_texts;
+            self
+        }
+
+        /// The normalization function, defaults to [DEFAULT_NORMALIZER].
+        ///
+        /// Functions, the built-in ones included, are all recorded as `"custom"`, so databases built with different
+        /// functions load into each other without an error. Pass a [BuiltinSegment](crate::BuiltinSegment) or a
+        /// [FnNormalizer](crate::FnNormalizer) to [normalizer](Self::normalizer) to record its own id.
+        pub fn normalization_fn(self, func: fn(&str) -> String) -> Self {
+            self.normalizer(func)
+        }
+
+        /// The normalizer, for normalization that is configured at runtime or chains several steps, see [Normalizer].
+        pub fn normalizer(mut self, normalizer: impl Normalizer + 'static) -> Self {
+            self.normalizer = Arc::new(normalizer);
+            self
+        }
+
+        pub fn build(self) -> Result<FuzzyDetection, DetectionError> {
+            if self.min_confidence > 100 {
+                return Err(DetectionError::InvalidParameter(format!(
+                    "min_confidence must be between 0 and 100, got {}",
+                    self.min_confidence
+                )));
+            }
+
+            Ok(FuzzyDetection {
+                licenses: vec![],
+                min_confidence: self.min_confidence,
+                exit_on_exact_match: self.exit_on_exact_match,
+                store_reference_texts: self.store_reference_texts,
+                digests: DigestIndex::default(),
+                normalizer: self.normalizer,
+            })
+        }
+    }
+
     impl LicenseListActions<String> for FuzzyDetection {
         fn match_by_plain_text(&self, plain_text: &str) -> Vec<LicenseMatch> {
-            self.match_by_hash(FuzzyHash::new((self.normalization_fn)(plain_text)).to_string())
+            let normalized = self.normalizer.normalize(plain_text);
+            let exact = self.match_exact(&normalized);
+            if !exact.is_empty() {
+                return exact;
+            }
+            self.match_by_hash(FuzzyHash::new(normalized).to_string())
         }
 
         fn match_by_hash(&self, hash: String) -> Vec<LicenseMatch> {
@@ -49,13 +172,14 @@ pub mod fuzzy_implementation {
                     matches.push(LicenseMatch {
                         name: license.name.to_string(),
                         confidence: res as f32,
+                        metadata: license.metadata.clone(),
                     });
                     if self.exit_on_exact_match && res == 100 {
                         break;
                     }
                 }
             }
-            matches.sort_unstable_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap());
+            sort_matches(&mut matches);
             matches
         }
 
@@ -66,40 +190,75 @@ pub mod fuzzy_implementation {
                 .collect()
         }
 
-        fn load_from_memory(&mut self, raw: &Vec<u8>) {
-            let loaded: DiskData<String> = bincode::deserialize(&raw).unwrap_or(DiskData {
-                licenses: Vec::new(),
-            });
-            self.licenses.extend(loaded.licenses);
+        fn get_reference_text(&self, license_name: &str) -> Option<&str> {
+            self.licenses
+                .iter()
+                .find(|l| l.name == license_name)
+                .and_then(|l| l.text.as_deref())
         }
 
-        fn load_from_file(&mut self, file_path: &str) {
-            let mut file = File::open(file_path).unwrap();
-            let mut contents = Vec::new();
-            file.read_to_end(&mut contents).unwrap();
+        fn get_metadata(&self, license_name: &str) -> Option<&LicenseMetadata> {
+            self.licenses
+                .iter()
+                .find(|l| l.name == license_name)
+                .and_then(|l| l.metadata.as_ref())
+        }
 
-            self.load_from_memory(&contents);
+        /// Has no effect if the license has not been added.
+        fn set_metadata(&mut self, license_name: &str, metadata: LicenseMetadata) {
+            if let Some(l) = self.licenses.iter_mut().find(|l| l.name == license_name) {
+                l.metadata = Some(metadata);
+            }
+        }
+
+        fn get_parameters(&self) -> AlgorithmParameters {
+            AlgorithmParameters::Fuzzy
+        }
+
+        fn get_normalizer(&self) -> &dyn Normalizer {
+            self.normalizer.as_ref()
+        }
+
+        fn get_digest_index(&self) -> &DigestIndex {
+            &self.digests
+        }
+
+        fn try_load_from_memory(&mut self, raw: &[u8]) -> Result<(), DetectionError> {
+            let loaded = self.try_decode_database(raw)?;
+            for l in loaded.licenses.iter() {
+                if let Some(digest) = l.digest {
+                    self.digests.insert(&l.name, digest);
+                }
+            }
+            self.licenses.extend(loaded.licenses);
+            Ok(())
         }
 
         fn add_plain(&mut self, license_name: &str, license_text: &str) {
-            let stripped = (self.normalization_fn)(license_text);
+            let stripped = self.normalizer.normalize(license_text);
+            let digest = normalized_digest(&stripped);
             let fuzzy = FuzzyHash::new(stripped);
+            self.digests.insert(license_name, digest);
             self.licenses.push(LicenseEntry {
                 name: license_name.to_string(),
                 hash: fuzzy.to_string(),
+                text: self.store_reference_texts.then(|| license_text.to_string()),
+                metadata: None,
+                digest: Some(digest),
             });
         }
 
         fn hash_from_inline_string(&self, license_text: &str) -> String {
-            FuzzyHash::new((self.normalization_fn)(license_text)).to_string()
+            FuzzyHash::new(self.normalizer.normalize(license_text)).to_string()
         }
 
         fn remove(&mut self, license_name: &str) {
-            self.licenses.retain(|l| l.name != license_name.to_string());
+            self.licenses.retain(|l| l.name != license_name);
+            self.digests.remove(license_name);
         }
 
-        fn set_normalization_fn(&mut self, func: fn(&str) -> String) {
-            self.normalization_fn = func;
+        fn set_normalizer(&mut self, normalizer: Arc<dyn Normalizer>) {
+            self.normalizer = normalizer;
         }
     }
 }
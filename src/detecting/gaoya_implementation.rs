@@ -16,14 +16,17 @@
 */
 
 pub mod gaoya_implementation {
-    use std::{fs::File, hash::BuildHasherDefault, io::Read};
+    use std::{collections::HashMap, hash::BuildHasherDefault, sync::Arc};
 
     use gaoya::{
         minhash::{MinHashIndex, MinHasher, MinHasher32},
         text::shingle_text,
     };
 
-    use crate::{detecting::detecting::DiskData, LicenseListActions, LicenseMatch};
+    use crate::{
+        normalized_digest, sort_matches, AlgorithmParameters, DetectionError, DigestIndex, LicenseListActions,
+        LicenseMatch, LicenseMetadata, Normalizer, Shingling, DEFAULT_NORMALIZER,
+    };
 
     // #[derive(Serialize, Deserialize, Debug, Clone)]
     // /// How the contents of the JSON db looks like, used for parsing purposes.
@@ -35,26 +38,228 @@ pub mod gaoya_implementation {
     pub struct GaoyaDetection {
         pub index: MinHashIndex<u32, String>,
         pub min_hasher: MinHasher32<BuildHasherDefault<fnv::FnvHasher>>,
+        /// The number of characters or words, see [shingling](GaoyaDetection::shingling), in a shingle.
         pub shingle_text_size: usize,
+        /// Whether the normalized text is shingled by characters or by words.
+        pub shingling: Shingling,
+        /// Matches below this confidence (0-100) are discarded, even if the index considers them similar.
+        pub min_confidence: u8,
+        /// The number of bands the index was created with, must match the `num_bands` passed to [MinHashIndex::new].
+        pub num_bands: usize,
+        /// The width of each band the index was created with, must match the `band_width` passed to [MinHashIndex::new].
+        pub band_width: usize,
+        /// Whether [add_plain](LicenseListActions::add_plain) keeps the license text, so it is saved in the database and can be diffed against.
+        pub store_reference_texts: bool,
+        /// The reference texts by license name, see [store_reference_texts](GaoyaDetection::store_reference_texts).
+        pub reference_texts: HashMap<String, String>,
+        /// The metadata by license name, see [set_metadata](LicenseListActions::set_metadata).
+        pub metadata: HashMap<String, LicenseMetadata>,
+        /// The digests of the normalized license texts, kept up to date by [add_plain](LicenseListActions::add_plain),
+        /// [remove](LicenseListActions::remove) and loading a database.
+        pub digests: DigestIndex,
+
+        /// Normalizes license texts before they are hashed, see [Normalizer].
+        pub normalizer: Arc<dyn Normalizer>,
+    }
+
+    impl GaoyaDetection {
+        /// Returns a builder to configure a new, empty, [GaoyaDetection].
+        ///
+        /// The builder creates the MinHash index and hasher from the given parameters so that they always agree with each other.
+        /// ```
+        /// use whichlicense_detection::detecting::gaoya_implementation::gaoya_implementation::GaoyaDetection;
+        ///
+        /// let gaoya = GaoyaDetection::builder()
+        ///     .bands(42)
+        ///     .band_width(3)
+        ///     .threshold(0.5)
+        ///     .shingle_size(50)
+        ///     .build()
+        ///     .unwrap();
+        /// assert_eq!(gaoya.num_bands, 42);
+        /// ```
+        pub fn builder() -> GaoyaDetectionBuilder {
+            GaoyaDetectionBuilder::default()
+        }
+
+        fn signature(&self, normalized_text: &str) -> Vec<u32> {
+            match self.shingling {
+                Shingling::Characters => self
+                    .min_hasher
+                    .create_signature(shingle_text(normalized_text, self.shingle_text_size)),
+                Shingling::Words => {
+                    let words: Vec<&str> = normalized_text.split_whitespace().collect();
+                    // texts shorter than a single shingle are hashed as a whole instead of not at all.
+                    let size = self.shingle_text_size.min(words.len()).max(1);
+                    self.min_hasher.create_signature(words.windows(size))
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "embedded-database")]
+    impl GaoyaDetection {
+        /// Creates a [GaoyaDetection] with the parameters of, and loaded with, the database embedded in the library.
+        ///
+        /// Requires the `embedded-database` feature.
+        pub fn with_default_database() -> Self {
+            let mut gaoya = Self::builder()
+                .bands(42)
+                .band_width(3)
+                .threshold(0.5)
+                .shingle_size(50)
+                .normalizer(crate::BuiltinSegment::StripLicense)
+                .build()
+                .unwrap();
+            gaoya
+                .try_load_from_memory(crate::EMBEDDED_GAOYA_DATABASE)
+                .expect("the embedded gaoya database is valid");
+            gaoya
+        }
+    }
+
+    impl Default for GaoyaDetection {
+        fn default() -> Self {
+            Self::builder().build().unwrap()
+        }
+    }
+
+    /// Builder for [GaoyaDetection], see [GaoyaDetection::builder].
+    #[derive(Debug, Clone)]
+    pub struct GaoyaDetectionBuilder {
+        num_bands: usize,
+        band_width: usize,
+        threshold: f64,
+        shingle_text_size: usize,
+        shingling: Shingling,
+        min_confidence: u8,
+        store_reference_texts: bool,
+        normalizer: Arc<dyn Normalizer>,
+    }
+
+    impl Default for GaoyaDetectionBuilder {
+        fn default() -> Self {
+            Self {
+                num_bands: 42,
+                band_width: 3,
+                threshold: 0.5,
+                shingle_text_size: 50,
+                shingling: Shingling::Characters,
+                min_confidence: 50,
+                store_reference_texts: false,
+                normalizer: Arc::new(DEFAULT_NORMALIZER),
+            }
+        }
+    }
+
+    impl GaoyaDetectionBuilder {
+        /// The number of bands of the MinHash index. Defaults to 42.
+        pub fn bands(mut self, num_bands: usize) -> Self {
+            self.num_bands = num_bands;
+            self
+        }
+
+        /// The number of hashes in each band. Defaults to 3.
+        pub fn band_width(mut self, band_width: usize) -> Self {
+            self.band_width = band_width;
+            self
+        }
+
+        /// The Jaccard similarity (0-1] a license needs to be returned by the index. Defaults to 0.5.
+        pub fn threshold(mut self, threshold: f64) -> Self {
+            self.threshold = threshold;
+            self
+        }
+
+        /// The number of characters or words in the shingles the normalized text is split into. Defaults to 50.
+        pub fn shingle_size(mut self, shingle_text_size: usize) -> Self {
+            self.shingle_text_size = shingle_text_size;
+            self
+        }
+
+        /// Whether to shingle characters or words. Defaults to [Shingling::Characters].
+        ///
+        /// Word shingles need a normalization function that keeps words apart, such as [normalize_tokens](crate::normalize_tokens).
+        pub fn shingling(mut self, shingling: Shingling) -> Self {
+            self.shingling = shingling;
+            self
+        }
+
+        /// Matches below this confidence (0-100) are discarded. Defaults to 50.
+        pub fn min_confidence(mut self, min_confidence: u8) -> Self {
+            self.min_confidence = min_confidence;
+            self
+        }
+
+        /// Keep the text of added licenses so it is saved in the database and can be diffed against. Defaults to false.
+        pub fn store_reference_texts(mut self, store_reference_texts: bool) -> Self {
+            self.store_reference_texts = store_reference_texts;
+            self
+        }
+
+        /// The normalization function, defaults to [DEFAULT_NORMALIZER].
+        ///
+        /// Functions, the built-in ones included, are all recorded as `"custom"`, so databases built with different
+        /// functions load into each other without an error. Pass a [BuiltinSegment](crate::BuiltinSegment) or a
+        /// [FnNormalizer](crate::FnNormalizer) to [normalizer](Self::normalizer) to record its own id.
+        pub fn normalization_fn(self, func: fn(&str) -> String) -> Self {
+            self.normalizer(func)
+        }
+
+        /// The normalizer, for normalization that is configured at runtime or chains several steps, see [Normalizer].
+        pub fn normalizer(mut self, normalizer: impl Normalizer + 'static) -> Self {
+            self.normalizer = Arc::new(normalizer);
+            self
+        }
+
+        pub fn build(self) -> Result<GaoyaDetection, DetectionError> {
+            if self.num_bands == 0 {
+                return Err(DetectionError::InvalidParameter("bands must be at least 1".to_string()));
+            }
+            if self.band_width == 0 {
+                return Err(DetectionError::InvalidParameter("band_width must be at least 1".to_string()));
+            }
+            if !(self.threshold > 0.0 && self.threshold <= 1.0) {
+                return Err(DetectionError::InvalidParameter(format!(
+                    "threshold must be greater than 0 and at most 1, got {}",
+                    self.threshold
+                )));
+            }
+            if self.shingle_text_size == 0 {
+                return Err(DetectionError::InvalidParameter("shingle_size must be at least 1".to_string()));
+            }
+            if self.min_confidence > 100 {
+                return Err(DetectionError::InvalidParameter(format!(
+                    "min_confidence must be between 0 and 100, got {}",
+                    self.min_confidence
+                )));
+            }
 
-        pub normalization_fn: fn(&str) -> String,
+            Ok(GaoyaDetection {
+                index: MinHashIndex::new(self.num_bands, self.band_width, self.threshold),
+                min_hasher: MinHasher32::new(self.num_bands * self.band_width),
+                shingle_text_size: self.shingle_text_size,
+                shingling: self.shingling,
+                min_confidence: self.min_confidence,
+                num_bands: self.num_bands,
+                band_width: self.band_width,
+                store_reference_texts: self.store_reference_texts,
+                reference_texts: HashMap::new(),
+                metadata: HashMap::new(),
+                digests: DigestIndex::default(),
+                normalizer: self.normalizer,
+            })
+        }
     }
+
     impl LicenseListActions<Vec<u32>> for GaoyaDetection {
         fn match_by_plain_text(&self, plain_text: &str) -> Vec<LicenseMatch> {
-            let signature = self.min_hasher.create_signature(shingle_text(
-                &(self.normalization_fn)(plain_text),
-                self.shingle_text_size,
-            ));
-            let res = self.index.query_owned_return_similarity(&signature);
-            let mut matches: Vec<LicenseMatch> = Vec::new();
-            // TODO: simd? maybe it already does it?
-            for (name, conf) in res {
-                matches.push(LicenseMatch {
-                    name,
-                    confidence: conf as f32 * 100.0,
-                });
+            let normalized = self.normalizer.normalize(plain_text);
+            let exact = self.match_exact(&normalized);
+            if !exact.is_empty() {
+                return exact;
             }
-            matches
+            self.match_by_hash(self.signature(&normalized))
         }
 
         fn match_by_hash(&self, hash: Vec<u32>) -> Vec<LicenseMatch> {
@@ -62,11 +267,13 @@ pub mod gaoya_implementation {
             let mut matches: Vec<LicenseMatch> = Vec::new();
             // TODO: simd? maybe it already does it?
             for (name, conf) in res {
-                matches.push(LicenseMatch {
-                    name,
-                    confidence: conf as f32 * 100.0,
-                });
+                let confidence = conf as f32 * 100.0;
+                if confidence >= self.min_confidence as f32 {
+                    let metadata = self.metadata.get(&name).cloned();
+                    matches.push(LicenseMatch { name, confidence, metadata });
+                }
             }
+            sort_matches(&mut matches);
             matches
         }
 
@@ -78,44 +285,88 @@ pub mod gaoya_implementation {
                 .collect()
         }
 
-        fn load_from_file(&mut self, file_path: &str) {
-            let mut file = File::open(file_path).unwrap();
-            let mut contents = Vec::new();
-            file.read_to_end(&mut contents).unwrap();
+        fn get_reference_text(&self, license_name: &str) -> Option<&str> {
+            self.reference_texts.get(license_name).map(String::as_str)
+        }
+
+        fn get_metadata(&self, license_name: &str) -> Option<&LicenseMetadata> {
+            self.metadata.get(license_name)
+        }
+
+        /// Has no effect if the license has not been added.
+        fn set_metadata(&mut self, license_name: &str, metadata: LicenseMetadata) {
+            if self.index.get_id_signature_map().contains_key(license_name) {
+                self.metadata.insert(license_name.to_string(), metadata);
+            }
+        }
+
+        fn get_parameters(&self) -> AlgorithmParameters {
+            AlgorithmParameters::Gaoya {
+                num_bands: self.num_bands,
+                band_width: self.band_width,
+                shingle_text_size: self.shingle_text_size,
+                shingling: self.shingling,
+            }
+        }
+
+        fn get_normalizer(&self) -> &dyn Normalizer {
+            self.normalizer.as_ref()
+        }
 
-            self.load_from_memory(&contents);
+        fn get_digest_index(&self) -> &DigestIndex {
+            &self.digests
         }
 
-        fn load_from_memory(&mut self, raw: &Vec<u8>) {
-            let decoded: DiskData<Vec<u32>> = bincode::deserialize(&raw[..]).unwrap_or(DiskData {
-                licenses: Vec::new(),
-            });
+        fn try_load_from_memory(&mut self, raw: &[u8]) -> Result<(), DetectionError> {
+            let decoded = self.try_decode_database(raw)?;
+            let num_hashes = self.min_hasher.num_hashes();
+            // the index asserts on the signature length, check it up front so a mismatched database can not panic.
+            if let Some(l) = decoded.licenses.iter().find(|l| l.hash.len() != num_hashes) {
+                return Err(DetectionError::IncompatibleFormat(format!(
+                    "signature of '{}' has {} hashes but the index expects {}",
+                    l.name,
+                    l.hash.len(),
+                    num_hashes
+                )));
+            }
             for l in decoded.licenses {
+                if let Some(text) = l.text {
+                    self.reference_texts.insert(l.name.clone(), text);
+                }
+                if let Some(metadata) = l.metadata {
+                    self.metadata.insert(l.name.clone(), metadata);
+                }
+                if let Some(digest) = l.digest {
+                    self.digests.insert(&l.name, digest);
+                }
                 self.index.insert(l.name, l.hash);
             }
+            Ok(())
         }
 
         fn add_plain(&mut self, license_name: &str, license_text: &str) {
-            let signature = self.min_hasher.create_signature(shingle_text(
-                &(self.normalization_fn)(license_text),
-                self.shingle_text_size,
-            ));
+            let normalized = self.normalizer.normalize(license_text);
+            let signature = self.signature(&normalized);
             self.index.insert(license_name.to_string(), signature);
+            self.digests.insert(license_name, normalized_digest(&normalized));
+            if self.store_reference_texts {
+                self.reference_texts.insert(license_name.to_string(), license_text.to_string());
+            }
         }
 
         fn hash_from_inline_string(&self, license_text: &str) -> Vec<u32> {
-            self.min_hasher.create_signature(shingle_text(
-                &(self.normalization_fn)(license_text),
-                self.shingle_text_size,
-            ))
+            self.signature(&self.normalizer.normalize(license_text))
         }
 
         fn remove(&mut self, license_name: &str) {
             self.index.remove(&license_name.to_string());
+            self.reference_texts.remove(license_name);
+            self.metadata.remove(license_name);
+            self.digests.remove(license_name);
         }
 
-        fn set_normalization_fn(&mut self, func: fn(&str) -> String) {
-            self.normalization_fn = func;
+        fn set_normalizer(&mut self, normalizer: Arc<dyn Normalizer>) {
+            self.normalizer = normalizer;
         }
     }
 }
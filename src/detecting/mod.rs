@@ -15,25 +15,64 @@
 *   limitations under the License.
 */
 
+pub mod block_hash_implementation;
+pub mod database;
+#[cfg(any(feature = "ffi", feature = "wasm"))]
+pub(crate) mod dynamic;
 pub mod fuzzy_implementation;
 pub mod gaoya_implementation;
 
 pub mod detecting {
-    use std::{fs::File, io::Write};
+    use std::{collections::HashMap, ops::Range, sync::Arc};
+    #[cfg(not(target_arch = "wasm32"))]
+    use std::{
+        fs::{self, File},
+        io::{ErrorKind, Write},
+    };
 
-    use serde::{Serialize, Deserialize};
-    use crate::strip_license;
+    use serde::{de::DeserializeOwned, Serialize, Deserialize};
+    use sha2::{Digest, Sha256};
+    use crate::{
+        decode_database, diff_normalized, diff_texts, encode_database, strip_license, AlgorithmParameters, BuiltinSegment, DatabaseHeader,
+        DetectionError,
+        LicenseDiff, LicenseMetadata, NormalizedText, Normalizer, RawLicense,
+    };
 
-    #[derive(Debug, Clone)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct LicenseMatch {
         pub name: String,
         pub confidence: f32,
+        /// The metadata of the matched license, if the database contains it.
+        pub metadata: Option<LicenseMetadata>,
+    }
+
+    /// The matches of a text together with the information needed to map them back onto that text,
+    /// see [match_by_plain_text_detailed](LicenseListActions::match_by_plain_text_detailed).
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct DetailedMatches {
+        pub matches: Vec<LicenseMatch>,
+        /// The normalized text the matches were computed from, with its offsets into the original text.
+        pub normalized: NormalizedText,
+        /// The byte range of the original text that matched the reference text of the top match, `None` if nothing did.
+        ///
+        /// Without a reference text this is the range that contributed to the normalized text.
+        pub matched_range: Option<Range<usize>>,
+        /// The byte ranges of the original text deviating from the reference text of the top match, see [NormalizedDiff](crate::NormalizedDiff).
+        ///
+        /// Always empty when the reference text of the top match is not available.
+        pub deviations: Vec<Range<usize>>,
     }
 
     #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct LicenseEntry<HT> {
         pub name: String,
         pub hash: HT,
+        /// The reference text of the license, only stored when the database was built with reference texts.
+        pub text: Option<String>,
+        /// The metadata of the license, only stored when the license was added with its metadata.
+        pub metadata: Option<LicenseMetadata>,
+        /// The [normalized_digest] of the license text, `None` for databases built without the license texts.
+        pub digest: Option<[u8; 32]>,
     }
     #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct DiskData<K> {
@@ -41,47 +80,263 @@ pub mod detecting {
     }
 
     pub static DEFAULT_NORMALIZATION_FN: fn(&str) -> String = strip_license;
+
+    /// The normalizer of the detectors unless configured otherwise, [DEFAULT_NORMALIZATION_FN] recorded as `"strip_license"`.
+    pub const DEFAULT_NORMALIZER: BuiltinSegment = BuiltinSegment::StripLicense;
+
+    /// The SHA-256 digest of an already normalized text, two texts with the same digest are an exact match.
+    pub fn normalized_digest(normalized_text: &str) -> [u8; 32] {
+        Sha256::digest(normalized_text.as_bytes()).into()
+    }
+
+    /// Finds the licenses whose normalized text is identical to the input without comparing any hashes,
+    /// see [match_exact](LicenseListActions::match_exact).
+    #[derive(Debug, Clone, Default)]
+    pub struct DigestIndex {
+        by_digest: HashMap<[u8; 32], Vec<String>>,
+        by_name: HashMap<String, [u8; 32]>,
+    }
+
+    impl DigestIndex {
+        /// Records the digest of a license, replacing the digest it had before.
+        pub fn insert(&mut self, license_name: &str, digest: [u8; 32]) {
+            self.remove(license_name);
+            self.by_digest.entry(digest).or_default().push(license_name.to_string());
+            self.by_name.insert(license_name.to_string(), digest);
+        }
+
+        pub fn remove(&mut self, license_name: &str) {
+            let Some(digest) = self.by_name.remove(license_name) else {
+                return;
+            };
+            if let Some(names) = self.by_digest.get_mut(&digest) {
+                names.retain(|name| name != license_name);
+                if names.is_empty() {
+                    self.by_digest.remove(&digest);
+                }
+            }
+        }
+
+        /// Returns the digest of a license, if it is known.
+        pub fn get(&self, license_name: &str) -> Option<[u8; 32]> {
+            self.by_name.get(license_name).copied()
+        }
+
+        /// Returns the names of the licenses with the given digest, licenses can share a text.
+        pub fn lookup(&self, digest: &[u8; 32]) -> &[String] {
+            self.by_digest.get(digest).map_or(&[], Vec::as_slice)
+        }
+
+        /// The number of licenses with a known digest.
+        pub fn len(&self) -> usize {
+            self.by_name.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.by_name.is_empty()
+        }
+    }
+
+    /// Sorts matches by descending confidence, matches with an equal confidence are sorted by name.
+    ///
+    /// This is the ordering every [LicenseListActions] implementation returns its matches in.
+    pub fn sort_matches(matches: &mut [LicenseMatch]) {
+        matches.sort_unstable_by(|a, b| {
+            b.confidence
+                .total_cmp(&a.confidence)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+    }
+
+    /// Actions shared by every detection algorithm.
+    ///
+    /// # Ordering
+    /// The matches returned by [match_by_plain_text](LicenseListActions::match_by_plain_text) and
+    /// [match_by_hash](LicenseListActions::match_by_hash) only contain licenses whose confidence is at or above the
+    /// minimum confidence of the algorithm and are sorted as done by [sort_matches], so the first element is always the top match.
     pub trait LicenseListActions<T: Serialize> {
         /// Converts the plain text into a representation that can be used to find a license
         /// then runs the match_by_hash function on that representation.
         fn match_by_plain_text(&self, plain_text: &str) -> Vec<LicenseMatch>;
 
+        /// Same as [match_by_plain_text](LicenseListActions::match_by_plain_text) but also returns the normalized text and its offset map,
+        /// so that regions of the normalized text (e.g. the parts deviating from a license) can be mapped back onto the original text.
+        ///
+        /// When the reference text of the top match is available the normalized text is also compared with it, which
+        /// gives the region of the original text that matched and the regions deviating from the license.
+        fn match_by_plain_text_detailed(&self, plain_text: &str) -> DetailedMatches {
+            let normalizer = self.get_normalizer();
+            let normalized = normalizer.normalize_with_offsets(plain_text);
+            let matches = self.match_by_plain_text(plain_text);
+            let reference = matches.first().and_then(|m| self.get_reference_text(&m.name));
+            let (matched_range, deviations) = match reference {
+                Some(reference) => {
+                    let diff = diff_normalized(reference, &normalizer.normalize_with_offsets(reference), plain_text, &normalized);
+                    (diff.matched_range, diff.deviations)
+                }
+                None => (normalized.original_range(0..normalized.offsets.len()), vec![]),
+            };
+            DetailedMatches {
+                matches,
+                matched_range,
+                deviations,
+                normalized,
+            }
+        }
+
         /// Attempts to find one or more matching licenses by hash.
         fn match_by_hash(&self, hash: T) -> Vec<LicenseMatch>;
 
+        /// Returns a match with a confidence of 100 for every license whose normalized text is identical to the given
+        /// (already normalized) text, in O(1) and without comparing any hashes.
+        ///
+        /// [match_by_plain_text](LicenseListActions::match_by_plain_text) returns these matches, and nothing else,
+        /// before doing any similarity work. Licenses loaded from a database without digests are never matched exactly.
+        fn match_exact(&self, normalized_text: &str) -> Vec<LicenseMatch> {
+            let index = self.get_digest_index();
+            if index.is_empty() {
+                return vec![];
+            }
+            let mut matches: Vec<LicenseMatch> = index
+                .lookup(&normalized_digest(normalized_text))
+                .iter()
+                .map(|name| LicenseMatch {
+                    name: name.clone(),
+                    confidence: 100.0,
+                    metadata: self.get_metadata(name).cloned(),
+                })
+                .collect();
+            sort_matches(&mut matches);
+            matches
+        }
+
+        /// Returns the digests of the normalized license texts, see [match_exact](LicenseListActions::match_exact).
+        fn get_digest_index(&self) -> &DigestIndex;
+
         fn get_license_list(&self) -> Vec<(String, T)>;
 
+        /// Returns the reference text of a license, if it was stored.
+        ///
+        /// Reference texts are only kept when the algorithm was built with `store_reference_texts` enabled,
+        /// or loaded from a database that contains them.
+        fn get_reference_text(&self, license_name: &str) -> Option<&str>;
+
+        /// Returns the metadata of a license, if it was stored.
+        fn get_metadata(&self, license_name: &str) -> Option<&LicenseMetadata>;
+
+        /// Attaches metadata to a license, it is saved in the database and returned with every match of the license.
+        fn set_metadata(&mut self, license_name: &str, metadata: LicenseMetadata);
+
+        /// Adds a license loaded by [load_licenses_from_folder](crate::load_licenses_from_folder) together with its metadata.
+        ///
+        /// The YAML heading is not part of the hashed text.
+        fn add_raw_license(&mut self, license: &RawLicense) {
+            self.add_plain(&license.name, license.body());
+            if let Some(metadata) = &license.metadata {
+                self.set_metadata(&license.name, metadata.clone());
+            }
+        }
+
+        /// Computes a word-level diff between the reference text of a matched license and the input it was matched against.
+        ///
+        /// Returns [DetectionError::MissingReferenceText] if the reference text of the license is not available.
+        fn diff_match(&self, license_match: &LicenseMatch, input: &str) -> Result<LicenseDiff, DetectionError> {
+            let reference = self
+                .get_reference_text(&license_match.name)
+                .ok_or_else(|| DetectionError::MissingReferenceText(license_match.name.clone()))?;
+            Ok(diff_texts(&license_match.name, reference, input))
+        }
+
+        /// Returns the parameters the hashes of this algorithm are computed with.
+        fn get_parameters(&self) -> AlgorithmParameters;
+
+        /// Returns the normalizer currently in use.
+        fn get_normalizer(&self) -> &dyn Normalizer;
+
+        /// Returns the header a database saved by this algorithm would carry.
+        fn get_database_header(&self, license_count: usize) -> DatabaseHeader {
+            DatabaseHeader::new(self.get_parameters(), self.get_normalizer(), license_count)
+        }
+
+        /// Decodes a database and verifies that it was computed with the same algorithm, parameters and normalization as this one.
+        fn try_decode_database(&self, raw: &[u8]) -> Result<DiskData<T>, DetectionError>
+        where
+            T: DeserializeOwned,
+        {
+            decode_database(raw, &self.get_database_header(0))
+        }
+
         /// Saves the computed license list to a file.
+        ///
+        /// # Panics
+        /// Panics if the file can not be written, see [try_save_to_file](LicenseListActions::try_save_to_file) for a fallible variant.
+        #[cfg(not(target_arch = "wasm32"))]
         fn save_to_file(&self, file_path: &str) {
-            // use bincode to serialize the data
-            let binding = self.get_license_list();
-            let data = DiskData {
-                licenses: binding.iter().map(|(name, hash)| LicenseEntry {
-                    name: name.clone(),
-                    hash: hash.clone(),
-                }).collect(),
-            };
-            let raw = bincode::serialize(&data).unwrap();
-            let mut file = File::create(file_path).unwrap();
-            file.write_all(&raw).unwrap();
+            self.try_save_to_file(file_path).unwrap()
+        }
+
+        /// Saves the computed license list to a file, returning an error if the file can not be written.
+        #[cfg(not(target_arch = "wasm32"))]
+        fn try_save_to_file(&self, file_path: &str) -> Result<(), DetectionError> {
+            let raw = self.try_save_to_memory()?;
+            let mut file = File::create(file_path)?;
+            file.write_all(&raw)?;
+            Ok(())
         }
 
         fn save_to_memory(&self) -> Vec<u8> {
-            let binding = self.get_license_list();
+            self.try_save_to_memory().unwrap()
+        }
+
+        /// Serializes the computed license list, returning an error if serialization fails.
+        fn try_save_to_memory(&self) -> Result<Vec<u8>, DetectionError> {
+            // use bincode to serialize the data
             let data = DiskData {
-                licenses: binding.iter().map(|(name, hash)| LicenseEntry {
-                    name: name.clone(),
-                    hash: hash.clone(),
+                licenses: self.get_license_list().into_iter().map(|(name, hash)| LicenseEntry {
+                    text: self.get_reference_text(&name).map(str::to_string),
+                    metadata: self.get_metadata(&name).cloned(),
+                    digest: self.get_digest_index().get(&name),
+                    name,
+                    hash,
                 }).collect(),
             };
-            bincode::serialize(&data).unwrap()
+            encode_database(&self.get_database_header(data.licenses.len()), &data)
         }
 
         /// Loads a computed license list from a file and stores it in the hosting struct.
-        fn load_from_file(&mut self, file_path: &str);
+        ///
+        /// # Panics
+        /// Panics if the file can not be read, see [try_load_from_file](LicenseListActions::try_load_from_file) for a fallible variant.
+        #[cfg(not(target_arch = "wasm32"))]
+        fn load_from_file(&mut self, file_path: &str) {
+            self.try_load_from_file(file_path).unwrap()
+        }
+
+        /// Loads a computed license list from a file, returning an error if the file is missing,
+        /// can not be read or does not contain a valid database.
+        #[cfg(not(target_arch = "wasm32"))]
+        fn try_load_from_file(&mut self, file_path: &str) -> Result<(), DetectionError> {
+            let contents = fs::read(file_path).map_err(|e| match e.kind() {
+                ErrorKind::NotFound => DetectionError::FileNotFound(file_path.to_string()),
+                _ => DetectionError::Io(e),
+            })?;
+
+            self.try_load_from_memory(&contents)
+        }
 
         /// Loads a computed license list from a stored byte vector and stores it in the hosting struct.
-        fn load_from_memory(&mut self, raw: &Vec<u8>);
+        ///
+        /// > A corrupt database is ignored and leaves the license list untouched,
+        /// > use [try_load_from_memory](LicenseListActions::try_load_from_memory) to find out why loading failed.
+        fn load_from_memory(&mut self, raw: &[u8]) {
+            let _ = self.try_load_from_memory(raw);
+        }
+
+        /// Loads a computed license list from a stored byte vector, returning an error if the data can not be decoded
+        /// or was computed with a different algorithm, different parameters or a different normalization function.
+        ///
+        /// Nothing is added to the hosting struct when an error is returned.
+        fn try_load_from_memory(&mut self, raw: &[u8]) -> Result<(), DetectionError>;
 
         /// Adds a license that has yet to be computed to the list.
         /// 
@@ -99,6 +354,18 @@ pub mod detecting {
         /// The normalization function is used to convert the license text into a representation
         /// that can be used to compute the hash, so that the confidence is not affected by text changes that do not matter,
         /// for example stylistic formatting changes.
-        fn set_normalization_fn(&mut self, func: fn(&str) -> String);
+        ///
+        /// See [set_normalizer](LicenseListActions::set_normalizer) for licenses that were already added.
+        fn set_normalization_fn(&mut self, func: fn(&str) -> String) {
+            self.set_normalizer(Arc::new(func));
+        }
+
+        /// Changes the normalizer, like [set_normalization_fn](LicenseListActions::set_normalization_fn) but for
+        /// normalizers configured at runtime or chaining several steps, see [Normalizer].
+        ///
+        /// > Licenses that were already added keep the hashes of the previous normalizer, only new licenses and inputs
+        /// > use the new one. The header of a database saved afterwards records the new normalizer for all of them,
+        /// > so change the normalizer before adding or loading licenses.
+        fn set_normalizer(&mut self, normalizer: Arc<dyn Normalizer>);
     }
 }
@@ -0,0 +1,256 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+pub mod diffing {
+    use std::ops::Range;
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::NormalizedText;
+
+    /// A run of consecutive words that are equal, only in the input or only in the reference text.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub enum DiffChunk {
+        /// Text present in both, as written in the input.
+        Equal(String),
+        /// Text only present in the input, e.g. an added clause or a changed copyright holder.
+        Added(String),
+        /// Text of the reference license missing from the input, e.g. a removed warranty disclaimer.
+        Removed(String),
+    }
+
+    /// A word-level diff between the reference text of a license and an input text.
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct LicenseDiff {
+        /// The name of the license the input was compared with.
+        pub license: String,
+        pub chunks: Vec<DiffChunk>,
+    }
+
+    impl LicenseDiff {
+        /// Returns true if the input contains exactly the words of the reference text, ignoring whitespace.
+        pub fn is_identical(&self) -> bool {
+            self.chunks.iter().all(|c| matches!(c, DiffChunk::Equal(_)))
+        }
+
+        /// Returns the number of words (added, removed) by the input.
+        pub fn changed_words(&self) -> (usize, usize) {
+            self.chunks.iter().fold((0, 0), |(added, removed), chunk| match chunk {
+                DiffChunk::Added(text) => (added + text.split_whitespace().count(), removed),
+                DiffChunk::Removed(text) => (added, removed + text.split_whitespace().count()),
+                DiffChunk::Equal(_) => (added, removed),
+            })
+        }
+
+        /// Renders the diff for a terminal, added text is shown in green and removed text in red with a strikethrough.
+        pub fn to_terminal(&self) -> String {
+            let mut out = String::new();
+            for chunk in self.chunks.iter() {
+                match chunk {
+                    DiffChunk::Equal(text) => out.push_str(text),
+                    DiffChunk::Added(text) => out.push_str(&format!("\x1b[32m{}\x1b[0m", text)),
+                    DiffChunk::Removed(text) => out.push_str(&format!("\x1b[31;9m{}\x1b[0m", text)),
+                }
+            }
+            out
+        }
+
+        /// Renders the diff as an HTML fragment, added text is wrapped in `<ins>` and removed text in `<del>`.
+        ///
+        /// The text is escaped, whitespace is kept as is so the fragment is best placed in a `<pre>` element.
+        pub fn to_html(&self) -> String {
+            let mut out = String::new();
+            for chunk in self.chunks.iter() {
+                match chunk {
+                    DiffChunk::Equal(text) => out.push_str(&escape_html(text)),
+                    DiffChunk::Added(text) => out.push_str(&format!("<ins>{}</ins>", escape_html(text))),
+                    DiffChunk::Removed(text) => out.push_str(&format!("<del>{}</del>", escape_html(text))),
+                }
+            }
+            out
+        }
+    }
+
+    fn escape_html(text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '&' => out.push_str("&amp;"),
+                '<' => out.push_str("&lt;"),
+                '>' => out.push_str("&gt;"),
+                '"' => out.push_str("&quot;"),
+                '\'' => out.push_str("&#39;"),
+                _ => out.push(c),
+            }
+        }
+        out
+    }
+
+    /// A word and the whitespace following it, only the word is compared.
+    struct Token<'a> {
+        word: &'a str,
+        text: &'a str,
+    }
+
+    impl PartialEq for Token<'_> {
+        fn eq(&self, other: &Self) -> bool {
+            self.word == other.word
+        }
+    }
+
+    /// Splits a text into words, keeping the whitespace in front of the first word as a token of its own.
+    fn tokenize(text: &str) -> Vec<Token<'_>> {
+        let mut tokens = Vec::new();
+        let mut rest = text;
+        while !rest.is_empty() {
+            let word_len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let after_word = &rest[word_len..];
+            let space_len = after_word.find(|c: char| !c.is_whitespace()).unwrap_or(after_word.len());
+            tokens.push(Token {
+                word: &rest[..word_len],
+                text: &rest[..word_len + space_len],
+            });
+            rest = &rest[word_len + space_len..];
+        }
+        tokens
+    }
+
+    fn push_chunk(chunks: &mut Vec<DiffChunk>, chunk: DiffChunk) {
+        match (chunks.last_mut(), chunk) {
+            (Some(DiffChunk::Equal(last)), DiffChunk::Equal(text))
+            | (Some(DiffChunk::Added(last)), DiffChunk::Added(text))
+            | (Some(DiffChunk::Removed(last)), DiffChunk::Removed(text)) => last.push_str(&text),
+            (_, chunk) => chunks.push(chunk),
+        }
+    }
+
+    /// Where an input matches and deviates from the reference text of a license, as byte ranges of the input.
+    #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+    pub struct NormalizedDiff {
+        /// From the first to the last word of the input equal to the reference text, `None` if no word is.
+        pub matched_range: Option<Range<usize>>,
+        /// The deviating regions of the input, in order. Text only present in the input is covered by its range, text of
+        /// the reference missing from the input is an empty range at the position where it is missing.
+        pub deviations: Vec<Range<usize>>,
+    }
+
+    /// Splits the normalized text into the (whitespace separated) words of the original text its characters were
+    /// produced from, leaving out words that normalized to nothing (e.g. comment markers).
+    fn normalized_words(original: &str, normalized: &NormalizedText) -> Vec<(Range<usize>, String)> {
+        let mut words: Vec<(Range<usize>, String)> = original
+            .split_whitespace()
+            .map(|word| {
+                let start = word.as_ptr() as usize - original.as_ptr() as usize;
+                (start..start + word.len(), String::new())
+            })
+            .collect();
+        if words.is_empty() {
+            return words;
+        }
+
+        let mut current = 0;
+        for (i, c) in normalized.text.char_indices() {
+            if c.is_whitespace() {
+                continue;
+            }
+            // characters that can not be traced back stay with the word before them.
+            let offset = &normalized.offsets[i];
+            while !offset.is_empty() && current + 1 < words.len() && words[current].0.end <= offset.start {
+                current += 1;
+            }
+            words[current].1.push(c);
+        }
+        words.retain(|(_, text)| !text.is_empty());
+        words
+    }
+
+    /// Computes a word-level diff of the normalized `input` against the normalized `reference` text of a license,
+    /// and maps it back onto the input through the offset map of `normalized_input`.
+    ///
+    /// Unlike [diff_texts] differences removed by normalization (case, punctuation, comment markers, ...) are not reported.
+    pub fn diff_normalized(
+        reference: &str,
+        normalized_reference: &NormalizedText,
+        input: &str,
+        normalized_input: &NormalizedText,
+    ) -> NormalizedDiff {
+        let reference_words = normalized_words(reference, normalized_reference);
+        let input_words = normalized_words(input, normalized_input);
+        let left: Vec<&str> = reference_words.iter().map(|(_, text)| text.as_str()).collect();
+        let right: Vec<&str> = input_words.iter().map(|(_, text)| text.as_str()).collect();
+
+        let mut diff = NormalizedDiff::default();
+        // the index of the next word of the input, and whether the previous word deviated.
+        let mut next = 0;
+        let mut deviating = false;
+        for res in diff::slice(&left, &right) {
+            let range = match res {
+                diff::Result::Both(..) => {
+                    let range = input_words[next].0.clone();
+                    diff.matched_range = Some(match diff.matched_range {
+                        Some(matched) => matched.start..range.end,
+                        None => range,
+                    });
+                    next += 1;
+                    deviating = false;
+                    continue;
+                }
+                diff::Result::Right(_) => {
+                    next += 1;
+                    input_words[next - 1].0.clone()
+                }
+                diff::Result::Left(_) => {
+                    let at = match next {
+                        0 => input_words.first().map_or(0, |(range, _)| range.start),
+                        _ => input_words[next - 1].0.end,
+                    };
+                    at..at
+                }
+            };
+            match diff.deviations.last_mut() {
+                Some(last) if deviating && last.start == last.end && range.start < range.end => *last = range,
+                Some(last) if deviating => *last = last.start.min(range.start)..last.end.max(range.end),
+                _ => diff.deviations.push(range),
+            }
+            deviating = true;
+        }
+        diff
+    }
+
+    /// Computes a word-level diff of `input` against the `reference` text of the license called `license`.
+    ///
+    /// Words are compared exactly (including case and punctuation), differences in whitespace are ignored
+    /// and the whitespace of the input is kept in the [DiffChunk::Equal] and [DiffChunk::Added] chunks.
+    pub fn diff_texts(license: &str, reference: &str, input: &str) -> LicenseDiff {
+        let reference_tokens = tokenize(reference);
+        let input_tokens = tokenize(input);
+
+        let mut chunks: Vec<DiffChunk> = Vec::new();
+        for res in diff::slice(&reference_tokens, &input_tokens) {
+            match res {
+                diff::Result::Both(_, token) => push_chunk(&mut chunks, DiffChunk::Equal(token.text.to_string())),
+                diff::Result::Right(token) => push_chunk(&mut chunks, DiffChunk::Added(token.text.to_string())),
+                diff::Result::Left(token) => push_chunk(&mut chunks, DiffChunk::Removed(token.text.to_string())),
+            }
+        }
+
+        LicenseDiff {
+            license: license.to_string(),
+            chunks,
+        }
+    }
+}
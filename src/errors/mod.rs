@@ -0,0 +1,76 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+pub mod errors {
+    use std::{fmt, io};
+
+    /// The error type returned by every fallible operation of this crate.
+    #[derive(Debug)]
+    pub enum DetectionError {
+        /// The given database file does not exist.
+        FileNotFound(String),
+        /// The database could not be decoded, it is most likely truncated or damaged.
+        CorruptDatabase(String),
+        /// The database was decoded but can not be used by the algorithm it is being loaded into.
+        IncompatibleFormat(String),
+        /// Any other I/O failure (permissions, full disk, etc.).
+        Io(io::Error),
+        /// A builder was given a parameter outside of its valid range.
+        InvalidParameter(String),
+        /// The reference text of the given license is not stored in the database.
+        MissingReferenceText(String),
+        /// An SPDX license expression could not be parsed.
+        InvalidExpression(String),
+    }
+
+    impl fmt::Display for DetectionError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::FileNotFound(path) => write!(f, "database file not found: {}", path),
+                Self::CorruptDatabase(reason) => write!(f, "corrupt database: {}", reason),
+                Self::IncompatibleFormat(reason) => write!(f, "incompatible database: {}", reason),
+                Self::Io(e) => write!(f, "I/O error: {}", e),
+                Self::InvalidParameter(reason) => write!(f, "invalid parameter: {}", reason),
+                Self::MissingReferenceText(name) => write!(f, "no reference text stored for license: {}", name),
+                Self::InvalidExpression(reason) => write!(f, "invalid SPDX expression: {}", reason),
+            }
+        }
+    }
+
+    impl std::error::Error for DetectionError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                Self::Io(e) => Some(e),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<io::Error> for DetectionError {
+        fn from(e: io::Error) -> Self {
+            Self::Io(e)
+        }
+    }
+
+    impl From<bincode::Error> for DetectionError {
+        /// Databases are always decoded from memory, so even an unexpected EOF reported by bincode
+        /// means the data itself is truncated rather than an I/O failure.
+        fn from(e: bincode::Error) -> Self {
+            Self::CorruptDatabase(e.to_string())
+        }
+    }
+}
@@ -0,0 +1,413 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+/// The C ABI of the crate, the matching header `include/whichlicense_detection.h` is generated with cbindgen (see `cbindgen.toml`).
+///
+/// Every function returns a [WldStatus], results are written to out-pointers. Memory handed out by the library
+/// (detectors, matches, strings and byte buffers) must be released with the matching `wld_*_free` function.
+/// When a call fails, [wld_last_error_message] describes the failure in more detail.
+pub mod ffi {
+    use std::{
+        cell::RefCell,
+        ffi::{c_char, CStr, CString},
+        panic::{catch_unwind, AssertUnwindSafe},
+        ptr, slice,
+    };
+
+    use crate::{
+        detecting::dynamic::dynamic::{Algorithm, AnyDetector},
+        strip_license, DetectionError,
+    };
+
+    /// The result of every FFI call.
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum WldStatus {
+        Ok = 0,
+        /// A required pointer argument was NULL.
+        NullPointer = 1,
+        /// A string argument was not valid UTF-8.
+        InvalidUtf8 = 2,
+        FileNotFound = 3,
+        CorruptDatabase = 4,
+        IncompatibleFormat = 5,
+        Io = 6,
+        InvalidParameter = 7,
+        MissingReferenceText = 8,
+        InvalidExpression = 9,
+        /// The library panicked, this is a bug.
+        Panic = 10,
+    }
+
+    /// The algorithm of a detector created with [wld_detector_new], passed as a `uint32_t`.
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum WldAlgorithm {
+        Fuzzy = 0,
+        Gaoya = 1,
+        BlockHash = 2,
+    }
+
+    /// A single license match, see `LicenseMatch`.
+    #[repr(C)]
+    pub struct WldMatch {
+        /// The NUL terminated name of the license, owned by the surrounding [WldMatches].
+        pub name: *mut c_char,
+        pub confidence: f32,
+    }
+
+    /// The matches returned by [wld_detector_match], sorted by confidence. Released with [wld_matches_free].
+    #[repr(C)]
+    pub struct WldMatches {
+        pub matches: *mut WldMatch,
+        pub len: usize,
+    }
+
+    /// An opaque handle to a detector, released with [wld_detector_free].
+    pub struct WldDetector {
+        inner: AnyDetector,
+    }
+
+
+    thread_local! {
+        static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+    }
+
+    struct Failure(WldStatus, String);
+
+    impl From<DetectionError> for Failure {
+        fn from(e: DetectionError) -> Self {
+            let status = match e {
+                DetectionError::FileNotFound(_) => WldStatus::FileNotFound,
+                DetectionError::CorruptDatabase(_) => WldStatus::CorruptDatabase,
+                DetectionError::IncompatibleFormat(_) => WldStatus::IncompatibleFormat,
+                DetectionError::Io(_) => WldStatus::Io,
+                DetectionError::InvalidParameter(_) => WldStatus::InvalidParameter,
+                DetectionError::MissingReferenceText(_) => WldStatus::MissingReferenceText,
+                DetectionError::InvalidExpression(_) => WldStatus::InvalidExpression,
+            };
+            Self(status, e.to_string())
+        }
+    }
+
+    /// Runs an FFI call, converting its failure or panic into a status and remembering the error message.
+    fn ffi_call(call: impl FnOnce() -> Result<(), Failure>) -> WldStatus {
+        let result = catch_unwind(AssertUnwindSafe(call)).unwrap_or_else(|panic| {
+            let message = match panic.downcast_ref::<&str>() {
+                Some(message) => message.to_string(),
+                None => panic.downcast_ref::<String>().cloned().unwrap_or_default(),
+            };
+            Err(Failure(WldStatus::Panic, format!("panicked: {}", message)))
+        });
+        match result {
+            Ok(()) => WldStatus::Ok,
+            Err(Failure(status, message)) => {
+                let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+                LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+                status
+            }
+        }
+    }
+
+    fn not_null<T>(pointer: *const T, name: &str) -> Result<(), Failure> {
+        match pointer.is_null() {
+            true => Err(Failure(WldStatus::NullPointer, format!("{} is NULL", name))),
+            false => Ok(()),
+        }
+    }
+
+    /// Converts the raw value of a [WldAlgorithm], C callers can pass any integer so it is not taken as the enum itself.
+    fn read_algorithm(algorithm: u32) -> Result<Algorithm, Failure> {
+        match algorithm {
+            a if a == WldAlgorithm::Fuzzy as u32 => Ok(Algorithm::Fuzzy),
+            a if a == WldAlgorithm::Gaoya as u32 => Ok(Algorithm::Gaoya),
+            a if a == WldAlgorithm::BlockHash as u32 => Ok(Algorithm::BlockHash),
+            _ => Err(Failure(WldStatus::InvalidParameter, format!("unknown algorithm {}", algorithm))),
+        }
+    }
+
+    /// # Safety
+    /// `pointer` must be NULL or point to a NUL terminated string.
+    unsafe fn read_str<'a>(pointer: *const c_char, name: &str) -> Result<&'a str, Failure> {
+        not_null(pointer, name)?;
+        CStr::from_ptr(pointer)
+            .to_str()
+            .map_err(|e| Failure(WldStatus::InvalidUtf8, format!("{} is not valid UTF-8: {}", name, e)))
+    }
+
+    /// # Safety
+    /// `detector` must be NULL or a handle returned by this library that has not been freed.
+    unsafe fn detector_ref<'a>(detector: *const WldDetector) -> Result<&'a AnyDetector, Failure> {
+        not_null(detector, "detector")?;
+        Ok(&(*detector).inner)
+    }
+
+    /// # Safety
+    /// `detector` must be NULL or a handle returned by this library that has not been freed.
+    unsafe fn detector_mut<'a>(detector: *mut WldDetector) -> Result<&'a mut AnyDetector, Failure> {
+        not_null(detector, "detector")?;
+        Ok(&mut (*detector).inner)
+    }
+
+    fn into_handle(detector: AnyDetector) -> *mut WldDetector {
+        Box::into_raw(Box::new(WldDetector { inner: detector }))
+    }
+
+    fn to_c_string(value: &str) -> *mut c_char {
+        CString::new(value.replace('\0', " ")).unwrap_or_default().into_raw()
+    }
+
+    /// Returns a static, NUL terminated description of the raw value of a [WldStatus].
+    ///
+    /// C callers can pass any integer so it is not taken as the enum itself, unknown values are described as such.
+    #[no_mangle]
+    pub extern "C" fn wld_status_description(status: u32) -> *const c_char {
+        let description: &'static CStr = match status {
+            s if s == WldStatus::Ok as u32 => c"ok",
+            s if s == WldStatus::NullPointer as u32 => c"a required pointer was NULL",
+            s if s == WldStatus::InvalidUtf8 as u32 => c"a string was not valid UTF-8",
+            s if s == WldStatus::FileNotFound as u32 => c"file not found",
+            s if s == WldStatus::CorruptDatabase as u32 => c"corrupt database",
+            s if s == WldStatus::IncompatibleFormat as u32 => c"incompatible database format",
+            s if s == WldStatus::Io as u32 => c"I/O error",
+            s if s == WldStatus::InvalidParameter as u32 => c"invalid parameter",
+            s if s == WldStatus::MissingReferenceText as u32 => c"no reference text stored for license",
+            s if s == WldStatus::InvalidExpression as u32 => c"invalid SPDX expression",
+            s if s == WldStatus::Panic as u32 => c"the library panicked",
+            _ => c"unknown status",
+        };
+        description.as_ptr()
+    }
+
+    /// Returns the message of the last failed call on this thread, or NULL if no call failed yet.
+    ///
+    /// The string is owned by the library and valid until the next failing call on the same thread.
+    #[no_mangle]
+    pub extern "C" fn wld_last_error_message() -> *const c_char {
+        LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+    }
+
+    /// Creates a detector without any licenses, `algorithm` is one of the [WldAlgorithm] values.
+    ///
+    /// # Safety
+    /// `out` must be NULL or valid for writes.
+    #[no_mangle]
+    pub unsafe extern "C" fn wld_detector_new(algorithm: u32, out: *mut *mut WldDetector) -> WldStatus {
+        ffi_call(|| {
+            let algorithm = read_algorithm(algorithm)?;
+            not_null(out, "out")?;
+            *out = into_handle(AnyDetector::new(algorithm)?);
+            Ok(())
+        })
+    }
+
+    /// Creates a detector using the database embedded in the library, `algorithm` is one of the [WldAlgorithm] values.
+    ///
+    /// Returns [WldStatus::InvalidParameter] for [WldAlgorithm::BlockHash], there is no embedded block hash database.
+    ///
+    /// # Safety
+    /// `out` must be NULL or valid for writes.
+    #[cfg(feature = "embedded-database")]
+    #[no_mangle]
+    pub unsafe extern "C" fn wld_detector_with_default_database(algorithm: u32, out: *mut *mut WldDetector) -> WldStatus {
+        ffi_call(|| {
+            let algorithm = read_algorithm(algorithm)?;
+            not_null(out, "out")?;
+            *out = into_handle(AnyDetector::with_default_database(algorithm)?);
+            Ok(())
+        })
+    }
+
+    /// Creates a detector from a saved database, the algorithm and its parameters are read from the database's header.
+    ///
+    /// # Safety
+    /// `data` must be NULL or valid for reads of `len` bytes, `out` must be NULL or valid for writes.
+    #[no_mangle]
+    pub unsafe extern "C" fn wld_detector_load(data: *const u8, len: usize, out: *mut *mut WldDetector) -> WldStatus {
+        ffi_call(|| {
+            not_null(data, "data")?;
+            not_null(out, "out")?;
+            *out = into_handle(AnyDetector::load(slice::from_raw_parts(data, len))?);
+            Ok(())
+        })
+    }
+
+    /// Saves the detector's database, the buffer written to `out_data` is released with [wld_bytes_free].
+    ///
+    /// # Safety
+    /// `detector` must be NULL or a live handle, `out_data` and `out_len` must be NULL or valid for writes.
+    #[no_mangle]
+    pub unsafe extern "C" fn wld_detector_save(
+        detector: *const WldDetector,
+        out_data: *mut *mut u8,
+        out_len: *mut usize,
+    ) -> WldStatus {
+        ffi_call(|| {
+            let detector = detector_ref(detector)?;
+            not_null(out_data, "out_data")?;
+            not_null(out_len, "out_len")?;
+            let raw = detector.try_save_to_memory()?.into_boxed_slice();
+            *out_len = raw.len();
+            *out_data = Box::into_raw(raw) as *mut u8;
+            Ok(())
+        })
+    }
+
+    /// Adds (or replaces) a license.
+    ///
+    /// # Safety
+    /// `detector` must be NULL or a live handle, `name` and `text` must be NULL or NUL terminated strings.
+    #[no_mangle]
+    pub unsafe extern "C" fn wld_detector_add_license(
+        detector: *mut WldDetector,
+        name: *const c_char,
+        text: *const c_char,
+    ) -> WldStatus {
+        ffi_call(|| {
+            let detector = detector_mut(detector)?;
+            let name = read_str(name, "name")?;
+            let text = read_str(text, "text")?;
+            detector.remove(name);
+            detector.add_plain(name, text);
+            Ok(())
+        })
+    }
+
+    /// Removes a license, unknown licenses are ignored.
+    ///
+    /// # Safety
+    /// `detector` must be NULL or a live handle, `name` must be NULL or a NUL terminated string.
+    #[no_mangle]
+    pub unsafe extern "C" fn wld_detector_remove_license(detector: *mut WldDetector, name: *const c_char) -> WldStatus {
+        ffi_call(|| {
+            let detector = detector_mut(detector)?;
+            detector.remove(read_str(name, "name")?);
+            Ok(())
+        })
+    }
+
+    /// Sets the confidence (0-100) below which matches are discarded, larger values return [WldStatus::InvalidParameter].
+    ///
+    /// # Safety
+    /// `detector` must be NULL or a live handle.
+    #[no_mangle]
+    pub unsafe extern "C" fn wld_detector_set_min_confidence(detector: *mut WldDetector, min_confidence: u8) -> WldStatus {
+        ffi_call(|| {
+            detector_mut(detector)?.set_min_confidence(min_confidence)?;
+            Ok(())
+        })
+    }
+
+    /// Matches a license text, the matches written to `out` are released with [wld_matches_free].
+    ///
+    /// A detector may be used from multiple threads at once as long as it is not modified at the same time.
+    ///
+    /// # Safety
+    /// `detector` must be NULL or a live handle, `text` must be NULL or a NUL terminated string and `out` must be NULL or valid for writes.
+    #[no_mangle]
+    pub unsafe extern "C" fn wld_detector_match(
+        detector: *const WldDetector,
+        text: *const c_char,
+        out: *mut WldMatches,
+    ) -> WldStatus {
+        ffi_call(|| {
+            let detector = detector_ref(detector)?;
+            let text = read_str(text, "text")?;
+            not_null(out, "out")?;
+
+            let matches: Box<[WldMatch]> = detector
+                .match_by_plain_text(text)
+                .iter()
+                .map(|m| WldMatch {
+                    name: to_c_string(&m.name),
+                    confidence: m.confidence,
+                })
+                .collect();
+            *out = WldMatches {
+                len: matches.len(),
+                matches: Box::into_raw(matches) as *mut WldMatch,
+            };
+            Ok(())
+        })
+    }
+
+    /// Releases a detector, NULL is ignored.
+    ///
+    /// # Safety
+    /// `detector` must be NULL or a live handle, it must not be used afterwards.
+    #[no_mangle]
+    pub unsafe extern "C" fn wld_detector_free(detector: *mut WldDetector) {
+        if !detector.is_null() {
+            drop(Box::from_raw(detector));
+        }
+    }
+
+    /// Releases the matches returned by [wld_detector_match] and resets `matches` to an empty list.
+    ///
+    /// # Safety
+    /// `matches` must be NULL or point to matches returned by [wld_detector_match] that have not been freed.
+    #[no_mangle]
+    pub unsafe extern "C" fn wld_matches_free(matches: *mut WldMatches) {
+        if matches.is_null() || (*matches).matches.is_null() {
+            return;
+        }
+        let owned = Box::from_raw(ptr::slice_from_raw_parts_mut((*matches).matches, (*matches).len));
+        for m in owned.iter() {
+            drop(CString::from_raw(m.name));
+        }
+        *matches = WldMatches {
+            matches: ptr::null_mut(),
+            len: 0,
+        };
+    }
+
+    /// Normalizes a license text with the default normalization function, the string written to `out` is released with [wld_string_free].
+    ///
+    /// # Safety
+    /// `text` must be NULL or a NUL terminated string, `out` must be NULL or valid for writes.
+    #[no_mangle]
+    pub unsafe extern "C" fn wld_strip_license(text: *const c_char, out: *mut *mut c_char) -> WldStatus {
+        ffi_call(|| {
+            let text = read_str(text, "text")?;
+            not_null(out, "out")?;
+            *out = to_c_string(&strip_license(text));
+            Ok(())
+        })
+    }
+
+    /// Releases a string returned by the library, NULL is ignored.
+    ///
+    /// # Safety
+    /// `string` must be NULL or a string returned by this library that has not been freed.
+    #[no_mangle]
+    pub unsafe extern "C" fn wld_string_free(string: *mut c_char) {
+        if !string.is_null() {
+            drop(CString::from_raw(string));
+        }
+    }
+
+    /// Releases a buffer returned by [wld_detector_save], NULL is ignored.
+    ///
+    /// # Safety
+    /// `data` must be NULL or a buffer returned by this library together with its length, it must not have been freed.
+    #[no_mangle]
+    pub unsafe extern "C" fn wld_bytes_free(data: *mut u8, len: usize) {
+        if !data.is_null() {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+        }
+    }
+}
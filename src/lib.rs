@@ -15,20 +15,49 @@
 *   limitations under the License.
 */
 
+#![allow(clippy::module_inception)]
+
+pub mod custom_fuzzy;
 pub mod detecting;
+pub mod diffing;
+pub mod errors;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod license_tools;
-// pub mod offloading;
+pub mod offloading;
 pub mod pipeline_tools;
+pub mod policy;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod scanning;
+pub mod segmenting;
+pub mod spdx;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 
 pub use crate::detecting::detecting::*;
+pub use crate::detecting::database::database::*;
+pub use crate::diffing::diffing::*;
+pub use crate::errors::errors::*;
+#[cfg(feature = "ffi")]
+pub use crate::ffi::ffi::*;
 pub use crate::license_tools::license_tools::*;
+pub use crate::license_tools::matching_guidelines::matching_guidelines::*;
+pub use crate::license_tools::metadata::metadata::*;
+pub use crate::license_tools::normalizer::normalizer::*;
+pub use crate::offloading::threaded_detection::*;
+pub use crate::pipeline_tools::builtin::builtin::*;
 pub use crate::pipeline_tools::pipeline::*;
-// pub use crate::offloading::threaded_detection::*;
-
+pub use crate::pipeline_tools::report::report::*;
+pub use crate::policy::policy::*;
+#[cfg(feature = "python")]
+pub use crate::python::python::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::scanning::scanning::*;
+pub use crate::segmenting::segmenting::*;
+pub use crate::spdx::spdx::*;
+#[cfg(feature = "wasm")]
+pub use crate::wasm::wasm::*;
 
-// // C mappings
-// #[no_mangle]
-// pub extern "C" fn e_strip_license(l: &str) -> String {
-//     strip_license(l)
-// }
\ No newline at end of file
@@ -0,0 +1,132 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+/// Normalization following the [SPDX License List Matching Guidelines](https://spdx.github.io/spdx-spec/v2.3/license-matching-guidelines-and-templates/).
+pub mod matching_guidelines {
+    use std::sync::LazyLock;
+
+    use regex::Regex;
+
+    use crate::{tokenize_license, BuiltinSegment};
+
+    // placeholders left (or introduced) by `BuiltinSegment::ReplaceYearAndHolder`.
+    static REPLACEABLE_TEXT: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"<year>|<copyright holders>").expect("the replaceable text pattern is valid"));
+    static COPYRIGHT_SYMBOL: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?i)\(c\)|©").expect("the copyright symbol pattern is valid"));
+    // hyphens and dashes between letters, so that e.g. `sub-license` and `sublicense` are equal.
+    static HYPHENATION: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(\p{L})[-‐‑–—](\p{L})").expect("the hyphenation pattern is valid"));
+
+    /// The varietal spellings of the guidelines, the first spelling is replaced by the second.
+    const EQUIVALENT_WORDS: &[(&str, &str)] = &[
+        ("acknowledgement", "acknowledgment"),
+        ("analogue", "analog"),
+        ("analyse", "analyze"),
+        ("artefact", "artifact"),
+        ("authorisation", "authorization"),
+        ("authorised", "authorized"),
+        ("calibre", "caliber"),
+        ("cancelled", "canceled"),
+        ("capitalisations", "capitalizations"),
+        ("catalogue", "catalog"),
+        ("categorise", "categorize"),
+        ("centre", "center"),
+        ("emphasised", "emphasized"),
+        ("favour", "favor"),
+        ("favourite", "favorite"),
+        ("fulfil", "fulfill"),
+        ("fulfilment", "fulfillment"),
+        ("https", "http"),
+        ("initialise", "initialize"),
+        ("judgement", "judgment"),
+        ("labelling", "labeling"),
+        ("labour", "labor"),
+        ("licence", "license"),
+        ("licenced", "licensed"),
+        ("licences", "licenses"),
+        ("licencing", "licensing"),
+        ("licencor", "licensor"),
+        ("maximise", "maximize"),
+        ("modelled", "modeled"),
+        ("modelling", "modeling"),
+        ("offence", "offense"),
+        ("optimise", "optimize"),
+        ("organisation", "organization"),
+        ("organise", "organize"),
+        ("practise", "practice"),
+        ("programme", "program"),
+        ("realise", "realize"),
+        ("recognise", "recognize"),
+        ("signalling", "signaling"),
+        ("utilisation", "utilization"),
+        ("whilst", "while"),
+        ("wilful", "willful"),
+    ];
+
+    fn equivalent_word(word: &str) -> &str {
+        EQUIVALENT_WORDS
+            .iter()
+            .find(|(varietal, _)| *varietal == word)
+            .map_or(word, |(_, equivalent)| equivalent)
+    }
+
+    /// Normalizes a license text following the SPDX License List Matching Guidelines, the result is a sequence of
+    /// lowercase words separated by single spaces (see [tokenize_license]).
+    ///
+    /// - whitespace, capitalization, punctuation, quotes and hyphens/dashes are not significant;
+    /// - code comment indicators, bullets and numbering at the start of a line are ignored;
+    /// - copyright notices (and "All rights reserved") are ignored, `(c)` and `©` are equal to `Copyright`;
+    /// - replaceable text, such as `<year>` or `[name of copyright owner]`, is ignored;
+    /// - varietal spellings (e.g. `licence` and `license`, `copyright owner` and `copyright holder`) as well as `http`
+    ///   and `https` are equal.
+    ///
+    /// ```
+    /// use whichlicense_detection::normalize_spdx;
+    ///
+    /// assert_eq!(
+    ///     normalize_spdx("Copyright (c) 2023 Jane Doe\n * 1. The Licence is granted by the copyright owner."),
+    ///     normalize_spdx("2) the license is granted by the Copyright Holder"),
+    /// );
+    /// ```
+    pub fn normalize_spdx(l: &str) -> String {
+        let text = [
+            BuiltinSegment::StripCommentMarkers,
+            BuiltinSegment::StripCopyrightLines,
+            BuiltinSegment::StripAllRightsReserved,
+            BuiltinSegment::RemoveBullets,
+            BuiltinSegment::ReplaceYearAndHolder,
+        ]
+        .iter()
+        .fold(l.to_string(), |text, segment| segment.apply(&text));
+        let text = REPLACEABLE_TEXT.replace_all(&text, " ");
+        let text = COPYRIGHT_SYMBOL.replace_all(&text, " copyright ");
+        let text = HYPHENATION.replace_all(&text, "$1$2");
+
+        let mut words: Vec<String> = Vec::new();
+        for word in tokenize_license(&text) {
+            let word = match (words.last().map(String::as_str), word.as_str()) {
+                (Some("copyright"), "owner") => "holder",
+                (Some("copyright"), "owners") => "holders",
+                (_, word) => equivalent_word(word),
+            }
+            .to_string();
+            words.push(word);
+        }
+        words.join(" ")
+    }
+}
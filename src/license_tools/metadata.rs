@@ -0,0 +1,273 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+pub mod metadata {
+    use std::{collections::HashMap, fmt};
+
+    use serde::{Deserialize, Serialize};
+
+    /// The category ScanCode assigns to a license.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+    pub enum LicenseCategory {
+        Permissive,
+        Copyleft,
+        CopyleftLimited,
+        PatentLicense,
+        PublicDomain,
+        ProprietaryFree,
+        FreeRestricted,
+        SourceAvailable,
+        Commercial,
+        Cla,
+        UnstatedLicense,
+        Other,
+        /// A category this version of the crate does not know about.
+        Unknown(String),
+    }
+
+    impl LicenseCategory {
+        const NAMES: [(&'static str, LicenseCategory); 12] = [
+            ("Permissive", Self::Permissive),
+            ("Copyleft", Self::Copyleft),
+            ("Copyleft Limited", Self::CopyleftLimited),
+            ("Patent License", Self::PatentLicense),
+            ("Public Domain", Self::PublicDomain),
+            ("Proprietary Free", Self::ProprietaryFree),
+            ("Free Restricted", Self::FreeRestricted),
+            ("Source-available", Self::SourceAvailable),
+            ("Commercial", Self::Commercial),
+            ("CLA", Self::Cla),
+            ("Unstated License", Self::UnstatedLicense),
+            ("Other", Self::Other),
+        ];
+
+        /// Parses a ScanCode category name (e.g. `Copyleft Limited`), ignoring case.
+        pub fn parse(name: &str) -> Self {
+            let name = name.trim();
+            match Self::NAMES.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+                Some((_, category)) => category.clone(),
+                None => Self::Unknown(name.to_string()),
+            }
+        }
+    }
+
+    impl fmt::Display for LicenseCategory {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            if let Self::Unknown(name) = self {
+                return write!(f, "{}", name);
+            }
+            let (name, _) = Self::NAMES.iter().find(|(_, c)| c == self).expect("every known category has a name");
+            write!(f, "{}", name)
+        }
+    }
+
+    /// The metadata found in the YAML heading of a ScanCode license file.
+    #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+    pub struct LicenseMetadata {
+        /// The ScanCode license key, e.g. `gpl-2.0-plus`.
+        pub key: Option<String>,
+        pub short_name: Option<String>,
+        pub name: Option<String>,
+        pub category: Option<LicenseCategory>,
+        pub owner: Option<String>,
+        /// The SPDX identifier of the license, e.g. `GPL-2.0-or-later`.
+        pub spdx_license_key: Option<String>,
+        /// Deprecated or alternative SPDX identifiers of the license.
+        pub other_spdx_license_keys: Vec<String>,
+        /// Whether this is a license exception, to be used after `WITH` in an SPDX expression.
+        pub is_exception: bool,
+        pub is_deprecated: bool,
+        /// The identifier used by the OSI, only set for OSI approved licenses.
+        pub osi_license_key: Option<String>,
+        pub homepage_url: Option<String>,
+        pub osi_url: Option<String>,
+        pub text_urls: Vec<String>,
+        pub other_urls: Vec<String>,
+    }
+
+    impl LicenseMetadata {
+        /// Whether the license is approved by the Open Source Initiative.
+        pub fn is_osi_approved(&self) -> bool {
+            self.osi_license_key.is_some() || self.osi_url.is_some()
+        }
+
+        /// Parses the YAML heading of a ScanCode license file (without the `---` delimiters).
+        ///
+        /// Only the subset of YAML used by ScanCode is supported: `key: value` pairs, lists of `- item` and
+        /// (block) scalars spanning multiple lines. Unknown keys and lines that can not be parsed are ignored.
+        pub fn from_yaml(yaml: &str) -> Self {
+            let mut values = parse_yaml(yaml);
+            let mut scalar = |key: &str| match values.remove(key) {
+                Some(YamlValue::Scalar(value)) if !value.is_empty() => Some(value),
+                _ => None,
+            };
+
+            let mut metadata = Self {
+                key: scalar("key"),
+                short_name: scalar("short_name"),
+                name: scalar("name"),
+                category: scalar("category").map(|c| LicenseCategory::parse(&c)),
+                owner: scalar("owner"),
+                spdx_license_key: scalar("spdx_license_key"),
+                is_exception: scalar("is_exception").is_some_and(|v| is_true(&v)),
+                is_deprecated: scalar("is_deprecated").is_some_and(|v| is_true(&v)),
+                osi_license_key: scalar("osi_license_key"),
+                homepage_url: scalar("homepage_url"),
+                osi_url: scalar("osi_url"),
+                ..Default::default()
+            };
+
+            let mut list = |key: &str| match values.remove(key) {
+                Some(YamlValue::List(items)) => items,
+                Some(YamlValue::Scalar(value)) if !value.is_empty() => vec![value],
+                _ => vec![],
+            };
+            metadata.other_spdx_license_keys = list("other_spdx_license_keys");
+            metadata.text_urls = list("text_urls");
+            metadata.other_urls = list("other_urls");
+            metadata
+        }
+    }
+
+    /// Splits a ScanCode license file into its YAML heading (without the `---` delimiters) and the license text following it.
+    ///
+    /// Returns `None` if the text does not start with a heading.
+    pub fn split_license_heading(text: &str) -> Option<(&str, &str)> {
+        let text = text.trim_start_matches('\u{feff}');
+        let rest = text.strip_prefix("---")?;
+        let rest = rest.strip_prefix('\n').or_else(|| rest.strip_prefix("\r\n"))?;
+
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            if line.trim_end() == "---" {
+                return Some((&rest[..offset], &rest[offset + line.len()..]));
+            }
+            offset += line.len();
+        }
+        None
+    }
+
+    /// Parses the heading of a ScanCode license file, see [split_license_heading] and [LicenseMetadata::from_yaml].
+    pub fn parse_license_heading(text: &str) -> Option<LicenseMetadata> {
+        split_license_heading(text).map(|(yaml, _)| LicenseMetadata::from_yaml(yaml))
+    }
+
+    fn is_true(value: &str) -> bool {
+        matches!(value.to_lowercase().as_str(), "yes" | "true" | "y" | "on")
+    }
+
+    enum YamlValue {
+        Scalar(String),
+        List(Vec<String>),
+    }
+
+    /// Removes the quotes around a scalar, unescaping doubled single quotes and escaped double quotes.
+    fn unquote(value: &str) -> String {
+        let value = value.trim();
+        if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+            return value[1..value.len() - 1].replace("''", "'");
+        }
+        if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+            return value[1..value.len() - 1].replace("\\\"", "\"").replace("\\\\", "\\");
+        }
+        value.to_string()
+    }
+
+    fn parse_yaml(yaml: &str) -> HashMap<String, YamlValue> {
+        let mut values: HashMap<String, YamlValue> = HashMap::new();
+        // the key the indented lines that follow belong to, and whether it is a literal (`|`) block scalar.
+        let mut current: Option<(String, bool)> = None;
+
+        for line in yaml.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                if let Some((key, true)) = &current {
+                    if let Some(YamlValue::Scalar(value)) = values.get_mut(key) {
+                        value.push('\n');
+                    }
+                }
+                continue;
+            }
+
+            let indented = line.starts_with([' ', '\t']);
+            let is_item = trimmed.starts_with("- ") || trimmed == "-";
+            if !indented && !is_item {
+                current = None;
+                let Some((key, value)) = trimmed.split_once(':') else {
+                    continue;
+                };
+                let key = key.trim().to_string();
+                let value = value.trim();
+                match value {
+                    "" => {
+                        values.insert(key.clone(), YamlValue::Scalar(String::new()));
+                        current = Some((key, false));
+                    }
+                    _ if value.starts_with('|') || value.starts_with('>') => {
+                        values.insert(key.clone(), YamlValue::Scalar(String::new()));
+                        current = Some((key, value.starts_with('|')));
+                    }
+                    _ => {
+                        // quoted scalars may span multiple lines, they are unquoted once complete.
+                        values.insert(key.clone(), YamlValue::Scalar(value.to_string()));
+                        current = Some((key, false));
+                    }
+                }
+                continue;
+            }
+
+            let Some((key, literal)) = &current else {
+                continue;
+            };
+            let entry = values.entry(key.clone()).or_insert_with(|| YamlValue::Scalar(String::new()));
+            if is_item {
+                let item = trimmed.strip_prefix('-').unwrap_or_default();
+                match entry {
+                    YamlValue::List(items) => items.push(unquote(item)),
+                    YamlValue::Scalar(value) if value.is_empty() => *entry = YamlValue::List(vec![unquote(item)]),
+                    YamlValue::Scalar(value) => {
+                        value.push(' ');
+                        value.push_str(trimmed);
+                    }
+                }
+                continue;
+            }
+            match entry {
+                YamlValue::List(items) => {
+                    // continuation of the previous (multi-line) list item.
+                    if let Some(last) = items.last_mut() {
+                        last.push(' ');
+                        last.push_str(trimmed);
+                    }
+                }
+                YamlValue::Scalar(value) => {
+                    if !value.is_empty() && !value.ends_with('\n') {
+                        value.push(if *literal { '\n' } else { ' ' });
+                    }
+                    value.push_str(trimmed);
+                }
+            }
+        }
+
+        for value in values.values_mut() {
+            if let YamlValue::Scalar(value) = value {
+                *value = unquote(value);
+            }
+        }
+        values
+    }
+}
@@ -15,8 +15,21 @@
 *   limitations under the License.
 */
 
+pub mod matching_guidelines;
+pub mod metadata;
+pub mod normalizer;
+
 pub mod license_tools {
     use regex::Regex;
+    use serde::{Deserialize, Serialize};
+    use unicode_normalization::UnicodeNormalization;
+    use unicode_segmentation::UnicodeSegmentation;
+
+    use crate::{split_license_heading, LicenseMetadata};
+    use std::{collections::HashMap, ops::Range};
+    #[cfg(not(target_arch = "wasm32"))]
+    use crate::parse_license_heading;
+    #[cfg(not(target_arch = "wasm32"))]
     use std::{
         fs::{self, File},
         io::Read,
@@ -34,12 +47,168 @@ pub mod license_tools {
         l.chars().filter(|c| c.is_ascii_alphanumeric()).collect()
     }
 
+    /// Splits a text on Unicode word boundaries (UAX #29) into NFKC normalized, case-folded, words.
+    ///
+    /// Whitespace and punctuation are dropped and every ideograph is a word of its own, so unlike [strip_license]
+    /// words are never merged and non-ASCII letters are kept.
+    /// ```
+    /// use whichlicense_detection::tokenize_license;
+    ///
+    /// assert_eq!(tokenize_license("MIT License, ﬁle 版权"), ["mit", "license", "file", "版", "权"]);
+    /// ```
+    pub fn tokenize_license(l: &str) -> Vec<String> {
+        token_indices(l).map(|(_, token)| token).collect()
+    }
+
+    /// Normalization function joining the words of [tokenize_license] with a single space.
+    ///
+    /// Use it together with [Shingling::Words](crate::Shingling::Words) to shingle words instead of characters in
+    /// [GaoyaDetection](crate::detecting::gaoya_implementation::gaoya_implementation::GaoyaDetection).
+    pub fn normalize_tokens(l: &str) -> String {
+        tokenize_license(l).join(" ")
+    }
+
+    fn token_indices(l: &str) -> impl Iterator<Item = (Range<usize>, String)> + '_ {
+        l.unicode_word_indices()
+            .map(|(i, word)| (i..i + word.len(), word.nfkc().flat_map(char::to_lowercase).collect()))
+    }
+
+    /// A normalized text together with where each of its bytes came from in the original text.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct NormalizedText {
+        pub text: String,
+        /// `offsets[i]` is the byte range in the original text that byte `i` of [text](NormalizedText::text) was produced from.
+        ///
+        /// Bytes that could not be traced back to the original (e.g. inserted by a custom normalization function) have an empty range.
+        pub offsets: Vec<Range<usize>>,
+    }
+
+    impl NormalizedText {
+        /// Maps a byte range of the normalized text back to the byte range of the original text it covers.
+        ///
+        /// Returns `None` for an empty or out of bounds range.
+        pub fn original_range(&self, normalized: Range<usize>) -> Option<Range<usize>> {
+            if normalized.is_empty() || normalized.end > self.offsets.len() {
+                return None;
+            }
+            let start = self.offsets[normalized.clone()].iter().find(|r| !r.is_empty())?.start;
+            let end = self.offsets[normalized].iter().rev().find(|r| !r.is_empty())?.end;
+            Some(start..end)
+        }
+    }
+
+    /// Same as [strip_license] but also returns the offset map of the stripped text.
+    pub fn strip_license_with_offsets(l: &str) -> NormalizedText {
+        let mut normalized = NormalizedText {
+            text: String::new(),
+            offsets: Vec::new(),
+        };
+        for (i, c) in l.char_indices().filter(|(_, c)| c.is_ascii_alphanumeric()) {
+            normalized.text.push(c);
+            normalized.offsets.push(i..i + 1);
+        }
+        normalized
+    }
+
+    /// Same as [normalize_tokens] but also returns the offset map of the normalized text.
+    ///
+    /// Every byte of a word maps to the whole word it was produced from, the separating spaces map to nothing.
+    pub fn normalize_tokens_with_offsets(l: &str) -> NormalizedText {
+        let mut normalized = NormalizedText {
+            text: String::new(),
+            offsets: Vec::new(),
+        };
+        for (range, token) in token_indices(l) {
+            if !normalized.text.is_empty() {
+                normalized.text.push(' ');
+                normalized.offsets.push(range.start..range.start);
+            }
+            normalized.text.push_str(&token);
+            normalized.offsets.extend(std::iter::repeat_n(range, token.len()));
+        }
+        normalized
+    }
+
+    /// Computes the offset map of a text normalized by an arbitrary normalization function.
+    ///
+    /// Every character of the normalized text is greedily aligned with the next equal (ignoring case) character of the original text.
+    /// This is exact for normalization functions that only remove characters or change their case, and a best effort for anything else.
+    ///
+    /// Runs in linear time: the positions of every character of the original are collected up front, and the position
+    /// to continue from only ever moves forward for every character.
+    pub fn align_normalized(original: &str, normalized: &str) -> NormalizedText {
+        let mut positions: HashMap<[char; 3], (Vec<Range<usize>>, usize)> = HashMap::new();
+        for (i, o) in original.char_indices() {
+            positions.entry(fold_case(o)).or_default().0.push(i..i + o.len_utf8());
+        }
+
+        let mut offsets: Vec<Range<usize>> = Vec::with_capacity(normalized.len());
+        let mut cursor = 0;
+        for c in normalized.chars() {
+            let found = positions.get_mut(&fold_case(c)).and_then(|(ranges, next)| {
+                while *next < ranges.len() && ranges[*next].start < cursor {
+                    *next += 1;
+                }
+                ranges.get(*next).cloned()
+            });
+
+            let range = match found {
+                Some(range) => {
+                    cursor = range.end;
+                    range
+                }
+                None => cursor..cursor,
+            };
+            offsets.extend(std::iter::repeat_n(range, c.len_utf8()));
+        }
+
+        NormalizedText {
+            text: normalized.to_string(),
+            offsets,
+        }
+    }
+
+    /// The lowercase form of a character, which is at most three characters long.
+    fn fold_case(c: char) -> [char; 3] {
+        let mut folded = ['\0'; 3];
+        for (slot, lower) in folded.iter_mut().zip(c.to_lowercase()) {
+            *slot = lower;
+        }
+        folded
+    }
+
+    /// Normalizes a text with the given normalization function and computes its offset map.
+    ///
+    /// The offset maps of [strip_license] and [normalize_tokens] are computed exactly, any other function goes through [align_normalized].
+    pub fn normalize_with_offsets(text: &str, normalization_fn: fn(&str) -> String) -> NormalizedText {
+        if std::ptr::fn_addr_eq(normalization_fn, strip_license as fn(&str) -> String) {
+            return strip_license_with_offsets(text);
+        }
+        if std::ptr::fn_addr_eq(normalization_fn, normalize_tokens as fn(&str) -> String) {
+            return normalize_tokens_with_offsets(text);
+        }
+        align_normalized(text, &normalization_fn(text))
+    }
+
     pub struct RawLicense {
         pub name: String,
         pub text: String,
+        /// The metadata parsed from the YAML heading of the license file, if it had one.
+        pub metadata: Option<LicenseMetadata>,
+    }
+
+    impl RawLicense {
+        /// Returns the license text without its YAML heading.
+        pub fn body(&self) -> &str {
+            match split_license_heading(&self.text) {
+                Some((_, body)) => body,
+                None => &self.text,
+            }
+        }
     }
 
-    /// loads and returns a vector of RawLicense structs containing the name and plain text of each license.
+    /// loads and returns a vector of RawLicense structs containing the name, plain text and metadata of each license.
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn load_licenses_from_folder(folder_path: &str) -> Vec<RawLicense> {
         let paths = fs::read_dir(folder_path).unwrap();
         let mut licenses: Vec<RawLicense> = Vec::new();
@@ -58,6 +227,7 @@ pub mod license_tools {
 
             licenses.push(RawLicense {
                 name: path.unwrap().file_name().to_str().unwrap().to_string(),
+                metadata: parse_license_heading(&contents),
                 text: contents,
             });
         }
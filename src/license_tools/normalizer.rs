@@ -0,0 +1,184 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+pub mod normalizer {
+    use std::{fmt, sync::Arc};
+
+    use crate::{align_normalized, normalize_with_offsets, BuiltinSegment, NormalizedText};
+
+    /// Turns a license text into the text the detection algorithms hash.
+    ///
+    /// Unlike a plain `fn(&str) -> String`, a normalizer can carry configuration loaded at runtime, such as a stop-word
+    /// list or an equivalence table. Its [id](Normalizer::id) is recorded in the header of saved databases, and a
+    /// database only loads into an algorithm whose normalizer has the same id.
+    pub trait Normalizer: Send + Sync {
+        fn normalize(&self, text: &str) -> String;
+
+        /// A stable identifier of this normalizer and its configuration.
+        ///
+        /// The built-in normalizers use the name of their [BuiltinSegment], see [normalizer_by_id].
+        fn id(&self) -> String;
+
+        /// Same as [normalize](Normalizer::normalize) but also returns the offset map of the normalized text.
+        ///
+        /// Defaults to aligning the normalized text with the original, see [align_normalized].
+        fn normalize_with_offsets(&self, text: &str) -> NormalizedText {
+            align_normalized(text, &self.normalize(text))
+        }
+    }
+
+    impl fmt::Debug for dyn Normalizer {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Normalizer({})", self.id())
+        }
+    }
+
+    /// Plain normalization functions, all identified as `"custom"`.
+    ///
+    /// A function pointer does not reliably tell which function it is, so every function shares that id and is thus not
+    /// checked when a database is loaded. Use the matching [BuiltinSegment] for the built-in functions, e.g.
+    /// [BuiltinSegment::StripLicense] for [strip_license](crate::strip_license), and [FnNormalizer] for any other.
+    impl Normalizer for fn(&str) -> String {
+        fn normalize(&self, text: &str) -> String {
+            self(text)
+        }
+
+        fn id(&self) -> String {
+            "custom".to_string()
+        }
+
+        fn normalize_with_offsets(&self, text: &str) -> NormalizedText {
+            normalize_with_offsets(text, *self)
+        }
+    }
+
+    impl<N: Normalizer + ?Sized> Normalizer for Arc<N> {
+        fn normalize(&self, text: &str) -> String {
+            (**self).normalize(text)
+        }
+
+        fn id(&self) -> String {
+            (**self).id()
+        }
+
+        fn normalize_with_offsets(&self, text: &str) -> NormalizedText {
+            (**self).normalize_with_offsets(text)
+        }
+    }
+
+    /// A normalizer backed by a closure, which may capture its configuration.
+    ///
+    /// ```
+    /// use whichlicense_detection::{strip_license, FnNormalizer, Normalizer};
+    ///
+    /// let stop_words = vec!["the".to_string(), "a".to_string()];
+    /// let normalizer = FnNormalizer::new("strip_license-without-stop-words", move |text: &str| {
+    ///     let words: Vec<&str> = text
+    ///         .split_whitespace()
+    ///         .filter(|word| !stop_words.iter().any(|stop| stop.eq_ignore_ascii_case(word)))
+    ///         .collect();
+    ///     strip_license(&words.join(" "))
+    /// });
+    /// assert_eq!(normalizer.normalize("The MIT License"), "MITLicense");
+    /// ```
+    pub struct FnNormalizer<F> {
+        id: String,
+        func: F,
+    }
+
+    impl<F: Fn(&str) -> String + Send + Sync> FnNormalizer<F> {
+        pub fn new(id: impl Into<String>, func: F) -> Self {
+            Self { id: id.into(), func }
+        }
+    }
+
+    impl<F: Fn(&str) -> String + Send + Sync> Normalizer for FnNormalizer<F> {
+        fn normalize(&self, text: &str) -> String {
+            (self.func)(text)
+        }
+
+        fn id(&self) -> String {
+            self.id.clone()
+        }
+    }
+
+    /// Runs several normalizers one after another.
+    ///
+    /// Its id is the ids of its normalizers joined with `+`, e.g. `strip_markup+normalize_spdx`.
+    /// A chain without normalizers leaves the text unchanged.
+    #[derive(Clone, Default)]
+    pub struct NormalizerChain {
+        normalizers: Vec<Arc<dyn Normalizer>>,
+    }
+
+    impl NormalizerChain {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Appends a normalizer, which runs on the output of the normalizers before it.
+        pub fn then(mut self, normalizer: impl Normalizer + 'static) -> Self {
+            self.normalizers.push(Arc::new(normalizer));
+            self
+        }
+
+        pub fn normalizers(&self) -> &[Arc<dyn Normalizer>] {
+            &self.normalizers
+        }
+    }
+
+    impl Normalizer for NormalizerChain {
+        fn normalize(&self, text: &str) -> String {
+            self.normalizers
+                .iter()
+                .fold(text.to_string(), |text, normalizer| normalizer.normalize(&text))
+        }
+
+        fn id(&self) -> String {
+            self.normalizers.iter().map(|n| n.id()).collect::<Vec<_>>().join("+")
+        }
+
+        fn normalize_with_offsets(&self, text: &str) -> NormalizedText {
+            match self.normalizers.as_slice() {
+                [only] => only.normalize_with_offsets(text),
+                _ => align_normalized(text, &self.normalize(text)),
+            }
+        }
+    }
+
+    impl fmt::Debug for NormalizerChain {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "NormalizerChain({})", self.id())
+        }
+    }
+
+    /// Returns the normalizer identified by `id`: a [BuiltinSegment] by name, or a [NormalizerChain] of them joined with `+`.
+    ///
+    /// Returns `None` for the ids of custom normalizers, those have to be configured again by the caller.
+    pub fn normalizer_by_id(id: &str) -> Option<Arc<dyn Normalizer>> {
+        let mut segments = id.split('+').map(BuiltinSegment::from_name).collect::<Option<Vec<_>>>()?;
+        if segments.len() == 1 {
+            return Some(Arc::new(segments.remove(0)));
+        }
+        Some(Arc::new(NormalizerChain {
+            normalizers: segments
+                .into_iter()
+                .map(|segment| Arc::new(segment) as Arc<dyn Normalizer>)
+                .collect(),
+        }))
+    }
+}
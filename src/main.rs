@@ -15,37 +15,860 @@
 *   limitations under the License.
 */
 
-pub mod detecting;
+use std::{
+    borrow::Cow,
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
-use detecting::detecting::DEFAULT_NORMALIZATION_FN;
-use detecting::fuzzy_implementation::fuzzy_implementation::FuzzyDetection;
-use detecting::gaoya_implementation::gaoya_implementation::GaoyaDetection;
-use whichlicense_detection::{strip_license, LicenseListActions, LicenseMatch, load_licenses_from_folder, strip_spdx_heading};
+use clap::{Parser, Subcommand, ValueEnum};
+use whichlicense_detection::detecting::block_hash_implementation::block_hash_implementation::BlockHashDetection;
+use whichlicense_detection::detecting::fuzzy_implementation::fuzzy_implementation::FuzzyDetection;
+use whichlicense_detection::detecting::gaoya_implementation::gaoya_implementation::GaoyaDetection;
+use whichlicense_detection::{
+    load_licenses_from_folder, normalizer_by_id, read_database_header, detect_spans,
+    scan_directory, AlgorithmParameters, BuiltinSegment, EMBEDDED_FUZZY_DATABASE, EMBEDDED_GAOYA_DATABASE, DatabaseHeader, DetectionError, LicenseListActions, LicenseDiff, LicenseMatch, LicenseSpan, Pipeline,
+    PipelineDefinition, PipelineReport, Policy, ScanOptions, ScanReport, SegmentOptions, Shingling, SpdxMapping, Verdict,
+};
 
-use gaoya::minhash::{MinHashIndex, MinHasher32};
+/// Exit code used when the command ran successfully but no license was detected.
+const EXIT_NOT_DETECTED: u8 = 1;
+/// Exit code used when the command failed, matching the exit code clap uses for usage errors.
+const EXIT_ERROR: u8 = 2;
+/// Exit code used when the detected license is denied by the policy given with `--policy`.
+const EXIT_DENIED: u8 = 3;
 
-fn main() {
-    let mut fuzzy = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
+#[derive(Parser)]
+#[command(version, about = "Detects licenses using the WhichLicense detection algorithms")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Detects the license of a file, use `-` to read from stdin.
+    ///
+    /// Exits with code 1 when no license was detected, and with code 3 when it is denied by `--policy`.
+    Detect {
+        /// The file containing the license text, or `-` for stdin.
+        input: String,
+        #[command(flatten)]
+        database: DatabaseArgs,
+        /// Matches below this confidence (0-100) are discarded.
+        #[arg(long, default_value_t = 50)]
+        min_confidence: u8,
+        /// Only output the given number of top matches (per span with `--spans`).
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Detect every license in the input together with its line span, for files containing multiple licenses.
+        #[arg(long)]
+        spans: bool,
+        /// Output the detected license (or licenses with `--spans`) as an SPDX expression instead.
+        #[arg(long)]
+        spdx: bool,
+        /// Evaluate the detected license (or licenses with `--spans`) against a JSON policy file and output the verdict instead.
+        #[arg(long, conflicts_with = "spdx")]
+        policy: Option<PathBuf>,
+        #[command(flatten)]
+        pipeline: PipelineArgs,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Validates a pipeline definition (JSON, TOML or YAML) and prints it back, optionally converted to another format.
+    Pipeline {
+        file: PathBuf,
+        /// The format to print the pipeline in, defaults to the format of the file.
+        #[arg(long, value_enum)]
+        to: Option<PipelineFormat>,
+    },
+    /// Scans a directory for license, notice and readme files and source file headers.
+    ///
+    /// Exits with code 1 when no license was detected in any file.
+    Scan {
+        /// The directory to scan.
+        directory: PathBuf,
+        #[command(flatten)]
+        database: DatabaseArgs,
+        /// Matches below this confidence (0-100) are discarded.
+        #[arg(long, default_value_t = 50)]
+        min_confidence: u8,
+        /// Gitignore-style pattern of files to skip, can be given multiple times.
+        #[arg(long, short)]
+        exclude: Vec<String>,
+        /// Files larger than this (in bytes) are skipped.
+        #[arg(long, default_value_t = 1024 * 1024)]
+        max_file_size: u64,
+        /// Do not look for license headers in source files.
+        #[arg(long)]
+        no_source_headers: bool,
+        /// Also scan files that are ignored by `.gitignore` files.
+        #[arg(long)]
+        no_gitignore: bool,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Builds a database from a folder of (ScanCode formatted) license files.
+    BuildDb {
+        /// The folder containing one license per file.
+        folder: PathBuf,
+        /// Where to write the database to.
+        #[arg(long, short)]
+        output: PathBuf,
+        #[arg(long, value_enum, default_value_t = Algorithm::Gaoya)]
+        algorithm: Algorithm,
+        #[command(flatten)]
+        hashing: HashingArgs,
+        /// Store the license texts in the database so that `diff` can compare against them.
+        #[arg(long)]
+        reference_texts: bool,
+    },
+    /// Shows a word-level diff between the input and the reference text of the detected license.
+    ///
+    /// Requires a database built with `--reference-texts`.
+    Diff {
+        /// The file containing the license text, or `-` for stdin.
+        input: String,
+        #[command(flatten)]
+        database: DatabaseArgs,
+        /// Diff against this license instead of the top match.
+        #[arg(long)]
+        license: Option<String>,
+        #[arg(long, value_enum, default_value_t = DiffFormat::Text)]
+        format: DiffFormat,
+    },
+    /// Lists the licenses contained in a database.
+    List {
+        #[command(flatten)]
+        database: DatabaseArgs,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Prints the header of a database.
+    Inspect {
+        database: PathBuf,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+}
+
+#[derive(clap::Args)]
+struct DatabaseArgs {
+    /// The database to use, the algorithm and its parameters are read from its header.
+    /// Defaults to the database embedded in this binary.
+    #[arg(long, short)]
+    database: Option<PathBuf>,
+    /// The algorithm to use when no database is given.
+    #[arg(long, value_enum, default_value_t = Algorithm::Gaoya)]
+    algorithm: Algorithm,
+    /// The Jaccard similarity (0-1] the gaoya index requires before comparing a license.
+    #[arg(long, default_value_t = 0.5)]
+    threshold: f64,
+}
+
+/// The parameters the licenses of a new database are hashed with.
+#[derive(clap::Args)]
+struct HashingArgs {
+    /// The normalization function applied to the licenses before hashing them.
+    #[arg(long, value_enum, default_value_t = Normalization::Spdx)]
+    normalization: Normalization,
+    /// The number of bands of the MinHash index (gaoya only).
+    #[arg(long, default_value_t = 42)]
+    bands: usize,
+    /// The number of hashes in each band (gaoya only).
+    #[arg(long, default_value_t = 3)]
+    band_width: usize,
+    /// The number of characters or words in a shingle (gaoya only).
+    #[arg(long, default_value_t = 50)]
+    shingle_size: usize,
+    /// Whether to shingle characters or words, words require `--normalization tokens` (gaoya only).
+    #[arg(long, value_enum, default_value_t = ShinglingArg::Characters)]
+    shingling: ShinglingArg,
+    /// The number of bytes in each block (block-hash only).
+    #[arg(long, default_value_t = 8)]
+    block_size: usize,
+    /// The number of bytes of each block that are hashed (block-hash only).
+    #[arg(long, default_value_t = 8)]
+    hash_length: usize,
+}
+
+#[derive(clap::Args)]
+struct PipelineArgs {
+    /// Run the pipeline of a JSON, TOML or YAML file on the input and use the matches of its last run.
+    #[arg(long, conflicts_with = "spans")]
+    pipeline: Option<PathBuf>,
+    /// The confidence (0-100) at which the pipeline stops running, values above 100 run every segment.
+    #[arg(long, default_value_t = 100.0, requires = "pipeline")]
+    desired_confidence: f32,
+    /// Output a report of every pipeline step (the segment, the text length, the top match and the time taken) and the best match instead.
+    #[arg(long, requires = "pipeline", conflicts_with_all = ["spdx", "policy"])]
+    explain: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Algorithm {
+    Fuzzy,
+    Gaoya,
+    BlockHash,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Normalization {
+    /// Follow the SPDX License List Matching Guidelines, see `normalize_spdx`.
+    Spdx,
+    /// Keep only ASCII letters and digits, see `strip_license`, the embedded databases use this.
+    StripLicense,
+    /// Case-folded Unicode words separated by spaces, see `normalize_tokens`.
+    Tokens,
+}
+
+impl Normalization {
+    fn segment(self) -> BuiltinSegment {
+        match self {
+            Self::Spdx => BuiltinSegment::NormalizeSpdx,
+            Self::StripLicense => BuiltinSegment::StripLicense,
+            Self::Tokens => BuiltinSegment::NormalizeTokens,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ShinglingArg {
+    Characters,
+    Words,
+}
+
+impl From<ShinglingArg> for Shingling {
+    fn from(shingling: ShinglingArg) -> Self {
+        match shingling {
+            ShinglingArg::Characters => Self::Characters,
+            ShinglingArg::Words => Self::Words,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum PipelineFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl PipelineFormat {
+    fn from_path(path: &Path) -> Result<Self, DetectionError> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Ok(Self::Json),
+            Some("toml") => Ok(Self::Toml),
+            Some("yaml" | "yml") => Ok(Self::Yaml),
+            _ => Err(DetectionError::InvalidParameter(format!(
+                "unknown pipeline format of {}, expected a .json, .toml or .yaml file",
+                path.display()
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DiffFormat {
+    Text,
+    Html,
+    Json,
+}
+
+enum Detector {
+    Fuzzy(FuzzyDetection),
+    Gaoya(Box<GaoyaDetection>),
+    BlockHash(BlockHashDetection),
+}
+
+impl Detector {
+    /// Loads the detector described by the database's header, or the embedded database of the given algorithm.
+    ///
+    /// The embedded databases go through the same builders as a given database, so the options are validated either way.
+    fn load(args: &DatabaseArgs, min_confidence: u8) -> Result<Self, DetectionError> {
+        let raw: Cow<'static, [u8]> = match &args.database {
+            Some(path) => Cow::Owned(read_file(path)?),
+            None => match args.algorithm {
+                Algorithm::Fuzzy => Cow::Borrowed(EMBEDDED_FUZZY_DATABASE),
+                Algorithm::Gaoya => Cow::Borrowed(EMBEDDED_GAOYA_DATABASE),
+                Algorithm::BlockHash => {
+                    return Err(DetectionError::InvalidParameter(
+                        "there is no embedded block-hash database, pass one with --database".to_string(),
+                    ))
+                }
+            },
+        };
+        let header = read_database_header(&raw)?;
+        let normalizer = normalizer_by_id(&header.normalization).ok_or_else(|| {
+            DetectionError::IncompatibleFormat(format!(
+                "database was normalized with '{}' which is not available from the command line",
+                header.normalization
+            ))
+        })?;
+
+        let mut detector = match header.parameters {
+            AlgorithmParameters::Fuzzy => Self::Fuzzy(
+                FuzzyDetection::builder()
+                    .min_confidence(min_confidence)
+                    .normalizer(normalizer)
+                    .build()?,
+            ),
+            AlgorithmParameters::Gaoya {
+                num_bands,
+                band_width,
+                shingle_text_size,
+                shingling,
+            } => Self::Gaoya(Box::new(
+                GaoyaDetection::builder()
+                    .bands(num_bands)
+                    .band_width(band_width)
+                    .shingle_size(shingle_text_size)
+                    .shingling(shingling)
+                    .threshold(args.threshold)
+                    .min_confidence(min_confidence)
+                    .normalizer(normalizer)
+                    .build()?,
+            )),
+            AlgorithmParameters::BlockHash { block_size, hash_length } => Self::BlockHash(
+                BlockHashDetection::builder()
+                    .block_size(block_size)
+                    .hash_length(hash_length)
+                    .min_confidence(min_confidence)
+                    .normalizer(normalizer)
+                    .build()?,
+            ),
+        };
+        match &mut detector {
+            Self::Fuzzy(fuzzy) => fuzzy.try_load_from_memory(&raw)?,
+            Self::Gaoya(gaoya) => gaoya.try_load_from_memory(&raw)?,
+            Self::BlockHash(block_hash) => block_hash.try_load_from_memory(&raw)?,
+        }
+        Ok(detector)
+    }
+
+    fn match_by_plain_text(&self, text: &str) -> Vec<LicenseMatch> {
+        match self {
+            Self::Fuzzy(fuzzy) => fuzzy.match_by_plain_text(text),
+            Self::Gaoya(gaoya) => gaoya.match_by_plain_text(text),
+            Self::BlockHash(block_hash) => block_hash.match_by_plain_text(text),
+        }
+    }
+
+    fn run_pipeline_with_report(&self, pipeline: &Pipeline, text: &str, desired_confidence: f32) -> PipelineReport {
+        match self {
+            Self::Fuzzy(fuzzy) => pipeline.run_with_report(fuzzy, text, desired_confidence),
+            Self::Gaoya(gaoya) => pipeline.run_with_report(gaoya.as_ref(), text, desired_confidence),
+            Self::BlockHash(block_hash) => pipeline.run_with_report(block_hash, text, desired_confidence),
+        }
+    }
+
+    fn scan(&self, directory: &Path, options: &ScanOptions) -> Result<ScanReport, DetectionError> {
+        match self {
+            Self::Fuzzy(fuzzy) => scan_directory(directory, fuzzy, options),
+            Self::Gaoya(gaoya) => scan_directory(directory, gaoya.as_ref(), options),
+            Self::BlockHash(block_hash) => scan_directory(directory, block_hash, options),
+        }
+    }
+
+    fn detect_spans(&self, text: &str, options: &SegmentOptions) -> Vec<LicenseSpan> {
+        match self {
+            Self::Fuzzy(fuzzy) => detect_spans(fuzzy, text, options),
+            Self::Gaoya(gaoya) => detect_spans(gaoya.as_ref(), text, options),
+            Self::BlockHash(block_hash) => detect_spans(block_hash, text, options),
+        }
+    }
+
+    fn diff_match(&self, license_match: &LicenseMatch, input: &str) -> Result<LicenseDiff, DetectionError> {
+        match self {
+            Self::Fuzzy(fuzzy) => fuzzy.diff_match(license_match, input),
+            Self::Gaoya(gaoya) => gaoya.diff_match(license_match, input),
+            Self::BlockHash(block_hash) => block_hash.diff_match(license_match, input),
+        }
+    }
+
+    fn license_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = match self {
+            Self::Fuzzy(fuzzy) => fuzzy.get_license_list().into_iter().map(|(name, _)| name).collect(),
+            Self::Gaoya(gaoya) => gaoya.get_license_list().into_iter().map(|(name, _)| name).collect(),
+            Self::BlockHash(block_hash) => block_hash.get_license_list().into_iter().map(|(name, _)| name).collect(),
+        };
+        names.sort_unstable();
+        names
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, DetectionError> {
+    fs::read(path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => DetectionError::FileNotFound(path.display().to_string()),
+        _ => DetectionError::Io(e),
+    })
+}
+
+fn read_input(input: &str) -> Result<String, DetectionError> {
+    if input == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        return Ok(text);
+    }
+    let raw = read_file(Path::new(input))?;
+    Ok(String::from_utf8_lossy(&raw).into_owned())
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).expect("output types serialize to JSON")
+}
+
+/// Writes the output of a command to stdout, a closed pipe (e.g. `| head`) is not considered an error.
+fn print_output(output: &str) -> Result<(), DetectionError> {
+    match io::stdout().lock().write_all(output.as_bytes()) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(DetectionError::Io(e)),
+        _ => Ok(()),
+    }
+}
+
+/// Prints the SPDX expression of the given matches, exits with 1 when there are none.
+fn print_spdx(matches: &[&LicenseMatch], format: Format) -> Result<ExitCode, DetectionError> {
+    let expression = match SpdxMapping::default().expression_of_matches(matches) {
+        Some(expression) => expression,
+        None => return Ok(ExitCode::from(EXIT_NOT_DETECTED)),
+    };
+    let output = match format {
+        Format::Text => format!("{}\n", expression),
+        Format::Json => to_json(&serde_json::json!({ "expression": expression })) + "\n",
     };
+    print_output(&output)?;
+    Ok(ExitCode::SUCCESS)
+}
 
-    for l in load_licenses_from_folder("./licenses/RAW") {
-        fuzzy.add_plain(&l.name, &strip_spdx_heading(&l.text));
+/// Reads a JSON policy file.
+fn load_policy(path: &Path) -> Result<Policy, DetectionError> {
+    let raw = read_file(path)?;
+    serde_json::from_slice(&raw)
+        .map_err(|e| DetectionError::InvalidParameter(format!("invalid policy {}: {}", path.display(), e)))
+}
+
+/// Reads a pipeline definition, the format is determined by the extension of the file.
+fn load_pipeline_definition(path: &Path) -> Result<(PipelineDefinition, PipelineFormat), DetectionError> {
+    let format = PipelineFormat::from_path(path)?;
+    let raw = String::from_utf8_lossy(&read_file(path)?).into_owned();
+    let definition = match format {
+        PipelineFormat::Json => serde_json::from_str(&raw).map_err(|e| e.to_string()),
+        PipelineFormat::Toml => toml::from_str(&raw).map_err(|e| e.to_string()),
+        PipelineFormat::Yaml => serde_yaml::from_str(&raw).map_err(|e| e.to_string()),
+    }
+    .map_err(|e| DetectionError::InvalidParameter(format!("invalid pipeline {}: {}", path.display(), e)))?;
+    Ok((definition, format))
+}
+
+/// Reads a pipeline definition and validates its segments.
+fn load_pipeline(path: &Path) -> Result<Pipeline, DetectionError> {
+    load_pipeline_definition(path)?.0.build()
+}
+
+fn serialize_pipeline(definition: &PipelineDefinition, format: PipelineFormat) -> Result<String, DetectionError> {
+    match format {
+        PipelineFormat::Json => Ok(to_json(definition) + "\n"),
+        PipelineFormat::Toml => toml::to_string_pretty(definition).map_err(|e| e.to_string()),
+        PipelineFormat::Yaml => serde_yaml::to_string(definition).map_err(|e| e.to_string()),
+    }
+    .map_err(|e| DetectionError::InvalidParameter(format!("can not serialize the pipeline: {}", e)))
+}
+
+fn pipeline(file: &Path, to: Option<PipelineFormat>) -> Result<ExitCode, DetectionError> {
+    let (definition, format) = load_pipeline_definition(file)?;
+    definition.build()?;
+    print_output(&serialize_pipeline(&definition, to.unwrap_or(format))?)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints every step of the pipeline report and its best match, exits with 1 when nothing was detected in any step.
+fn print_pipeline_report(report: &PipelineReport, format: Format) -> Result<ExitCode, DetectionError> {
+    let output = match format {
+        Format::Text => {
+            let mut output: String = report
+                .steps
+                .iter()
+                .enumerate()
+                .map(|(index, step)| {
+                    let (name, confidence) = match step.matches.first() {
+                        Some(top_match) => (top_match.name.as_str(), top_match.confidence),
+                        None => ("-", 0.0),
+                    };
+                    format!(
+                        "{}\t{}\t{} -> {}\t{}\t{:.2}\t{:.2}ms\n",
+                        index,
+                        step.segment_name.unwrap_or("-"),
+                        step.length_before,
+                        step.length_after,
+                        name,
+                        confidence,
+                        step.duration.as_secs_f64() * 1000.0
+                    )
+                })
+                .collect();
+            if let Some(best) = &report.best {
+                output += &format!(
+                    "best\t{}\t{}\t{:.2}\n",
+                    best.step, best.license_match.name, best.license_match.confidence
+                );
+            }
+            output + &format!("short-circuited\t{}\n", report.short_circuited)
+        }
+        Format::Json => to_json(report) + "\n",
+    };
+    print_output(&output)?;
+    Ok(match report.best {
+        Some(_) => ExitCode::SUCCESS,
+        None => ExitCode::from(EXIT_NOT_DETECTED),
+    })
+}
+
+/// Prints the verdict of the policy on the given matches, exits with 3 when they are denied.
+fn print_policy_decision(policy: &Policy, matches: &[&LicenseMatch], format: Format) -> Result<ExitCode, DetectionError> {
+    let decision = policy.evaluate_matches(matches);
+    let output = match format {
+        Format::Text => {
+            let expression = decision.expression.as_ref().map(|e| e.to_string()).unwrap_or_else(|| "-".to_string());
+            let mut output = format!("{}\t{}\n", decision.verdict, expression);
+            for reason in decision.reasons.iter() {
+                output += &format!("  {}: {}\n", reason.verdict, reason.message);
+            }
+            output
+        }
+        Format::Json => to_json(&decision) + "\n",
+    };
+    print_output(&output)?;
+    Ok(match decision.verdict {
+        Verdict::Deny => ExitCode::from(EXIT_DENIED),
+        _ => ExitCode::SUCCESS,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn detect(
+    input: &str,
+    database: &DatabaseArgs,
+    min_confidence: u8,
+    limit: Option<usize>,
+    spdx: bool,
+    policy: Option<&Path>,
+    pipeline_args: &PipelineArgs,
+    format: Format,
+) -> Result<ExitCode, DetectionError> {
+    let policy = policy.map(load_policy).transpose()?;
+    let pipeline = pipeline_args.pipeline.as_deref().map(load_pipeline).transpose()?;
+    let detector = Detector::load(database, min_confidence)?;
+    let text = read_input(input)?;
+    let mut matches = match pipeline {
+        Some(pipeline) => {
+            let report = detector.run_pipeline_with_report(&pipeline, &text, pipeline_args.desired_confidence);
+            if pipeline_args.explain {
+                return print_pipeline_report(&report, format);
+            }
+            report.into_runs().pop().unwrap_or_default()
+        }
+        None => detector.match_by_plain_text(&text),
+    };
+    if let Some(policy) = policy {
+        let top: Vec<&LicenseMatch> = matches.iter().take(1).collect();
+        return print_policy_decision(&policy, &top, format);
+    }
+    if spdx {
+        let top: Vec<&LicenseMatch> = matches.iter().take(1).collect();
+        return print_spdx(&top, format);
+    }
+    if let Some(limit) = limit {
+        matches.truncate(limit);
+    }
+
+    let output = match format {
+        Format::Text => matches
+            .iter()
+            .map(|m| format!("{}\t{:.2}\n", m.name, m.confidence))
+            .collect(),
+        Format::Json => to_json(&matches) + "\n",
+    };
+    print_output(&output)?;
+
+    Ok(if matches.is_empty() {
+        ExitCode::from(EXIT_NOT_DETECTED)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn detect_license_spans(
+    input: &str,
+    database: &DatabaseArgs,
+    min_confidence: u8,
+    limit: Option<usize>,
+    spdx: bool,
+    policy: Option<&Path>,
+    format: Format,
+) -> Result<ExitCode, DetectionError> {
+    let policy = policy.map(load_policy).transpose()?;
+    let detector = Detector::load(database, min_confidence)?;
+    let options = SegmentOptions {
+        min_confidence: min_confidence as f32,
+        ..Default::default()
+    };
+    let mut spans = detector.detect_spans(&read_input(input)?, &options);
+    if let Some(policy) = policy {
+        let top: Vec<&LicenseMatch> = spans.iter().map(|span| span.license()).collect();
+        return print_policy_decision(&policy, &top, format);
+    }
+    if spdx {
+        let top: Vec<&LicenseMatch> = spans.iter().map(|span| span.license()).collect();
+        return print_spdx(&top, format);
+    }
+    if let Some(limit) = limit {
+        spans.iter_mut().for_each(|span| span.matches.truncate(limit));
     }
-    fuzzy.save_to_file("./licenses/fuzzy_db");
 
-    let mut gaoya = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
+    let output = match format {
+        Format::Text => spans
+            .iter()
+            .flat_map(|span| {
+                span.matches
+                    .iter()
+                    .map(move |m| format!("{}-{}\t{}\t{:.2}\n", span.start_line, span.end_line, m.name, m.confidence))
+            })
+            .collect(),
+        Format::Json => to_json(&spans) + "\n",
+    };
+    print_output(&output)?;
+
+    Ok(if spans.is_empty() {
+        ExitCode::from(EXIT_NOT_DETECTED)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn scan(directory: &Path, database: &DatabaseArgs, min_confidence: u8, options: &ScanOptions, format: Format) -> Result<ExitCode, DetectionError> {
+    let detector = Detector::load(database, min_confidence)?;
+    let report = detector.scan(directory, options)?;
+
+    let output = match format {
+        Format::Text => report
+            .files
+            .iter()
+            .map(|file| match file.matches.first() {
+                Some(top) => format!("{}\t{}\t{:.2}\n", file.path.display(), top.name, top.confidence),
+                None => format!("{}\t-\n", file.path.display()),
+            })
+            .collect(),
+        Format::Json => to_json(&report) + "\n",
+    };
+    print_output(&output)?;
+    if format == Format::Text {
+        for skipped in report.skipped.iter() {
+            eprintln!("skipped {}: {:?}", skipped.path.display(), skipped.reason);
+        }
+    }
+
+    Ok(if report.detected().next().is_none() {
+        ExitCode::from(EXIT_NOT_DETECTED)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn build_db(
+    folder: &Path,
+    output: &Path,
+    algorithm: Algorithm,
+    hashing: &HashingArgs,
+    reference_texts: bool,
+) -> Result<ExitCode, DetectionError> {
+    if !folder.is_dir() {
+        return Err(DetectionError::FileNotFound(folder.display().to_string()));
+    }
+    let licenses = load_licenses_from_folder(&folder.to_string_lossy());
+    let output = output.to_string_lossy();
+
+    match algorithm {
+        Algorithm::Fuzzy => {
+            let mut fuzzy = FuzzyDetection::builder()
+                .normalizer(hashing.normalization.segment())
+                .store_reference_texts(reference_texts)
+                .build()?;
+            for l in licenses.iter() {
+                fuzzy.add_raw_license(l);
+            }
+            fuzzy.try_save_to_file(&output)?;
+        }
+        Algorithm::Gaoya => {
+            let mut gaoya = GaoyaDetection::builder()
+                .bands(hashing.bands)
+                .band_width(hashing.band_width)
+                .shingle_size(hashing.shingle_size)
+                .shingling(hashing.shingling.into())
+                .normalizer(hashing.normalization.segment())
+                .store_reference_texts(reference_texts)
+                .build()?;
+            for l in licenses.iter() {
+                gaoya.add_raw_license(l);
+            }
+            gaoya.try_save_to_file(&output)?;
+        }
+        Algorithm::BlockHash => {
+            let mut block_hash = BlockHashDetection::builder()
+                .block_size(hashing.block_size)
+                .hash_length(hashing.hash_length)
+                .normalizer(hashing.normalization.segment())
+                .store_reference_texts(reference_texts)
+                .build()?;
+            for l in licenses.iter() {
+                block_hash.add_raw_license(l);
+            }
+            block_hash.try_save_to_file(&output)?;
+        }
+    }
+
+    eprintln!("wrote {} licenses to {}", licenses.len(), output);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn diff(input: &str, database: &DatabaseArgs, license: Option<&str>, format: DiffFormat) -> Result<ExitCode, DetectionError> {
+    let detector = Detector::load(database, 0)?;
+    let text = read_input(input)?;
+
+    let license_match = match license {
+        Some(name) => LicenseMatch {
+            name: name.to_string(),
+            confidence: 0.0,
+            metadata: None,
+        },
+        None => match detector.match_by_plain_text(&text).into_iter().next() {
+            Some(top) => top,
+            None => return Ok(ExitCode::from(EXIT_NOT_DETECTED)),
+        },
+    };
+    let diff = detector.diff_match(&license_match, &text)?;
+
+    let output = match format {
+        DiffFormat::Text => diff.to_terminal() + "\n",
+        DiffFormat::Html => format!("<pre>{}</pre>\n", diff.to_html()),
+        DiffFormat::Json => to_json(&diff) + "\n",
+    };
+    print_output(&output)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn list(database: &DatabaseArgs, format: Format) -> Result<ExitCode, DetectionError> {
+    let names = Detector::load(database, 0)?.license_names();
+    let output = match format {
+        Format::Text => names.iter().map(|name| format!("{}\n", name)).collect(),
+        Format::Json => to_json(&names) + "\n",
+    };
+    print_output(&output)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn inspect(database: &Path, format: Format) -> Result<ExitCode, DetectionError> {
+    let raw = read_file(database)?;
+    let header: DatabaseHeader = read_database_header(&raw)?;
+
+    let output = match format {
+        Format::Text => {
+            let mut output = format!("format version: {}\n", header.format_version);
+            output += &format!("algorithm:      {}\n", header.algorithm);
+            if let AlgorithmParameters::Gaoya {
+                num_bands,
+                band_width,
+                shingle_text_size,
+                shingling,
+            } = header.parameters
+            {
+                output += &format!("bands:          {}\n", num_bands);
+                output += &format!("band width:     {}\n", band_width);
+                output += &format!("shingle size:   {}\n", shingle_text_size);
+                output += &format!("shingling:      {}\n", shingling);
+            }
+            if let AlgorithmParameters::BlockHash { block_size, hash_length } = header.parameters {
+                output += &format!("block size:     {}\n", block_size);
+                output += &format!("hash length:    {}\n", hash_length);
+            }
+            output += &format!("normalization:  {}\n", header.normalization);
+            output += &format!("licenses:       {}\n", header.license_count);
+            output += &format!("size:           {} bytes\n", raw.len());
+            output
+        }
+        Format::Json => to_json(&header) + "\n",
+    };
+    print_output(&output)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let res = match &cli.command {
+        Command::Detect {
+            input,
+            database,
+            min_confidence,
+            limit,
+            spans,
+            spdx,
+            policy,
+            pipeline,
+            format,
+        } => match spans {
+            true => detect_license_spans(input, database, *min_confidence, *limit, *spdx, policy.as_deref(), *format),
+            false => detect(input, database, *min_confidence, *limit, *spdx, policy.as_deref(), pipeline, *format),
+        },
+        Command::Pipeline { file, to } => pipeline(file, *to),
+        Command::Scan {
+            directory,
+            database,
+            min_confidence,
+            exclude,
+            max_file_size,
+            no_source_headers,
+            no_gitignore,
+            format,
+        } => {
+            let options = ScanOptions {
+                excludes: exclude.clone(),
+                respect_gitignore: !no_gitignore,
+                max_file_size: *max_file_size,
+                scan_source_headers: !no_source_headers,
+                ..Default::default()
+            };
+            scan(directory, database, *min_confidence, &options, *format)
+        }
+        Command::BuildDb {
+            folder,
+            output,
+            algorithm,
+            hashing,
+            reference_texts,
+        } => build_db(folder, output, *algorithm, hashing, *reference_texts),
+        Command::Diff {
+            input,
+            database,
+            license,
+            format,
+        } => diff(input, database, license.as_deref(), *format),
+        Command::List { database, format } => list(database, *format),
+        Command::Inspect { database, format } => inspect(database, *format),
     };
 
-    for l in load_licenses_from_folder("./licenses/RAW") {
-        gaoya.add_plain(&l.name, &strip_spdx_heading(&l.text));
+    match res {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
     }
-    gaoya.save_to_file("./licenses/gaoya_db");
 }
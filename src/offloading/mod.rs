@@ -0,0 +1,245 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+pub mod threaded_detection {
+    use std::{
+        collections::BTreeMap,
+        num::NonZeroUsize,
+        sync::{mpsc, Arc, Condvar, Mutex, PoisonError},
+        thread,
+    };
+
+    use serde::Serialize;
+
+    use crate::{LicenseListActions, LicenseMatch, Pipeline};
+
+    /// Configures the worker pool of the batch functions.
+    #[derive(Debug, Clone, Copy)]
+    pub struct BatchOptions {
+        /// The number of worker threads, `0` uses the available parallelism of the machine. Defaults to 0.
+        pub threads: usize,
+        /// The maximum number of inputs that are queued, being matched or waiting for an earlier input to finish.
+        /// Bounds the memory used for inputs that are produced lazily (e.g. read from disk). Defaults to 64.
+        pub max_in_flight: usize,
+    }
+
+    impl Default for BatchOptions {
+        fn default() -> Self {
+            Self {
+                threads: 0,
+                max_in_flight: 64,
+            }
+        }
+    }
+
+    impl BatchOptions {
+        fn worker_count(&self) -> usize {
+            match self.threads {
+                0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+                threads => threads,
+            }
+        }
+    }
+
+    /// Runs [LicenseListActions::match_by_plain_text] on every input using a pool of worker threads.
+    ///
+    /// The results are in the order of the inputs. Inputs are pulled from the iterator as workers become available,
+    /// at most [BatchOptions::max_in_flight] inputs are held in memory at once.
+    /// ```
+    /// use whichlicense_detection::*;
+    /// use whichlicense_detection::detecting::fuzzy_implementation::fuzzy_implementation::FuzzyDetection;
+    ///
+    /// let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    /// fuzzy.add_plain("apache-2.0", &std::fs::read_to_string("./LICENSE").unwrap());
+    ///
+    /// let files = ["./LICENSE", "./Cargo.toml"];
+    /// let inputs = files.iter().map(|f| std::fs::read_to_string(f).unwrap());
+    /// let results = match_batch(&fuzzy, inputs, &BatchOptions::default());
+    /// assert_eq!(results[0][0].name, "apache-2.0");
+    /// assert!(results[1].is_empty());
+    /// ```
+    pub fn match_batch<T, A, I>(alg: &A, inputs: I, options: &BatchOptions) -> Vec<Vec<LicenseMatch>>
+    where
+        T: Serialize,
+        A: LicenseListActions<T> + Sync + ?Sized,
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: AsRef<str> + Send,
+    {
+        let mut results = Vec::new();
+        match_batch_with(alg, inputs, options, |_, matches| results.push(matches));
+        results
+    }
+
+    /// Same as [match_batch] but hands every result to `on_result` (together with the index of its input) instead of collecting them.
+    ///
+    /// `on_result` is called on the calling thread in the order of the inputs, allowing the results of large batches to be
+    /// written out as they come in.
+    pub fn match_batch_with<T, A, I, F>(alg: &A, inputs: I, options: &BatchOptions, on_result: F)
+    where
+        T: Serialize,
+        A: LicenseListActions<T> + Sync + ?Sized,
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: AsRef<str> + Send,
+        F: FnMut(usize, Vec<LicenseMatch>),
+    {
+        process_ordered(inputs, options, |input| alg.match_by_plain_text(input.as_ref()), on_result);
+    }
+
+    /// Runs the pipeline on every input using a pool of worker threads, see [Pipeline::run] and [match_batch].
+    pub fn run_pipeline_batch<T, A, I>(
+        pipeline: &Pipeline,
+        alg: &A,
+        inputs: I,
+        desired_confidence: f32,
+        options: &BatchOptions,
+    ) -> Vec<Vec<Vec<LicenseMatch>>>
+    where
+        T: Serialize,
+        A: LicenseListActions<T> + Sync,
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: AsRef<str> + Send,
+    {
+        let mut results = Vec::new();
+        process_ordered(
+            inputs,
+            options,
+            |input| pipeline.run(alg, input.as_ref(), desired_confidence),
+            |_, result| results.push(result),
+        );
+        results
+    }
+
+    struct Progress {
+        /// The number of results handed to the caller.
+        emitted: usize,
+        /// Set when no more results are accepted, e.g. because a worker panicked.
+        stopped: bool,
+    }
+
+    /// Stops the feeder once the results are no longer collected (also when the caller's callback panics)
+    /// or, with `only_when_panicking`, once a worker panics as the result it was working on will never arrive.
+    struct StopOnDrop<'a> {
+        progress: &'a (Mutex<Progress>, Condvar),
+        only_when_panicking: bool,
+    }
+
+    impl Drop for StopOnDrop<'_> {
+        fn drop(&mut self) {
+            if self.only_when_panicking && !thread::panicking() {
+                return;
+            }
+            let (lock, condvar) = self.progress;
+            lock.lock().unwrap_or_else(PoisonError::into_inner).stopped = true;
+            condvar.notify_all();
+        }
+    }
+
+    /// Applies `work` to every input on a pool of worker threads and hands the results to `on_result` in the order of the inputs.
+    ///
+    /// A feeder thread pulls inputs from the iterator, but only while fewer than `max_in_flight` inputs are queued,
+    /// being worked on or waiting for an earlier result. A panic in `work` is propagated once every thread has stopped.
+    fn process_ordered<I, R, W, F>(inputs: I, options: &BatchOptions, work: W, mut on_result: F)
+    where
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: Send,
+        R: Send,
+        W: Fn(I::Item) -> R + Sync,
+        F: FnMut(usize, R),
+    {
+        let max_in_flight = options.max_in_flight.max(1);
+        let (task_sender, task_receiver) = mpsc::sync_channel::<(usize, I::Item)>(max_in_flight);
+        // shared by the workers, dropped (ending the feeder) once all of them have stopped.
+        let task_receiver = Arc::new(Mutex::new(task_receiver));
+        let (result_sender, result_receiver) = mpsc::sync_channel::<(usize, R)>(max_in_flight);
+        let progress = (
+            Mutex::new(Progress {
+                emitted: 0,
+                stopped: false,
+            }),
+            Condvar::new(),
+        );
+        let inputs = inputs.into_iter();
+
+        thread::scope(|scope| {
+            let progress = &progress;
+            scope.spawn(move || {
+                let (lock, condvar) = progress;
+                for (index, input) in inputs.enumerate() {
+                    let mut state = lock.lock().unwrap_or_else(PoisonError::into_inner);
+                    while index >= state.emitted + max_in_flight && !state.stopped {
+                        state = condvar.wait(state).unwrap_or_else(PoisonError::into_inner);
+                    }
+                    if state.stopped {
+                        return;
+                    }
+                    drop(state);
+                    if task_sender.send((index, input)).is_err() {
+                        return;
+                    }
+                }
+            });
+
+            let work = &work;
+            for _ in 0..options.worker_count() {
+                let task_receiver = Arc::clone(&task_receiver);
+                let result_sender = result_sender.clone();
+                scope.spawn(move || {
+                    let _stop = StopOnDrop {
+                        progress,
+                        only_when_panicking: true,
+                    };
+                    loop {
+                        // the lock is released before working on the task.
+                        let task = task_receiver.lock().unwrap_or_else(PoisonError::into_inner).recv();
+                        let Ok((index, input)) = task else {
+                            return;
+                        };
+                        if result_sender.send((index, work(input))).is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+            drop(task_receiver);
+            drop(result_sender);
+
+            let _stop = StopOnDrop {
+                progress,
+                only_when_panicking: false,
+            };
+            let mut pending: BTreeMap<usize, R> = BTreeMap::new();
+            let mut next = 0;
+            for (index, result) in result_receiver.iter() {
+                pending.insert(index, result);
+                let emitted = next;
+                while let Some(result) = pending.remove(&next) {
+                    on_result(next, result);
+                    next += 1;
+                }
+                if next != emitted {
+                    let (lock, condvar) = progress;
+                    lock.lock().unwrap_or_else(PoisonError::into_inner).emitted = next;
+                    condvar.notify_all();
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,246 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+pub mod builtin {
+    use std::{fmt, sync::LazyLock};
+
+    use regex::{Captures, Regex};
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        align_normalized, normalize_spdx, normalize_tokens, normalize_tokens_with_offsets, strip_license,
+        strip_license_with_offsets, strip_spdx_heading, NormalizedText, Normalizer,
+    };
+
+    /// A named transform shipped with the library, referenced by its snake_case name in pipeline definitions.
+    ///
+    /// Built-in segments are also [Normalizer]s identified by their name, which is how databases built with
+    /// them (or a [NormalizerChain](crate::NormalizerChain) of them) are loaded again, see [normalizer_by_id](crate::normalizer_by_id).
+    ///
+    /// Every built-in segment also has a constructor on [Segment](crate::Segment), e.g. [Segment::strip_copyright_lines](crate::Segment::strip_copyright_lines).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum BuiltinSegment {
+        /// Normalizes the text the way the embedded databases were built, see [strip_license].
+        StripLicense,
+        /// Splits the text into Unicode words and normalizes them, see [normalize_tokens].
+        NormalizeTokens,
+        /// Normalizes the text following the SPDX matching guidelines, see [normalize_spdx].
+        NormalizeSpdx,
+        /// Removes the YAML heading of a ScanCode license file, see [strip_spdx_heading].
+        StripSpdxHeading,
+        /// Removes every line starting with `Copyright`, `(c)` or `©` (optionally behind a comment marker).
+        StripCopyrightLines,
+        /// Removes the comment markers `//`, `#`, `/*`, `*/`, leading `*`, `;;`, `--` and `REM` from the start (and `*/` from the end) of every line.
+        ///
+        /// Lines ending in a comment that also opened on them after some code (e.g. `code(); /* note */`) are left alone.
+        StripCommentMarkers,
+        /// Removes every "All rights reserved".
+        StripAllRightsReserved,
+        /// Removes `http(s)://`, `ftp://` and `www.` URLs.
+        RemoveUrls,
+        /// Removes list bullets (`-`, `*`, `+`, `•`) and numbering (`1.`, `1.2`, `(a)`, `iv)`) from the start of every line.
+        RemoveBullets,
+        /// Replaces every run of whitespace with a single space and trims the text.
+        CollapseWhitespace,
+        /// Removes HTML tags and comments (decoding common entities) and Markdown headings, emphasis, links, code and quotes.
+        ///
+        /// Block-level tags (`p`, `br`, `h1`-`h6`, `div`, `li`, `tr`) are replaced with a line break so adjacent words stay apart.
+        StripMarkup,
+        /// Replaces the years and holders on copyright lines, as well as the placeholders license templates use for them
+        /// (e.g. `[yyyy]` and `[name of copyright owner]`), with `<year>` and `<copyright holders>`.
+        ReplaceYearAndHolder,
+    }
+
+    fn regex(pattern: &str) -> Regex {
+        Regex::new(pattern).expect("built-in segment patterns are valid")
+    }
+
+    static COPYRIGHT_LINE: LazyLock<Regex> = LazyLock::new(|| {
+        regex(r"(?im)^[ \t]*(?:(?:/\*+|//+|#+|;;+|--+|\*+)[ \t]*)?(?:copyright\b|\(c\)|©).*(?:\r?\n|$)")
+    });
+    static LEADING_COMMENT_MARKER: LazyLock<Regex> =
+        LazyLock::new(|| regex(r"(?m)^[ \t]*(?:/\*+|\*+/|//+|#+|;;+|--+|(?i:rem)\b|\*+)[ \t]?"));
+    static TRAILING_COMMENT_MARKER: LazyLock<Regex> = LazyLock::new(|| regex(r"(?m)^([^\n]*?)[ \t]*\*+/[ \t]*$"));
+    static ALL_RIGHTS_RESERVED: LazyLock<Regex> = LazyLock::new(|| regex(r"(?i)\ball\s+rights\s+reserved\b\.?"));
+    static URL: LazyLock<Regex> = LazyLock::new(|| {
+        regex(r#"(?i)\b(?:(?:https?|ftp)://|www\.)[^\s<>"'()\[\]]*[^\s<>"'()\[\].,;:!?]"#)
+    });
+    static BULLET: LazyLock<Regex> = LazyLock::new(|| {
+        regex(r"(?m)^[ \t]*(?:[-*+•◦‣▪]|\d+(?:\.\d+)+[.)]?|\(?\d+[.)]|\(?[a-zA-Z][.)]|\(?(?i:[ivxlcdm]+)[.)])[ \t]+")
+    });
+    static HTML_COMMENT: LazyLock<Regex> = LazyLock::new(|| regex(r"(?s)<!--.*?-->"));
+    static HTML_BLOCK_TAG: LazyLock<Regex> = LazyLock::new(|| regex(r"(?i)</?(?:br|div|h[1-6]|li|p|tr)\b[^>]*>"));
+    // only known tags, leaving placeholders like `<year>` alone.
+    static HTML_TAG: LazyLock<Regex> = LazyLock::new(|| {
+        regex(
+            r"(?i)</?(?:a|abbr|b|blockquote|body|br|center|code|dd|div|dl|dt|em|font|h[1-6]|head|hr|html|i|img|li|link|meta|ol|p|pre|small|span|strong|sub|sup|table|tbody|td|th|thead|title|tr|tt|u|ul)\b[^>]*>",
+        )
+    });
+    static MARKDOWN_LINK: LazyLock<Regex> = LazyLock::new(|| regex(r"!?\[([^\]\n]*)\]\([^)\n]*\)"));
+    static MARKDOWN_LINE_PREFIX: LazyLock<Regex> = LazyLock::new(|| regex(r"(?m)^[ \t]*(?:#{1,6}|>+)[ \t]*"));
+    static MARKDOWN_RULE_OR_FENCE: LazyLock<Regex> =
+        LazyLock::new(|| regex(r"(?m)^[ \t]*(?:(?:\*[ \t]*){3,}|(?:-[ \t]*){3,}|(?:_[ \t]*){3,}|```.*|~~~.*)$"));
+    static MARKDOWN_STRONG: LazyLock<Regex> = LazyLock::new(|| regex(r"\*\*([^*\n]+)\*\*|__([^_\n]+)__"));
+    static MARKDOWN_EMPHASIS: LazyLock<Regex> = LazyLock::new(|| regex(r"\*([^*\s][^*\n]*)\*"));
+    static MARKDOWN_CODE: LazyLock<Regex> = LazyLock::new(|| regex(r"`([^`\n]*)`"));
+    static YEAR_PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| {
+        regex(r"(?i)[<\[{]\s*(?:years?|yyyy(?:\s*-\s*yyyy)?|dates?)\s*[>\]}]")
+    });
+    static HOLDER_PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| {
+        regex(
+            r"(?i)[<\[{]\s*(?:copyright\s+(?:holders?|owners?)|name\s+of\s+(?:the\s+)?(?:copyright\s+)?(?:owners?|holders?|authors?)|owners?|holders?|authors?|organi[sz]ation|full\s*name)\s*[>\]}]",
+        )
+    });
+    static COPYRIGHT_MARKER: LazyLock<Regex> = LazyLock::new(|| regex(r"(?i)\bcopyright\b|\(c\)|©"));
+    static YEAR: LazyLock<Regex> = LazyLock::new(|| {
+        regex(r"(?i)\b(?:19|20)\d{2}(?:\s*[-–,]\s*(?:(?:19|20)\d{2}|present)\b)*\b")
+    });
+    static HOLDER: LazyLock<Regex> = LazyLock::new(|| regex(r"(?i)(<year>[,.]?)[ \t]+(?:by[ \t]+)?[^ \t<].*$"));
+
+    fn strip_comment_markers(text: &str) -> String {
+        let text = LEADING_COMMENT_MARKER.replace_all(text, "");
+        TRAILING_COMMENT_MARKER
+            .replace_all(&text, |captures: &Captures| match captures[1].contains("/*") {
+                // the comment opened on this line after some code, removing only its end would unbalance it.
+                true => captures[0].to_string(),
+                false => captures[1].to_string(),
+            })
+            .into_owned()
+    }
+
+    fn strip_markup(text: &str) -> String {
+        let text = HTML_COMMENT.replace_all(text, "");
+        let text = HTML_BLOCK_TAG.replace_all(&text, "\n");
+        let text = HTML_TAG.replace_all(&text, "");
+        let text = text
+            .replace("&nbsp;", " ")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&#39;", "'")
+            .replace("&apos;", "'")
+            .replace("&copy;", "©")
+            .replace("&amp;", "&");
+        let text = MARKDOWN_RULE_OR_FENCE.replace_all(&text, "");
+        let text = MARKDOWN_LINK.replace_all(&text, "$1");
+        let text = MARKDOWN_LINE_PREFIX.replace_all(&text, "");
+        let text = MARKDOWN_STRONG.replace_all(&text, "$1$2");
+        let text = MARKDOWN_EMPHASIS.replace_all(&text, "$1");
+        MARKDOWN_CODE.replace_all(&text, "$1").into_owned()
+    }
+
+    fn replace_year_and_holder(text: &str) -> String {
+        let text = YEAR_PLACEHOLDER.replace_all(text, "<year>");
+        let text = HOLDER_PLACEHOLDER.replace_all(&text, "<copyright holders>");
+        text.split_inclusive('\n')
+            .map(|line| {
+                if !COPYRIGHT_MARKER.is_match(line) {
+                    return line.to_string();
+                }
+                let (line, newline) = match line.strip_suffix('\n') {
+                    Some(line) => (line, "\n"),
+                    None => (line, ""),
+                };
+                let line = YEAR.replace_all(line, "<year>");
+                HOLDER.replace(&line, "$1 <copyright holders>").into_owned() + newline
+            })
+            .collect()
+    }
+
+    impl BuiltinSegment {
+        /// Every built-in segment, in the order they are documented.
+        pub const ALL: &'static [BuiltinSegment] = &[
+            Self::StripLicense,
+            Self::NormalizeTokens,
+            Self::NormalizeSpdx,
+            Self::StripSpdxHeading,
+            Self::StripCopyrightLines,
+            Self::StripCommentMarkers,
+            Self::StripAllRightsReserved,
+            Self::RemoveUrls,
+            Self::RemoveBullets,
+            Self::CollapseWhitespace,
+            Self::StripMarkup,
+            Self::ReplaceYearAndHolder,
+        ];
+
+        /// The name used to reference this segment in pipeline definitions.
+        pub fn name(&self) -> &'static str {
+            match self {
+                Self::StripLicense => "strip_license",
+                Self::NormalizeTokens => "normalize_tokens",
+                Self::NormalizeSpdx => "normalize_spdx",
+                Self::StripSpdxHeading => "strip_spdx_heading",
+                Self::StripCopyrightLines => "strip_copyright_lines",
+                Self::StripCommentMarkers => "strip_comment_markers",
+                Self::StripAllRightsReserved => "strip_all_rights_reserved",
+                Self::RemoveUrls => "remove_urls",
+                Self::RemoveBullets => "remove_bullets",
+                Self::CollapseWhitespace => "collapse_whitespace",
+                Self::StripMarkup => "strip_markup",
+                Self::ReplaceYearAndHolder => "replace_year_and_holder",
+            }
+        }
+
+        /// Returns the built-in segment with the given name, the inverse of [BuiltinSegment::name].
+        pub fn from_name(name: &str) -> Option<Self> {
+            Self::ALL.iter().copied().find(|builtin| builtin.name() == name)
+        }
+
+        pub fn apply(&self, text: &str) -> String {
+            match self {
+                Self::StripLicense => strip_license(text),
+                Self::NormalizeTokens => normalize_tokens(text),
+                Self::NormalizeSpdx => normalize_spdx(text),
+                Self::StripSpdxHeading => strip_spdx_heading(text),
+                Self::StripCopyrightLines => COPYRIGHT_LINE.replace_all(text, "").into_owned(),
+                Self::StripCommentMarkers => strip_comment_markers(text),
+                Self::StripAllRightsReserved => ALL_RIGHTS_RESERVED.replace_all(text, "").into_owned(),
+                Self::RemoveUrls => URL.replace_all(text, "").into_owned(),
+                Self::RemoveBullets => BULLET.replace_all(text, "").into_owned(),
+                Self::CollapseWhitespace => text.split_whitespace().collect::<Vec<_>>().join(" "),
+                Self::StripMarkup => strip_markup(text),
+                Self::ReplaceYearAndHolder => replace_year_and_holder(text),
+            }
+        }
+    }
+
+    impl Normalizer for BuiltinSegment {
+        fn normalize(&self, text: &str) -> String {
+            self.apply(text)
+        }
+
+        fn id(&self) -> String {
+            self.name().to_string()
+        }
+
+        fn normalize_with_offsets(&self, text: &str) -> NormalizedText {
+            match self {
+                Self::StripLicense => strip_license_with_offsets(text),
+                Self::NormalizeTokens => normalize_tokens_with_offsets(text),
+                _ => align_normalized(text, &self.apply(text)),
+            }
+        }
+    }
+
+    impl fmt::Display for BuiltinSegment {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.name())
+        }
+    }
+}
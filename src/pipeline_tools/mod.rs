@@ -15,10 +15,22 @@
 *   limitations under the License.
 */
 
+pub mod builtin;
+pub mod report;
+
 pub mod pipeline {
-    use serde::Serialize;
+    use std::time::Duration;
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        BuiltinSegment, DetectionError, LicenseListActions, LicenseMatch, PipelineBestMatch, PipelineReport, PipelineStep,
+    };
 
-    use crate::{LicenseListActions, LicenseMatch};
+    /// Signature of a [Segment::Custom] function, receiving the running license and the matches of every previous run.
+    ///
+    /// Required to be `Send + Sync` so that a [Pipeline] can be shared between threads.
+    pub type CustomSegmentFn = dyn Fn(&str, &Vec<Vec<LicenseMatch>>) -> String + Send + Sync;
 
     pub enum Using {
         Regex(regex::Regex),
@@ -32,12 +44,66 @@ pub mod pipeline {
         Remove(Using),
         Replace(Using, String),
 
-        Custom(Box<dyn Fn(&str, &Vec<Vec<LicenseMatch>>) -> String>),
+        Custom(Box<CustomSegmentFn>),
+
+        /// Executes a transform shipped with the library, see [BuiltinSegment].
+        Builtin(BuiltinSegment),
 
         /// Executes multiple segment actions before testing on the algorithm.
         Batch(Vec<Segment>),
     }
     impl Segment {
+        /// See [BuiltinSegment::StripCopyrightLines].
+        pub fn strip_copyright_lines() -> Self {
+            Self::Builtin(BuiltinSegment::StripCopyrightLines)
+        }
+
+        /// See [BuiltinSegment::StripCommentMarkers].
+        pub fn strip_comment_markers() -> Self {
+            Self::Builtin(BuiltinSegment::StripCommentMarkers)
+        }
+
+        /// See [BuiltinSegment::StripAllRightsReserved].
+        pub fn strip_all_rights_reserved() -> Self {
+            Self::Builtin(BuiltinSegment::StripAllRightsReserved)
+        }
+
+        /// See [BuiltinSegment::RemoveUrls].
+        pub fn remove_urls() -> Self {
+            Self::Builtin(BuiltinSegment::RemoveUrls)
+        }
+
+        /// See [BuiltinSegment::RemoveBullets].
+        pub fn remove_bullets() -> Self {
+            Self::Builtin(BuiltinSegment::RemoveBullets)
+        }
+
+        /// See [BuiltinSegment::CollapseWhitespace].
+        pub fn collapse_whitespace() -> Self {
+            Self::Builtin(BuiltinSegment::CollapseWhitespace)
+        }
+
+        /// See [BuiltinSegment::StripMarkup].
+        pub fn strip_markup() -> Self {
+            Self::Builtin(BuiltinSegment::StripMarkup)
+        }
+
+        /// See [BuiltinSegment::ReplaceYearAndHolder].
+        pub fn replace_year_and_holder() -> Self {
+            Self::Builtin(BuiltinSegment::ReplaceYearAndHolder)
+        }
+
+        /// A short name of the segment for reports, the name of the built-in segment or the kind of segment (e.g. `"remove"`).
+        pub fn name(&self) -> &'static str {
+            match self {
+                Self::Remove(_) => "remove",
+                Self::Replace(_, _) => "replace",
+                Self::Custom(_) => "custom",
+                Self::Batch(_) => "batch",
+                Self::Builtin(builtin) => builtin.name(),
+            }
+        }
+
         fn execute(&self, incoming_string: &str, previous_matches: &Vec<Vec<LicenseMatch>>) -> String {
             match self {
                 Self::Remove(using) => match using {
@@ -56,8 +122,122 @@ pub mod pipeline {
                     Using::Text(text) => incoming_string.replace(text, replacement),
                 },
                 Self::Custom(func) => func(incoming_string, previous_matches),
+                Self::Builtin(builtin) => builtin.apply(incoming_string),
             }
         }
+
+        /// Returns the declarative form of this segment, or `None` when it (or a segment in its batch) is [Segment::Custom].
+        pub fn definition(&self) -> Option<SegmentDefinition> {
+            Some(match self {
+                Self::Remove(using) => SegmentDefinition::Remove {
+                    using: UsingDefinition::from(using),
+                },
+                Self::Replace(using, replacement) => SegmentDefinition::Replace {
+                    using: UsingDefinition::from(using),
+                    replacement: replacement.clone(),
+                },
+                Self::Batch(segments) => SegmentDefinition::Batch {
+                    segments: segments.iter().map(Segment::definition).collect::<Option<_>>()?,
+                },
+                Self::Builtin(builtin) => SegmentDefinition::Builtin { name: *builtin },
+                Self::Custom(_) => return None,
+            })
+        }
+    }
+
+    /// The serializable counterpart of [Using], see [SegmentDefinition].
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum UsingDefinition {
+        Regex(String),
+        Text(String),
+    }
+
+    impl From<&Using> for UsingDefinition {
+        fn from(using: &Using) -> Self {
+            match using {
+                Using::Regex(re) => Self::Regex(re.as_str().to_string()),
+                Using::Text(text) => Self::Text(text.clone()),
+            }
+        }
+    }
+
+    impl UsingDefinition {
+        fn build(&self) -> Result<Using, DetectionError> {
+            Ok(match self {
+                Self::Regex(pattern) => Using::Regex(regex::Regex::new(pattern).map_err(|e| {
+                    DetectionError::InvalidParameter(format!("invalid segment regex '{}': {}", pattern, e))
+                })?),
+                Self::Text(text) => Using::Text(text.clone()),
+            })
+        }
+    }
+
+    /// A declarative [Segment], allowing pipelines to be described in data (e.g. JSON) instead of code.
+    ///
+    /// [Segment::Custom] has no declarative counterpart as it wraps a function.
+    /// ```json
+    /// [
+    ///     { "type": "remove", "regex": "\\(c\\)" },
+    ///     { "type": "replace", "text": "Copyright", "replacement": "" },
+    ///     { "type": "batch", "segments": [{ "type": "remove", "text": "-" }] },
+    ///     { "type": "builtin", "name": "strip_license" }
+    /// ]
+    /// ```
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    pub enum SegmentDefinition {
+        Remove {
+            #[serde(flatten)]
+            using: UsingDefinition,
+        },
+        Replace {
+            #[serde(flatten)]
+            using: UsingDefinition,
+            replacement: String,
+        },
+        Batch {
+            segments: Vec<SegmentDefinition>,
+        },
+        Builtin {
+            name: BuiltinSegment,
+        },
+    }
+
+    impl SegmentDefinition {
+        /// Creates the segment described by this definition, fails with [DetectionError::InvalidParameter] on an invalid regex.
+        pub fn build(&self) -> Result<Segment, DetectionError> {
+            Ok(match self {
+                Self::Remove { using } => Segment::Remove(using.build()?),
+                Self::Replace { using, replacement } => Segment::Replace(using.build()?, replacement.clone()),
+                Self::Batch { segments } => {
+                    Segment::Batch(segments.iter().map(SegmentDefinition::build).collect::<Result<_, _>>()?)
+                }
+                Self::Builtin { name } => Segment::Builtin(*name),
+            })
+        }
+    }
+
+    /// A serializable pipeline, e.g. stored as a JSON, TOML or YAML file and turned into a [Pipeline] with [PipelineDefinition::build].
+    /// ```toml
+    /// [[segments]]
+    /// type = "builtin"
+    /// name = "strip_spdx_heading"
+    ///
+    /// [[segments]]
+    /// type = "remove"
+    /// regex = "(?i)copyright.*"
+    /// ```
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct PipelineDefinition {
+        pub segments: Vec<SegmentDefinition>,
+    }
+
+    impl PipelineDefinition {
+        /// Creates the described pipeline, fails with [DetectionError::InvalidParameter] when a segment contains an invalid regex.
+        pub fn build(&self) -> Result<Pipeline, DetectionError> {
+            Pipeline::from_definitions(&self.segments)
+        }
     }
 
     pub struct Pipeline {
@@ -90,6 +270,24 @@ pub mod pipeline {
             }
         }
 
+        /// Creates a new pipeline from declarative segments, see [SegmentDefinition] and [Pipeline::new].
+        pub fn from_definitions(definitions: &[SegmentDefinition]) -> Result<Self, DetectionError> {
+            Ok(Self::new(
+                definitions.iter().map(SegmentDefinition::build).collect::<Result<_, _>>()?,
+            ))
+        }
+
+        /// Returns the declarative form of this pipeline, fails with [DetectionError::InvalidParameter] when it contains a [Segment::Custom].
+        pub fn definition(&self) -> Result<PipelineDefinition, DetectionError> {
+            let segments = self.segments.iter().map(Segment::definition).collect::<Option<_>>();
+            match segments {
+                Some(segments) => Ok(PipelineDefinition { segments }),
+                None => Err(DetectionError::InvalidParameter(
+                    "custom segments can not be serialized".to_string(),
+                )),
+            }
+        }
+
         /// Run the pipeline on the incoming license.
         /// 
         /// Returns a vector of each pipeline segment execution with the first (0th) element of that vector being the first run without any segments
@@ -118,42 +316,120 @@ pub mod pipeline {
         /// > I.e., if the confidence of ***the top (highest confidence) license*** is above this threshold, the pipeline will stop running.
         /// 
         /// > The confidence is a value between 0 and 100 (inclusive). 
-        /// Any value below 0 will be treated as 0 and any value above 100 will be clamped to 101, indicating that this pipeline will run to completion with no
-        /// short circuits.
+        /// > Any value below 0 will be treated as 0 and any value above 100 will be clamped to 101, indicating that this pipeline will run to completion with no
+        /// > short circuits.
+        ///
+        /// See [Pipeline::run_with_report] for a report explaining each run.
         pub fn run<T: Serialize>(&self, alg: &dyn LicenseListActions<T>, incoming_license: &str, desired_confidence: f32) -> Vec<Vec<LicenseMatch>> {
+            self.run_with_report(alg, incoming_license, desired_confidence).into_runs()
+        }
+
+        /// Same as [Pipeline::run] but returns a [PipelineReport] describing every step (the executed segment, the length of the
+        /// running license, the matches and the time taken), whether the pipeline short-circuited and the best match over all steps.
+        pub fn run_with_report<T: Serialize>(
+            &self,
+            alg: &dyn LicenseListActions<T>,
+            incoming_license: &str,
+            desired_confidence: f32,
+        ) -> PipelineReport {
             let desired_confidence = desired_confidence.clamp(0.0, 101.0);
+            let pipeline_stopwatch = Stopwatch::start();
 
             let mut piped_string = incoming_license.to_string();
-            let mut alg_match_results = alg.match_by_plain_text(&piped_string);
-            let mut top_match_confidence: f32 = match alg_match_results.get(0) {
-                Some(top_match) => top_match.confidence,
-                None => 0.0,
-            };
+            let stopwatch = Stopwatch::start();
+            // kept apart from the steps as custom segments receive the matches of every previous run.
+            let mut pipeline_results: Vec<Vec<LicenseMatch>> = Vec::with_capacity(self.segments.len() + 1);
+            pipeline_results.push(alg.match_by_plain_text(&piped_string));
+            let mut steps = vec![StepInfo {
+                segment_index: None,
+                length_before: piped_string.len(),
+                length_after: piped_string.len(),
+                duration: stopwatch.elapsed(),
+            }];
+
+            for (index, segment) in self.segments.iter().enumerate() {
+                let top_match_confidence = pipeline_results
+                    .last()
+                    .and_then(|matches| matches.first())
+                    .map_or(0.0, |top_match| top_match.confidence);
+                if top_match_confidence >= desired_confidence {
+                    break;
+                }
+
+                let stopwatch = Stopwatch::start();
+                let length_before = piped_string.len();
+                piped_string = segment.execute(&piped_string, &pipeline_results);
+                pipeline_results.push(alg.match_by_plain_text(&piped_string));
+                steps.push(StepInfo {
+                    segment_index: Some(index),
+                    length_before,
+                    length_after: piped_string.len(),
+                    duration: stopwatch.elapsed(),
+                });
+            }
 
-            let mut pipeline_results: Vec<Vec<LicenseMatch>> = Vec::with_capacity(self.segments.len());
-            pipeline_results.push(alg_match_results);
+            let short_circuited = steps.len() <= self.segments.len();
+            let steps: Vec<PipelineStep> = steps
+                .into_iter()
+                .zip(pipeline_results)
+                .map(|(info, matches)| PipelineStep {
+                    segment_index: info.segment_index,
+                    segment_name: info.segment_index.map(|index| self.segments[index].name()),
+                    length_before: info.length_before,
+                    length_after: info.length_after,
+                    matches,
+                    duration: info.duration,
+                })
+                .collect();
 
-            if top_match_confidence >= desired_confidence {
-                return pipeline_results;
+            let mut best: Option<PipelineBestMatch> = None;
+            for (index, step) in steps.iter().enumerate() {
+                if let Some(top_match) = step.matches.first() {
+                    if best.as_ref().is_none_or(|best| top_match.confidence > best.license_match.confidence) {
+                        best = Some(PipelineBestMatch {
+                            step: index,
+                            license_match: top_match.clone(),
+                        });
+                    }
+                }
             }
 
-            for segment in self.segments.iter() {
-                piped_string = segment.execute(&piped_string, &pipeline_results);
-                alg_match_results = alg.match_by_plain_text(&piped_string);
+            PipelineReport {
+                steps,
+                best,
+                short_circuited,
+                desired_confidence,
+                duration: pipeline_stopwatch.elapsed(),
+            }
+        }
+    }
 
-                top_match_confidence = match alg_match_results.get(0) {
-                    Some(top_match) => top_match.confidence,
-                    None => 0.0,
-                };
+    struct StepInfo {
+        segment_index: Option<usize>,
+        length_before: usize,
+        length_after: usize,
+        duration: Duration,
+    }
 
-                pipeline_results.push(alg_match_results);
+    /// Measures the duration of pipeline steps, wasm32 has no clock so every duration is zero there.
+    struct Stopwatch {
+        #[cfg(not(target_arch = "wasm32"))]
+        started: std::time::Instant,
+    }
 
-                if top_match_confidence >= desired_confidence {
-                    break;
-                }
+    impl Stopwatch {
+        fn start() -> Self {
+            Self {
+                #[cfg(not(target_arch = "wasm32"))]
+                started: std::time::Instant::now(),
             }
+        }
 
-            return pipeline_results;
+        fn elapsed(&self) -> Duration {
+            #[cfg(not(target_arch = "wasm32"))]
+            return self.started.elapsed();
+            #[cfg(target_arch = "wasm32")]
+            return Duration::ZERO;
         }
     }
 }
@@ -0,0 +1,90 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+pub mod report {
+    use std::time::Duration;
+
+    use serde::{Serialize, Serializer};
+
+    use crate::LicenseMatch;
+
+    fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+    }
+
+    /// A single run of the algorithm within [Pipeline::run_with_report](crate::Pipeline::run_with_report).
+    #[derive(Debug, Clone, Serialize)]
+    pub struct PipelineStep {
+        /// The index in [Pipeline::segments](crate::Pipeline::segments) of the segment executed before this run,
+        /// `None` for the initial run on the incoming license.
+        pub segment_index: Option<usize>,
+        /// The name of that segment, see [Segment::name](crate::Segment::name).
+        pub segment_name: Option<&'static str>,
+        /// The length (in bytes) of the running license before the segment was executed.
+        pub length_before: usize,
+        /// The length (in bytes) of the running license the algorithm was run on.
+        pub length_after: usize,
+        pub matches: Vec<LicenseMatch>,
+        /// The time taken by the segment and the algorithm, always zero on wasm32 where no clock is available.
+        #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+        pub duration: Duration,
+    }
+
+    impl PipelineStep {
+        /// The confidence of the top (highest confidence) match, 0 when nothing matched.
+        pub fn top_confidence(&self) -> f32 {
+            self.matches.first().map_or(0.0, |top_match| top_match.confidence)
+        }
+    }
+
+    /// The highest confidence match over all steps of a [PipelineReport].
+    #[derive(Debug, Clone, Serialize)]
+    pub struct PipelineBestMatch {
+        /// The index of the step in [PipelineReport::steps] that produced the match.
+        pub step: usize,
+        #[serde(flatten)]
+        pub license_match: LicenseMatch,
+    }
+
+    /// Explains a pipeline run, see [Pipeline::run_with_report](crate::Pipeline::run_with_report).
+    #[derive(Debug, Clone, Serialize)]
+    pub struct PipelineReport {
+        /// Every run of the algorithm, the first being the run on the incoming license.
+        pub steps: Vec<PipelineStep>,
+        /// The top match with the highest confidence over all steps, the earliest step wins ties.
+        pub best: Option<PipelineBestMatch>,
+        /// Whether the pipeline stopped before executing every segment because the desired confidence was reached.
+        pub short_circuited: bool,
+        /// The desired confidence after clamping, see [Pipeline::run](crate::Pipeline::run).
+        pub desired_confidence: f32,
+        /// The time taken by the whole run, always zero on wasm32 where no clock is available.
+        #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+        pub duration: Duration,
+    }
+
+    impl PipelineReport {
+        /// The matches of the last step, i.e. the state the pipeline ended in.
+        pub fn final_matches(&self) -> &[LicenseMatch] {
+            self.steps.last().map_or(&[], |step| &step.matches)
+        }
+
+        /// Returns the matches of every step, in the shape returned by [Pipeline::run](crate::Pipeline::run).
+        pub fn into_runs(self) -> Vec<Vec<LicenseMatch>> {
+            self.steps.into_iter().map(|step| step.matches).collect()
+        }
+    }
+}
@@ -0,0 +1,376 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+pub mod policy {
+    use std::{collections::HashMap, fmt};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::{DetectionError, LicenseCategory, LicenseMatch, LicenseMetadata, SpdxExpression, SpdxLicense, SpdxMapping};
+
+    /// The outcome of evaluating a license against a [Policy], ordered from least to most severe.
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Verdict {
+        Allow,
+        Review,
+        Deny,
+    }
+
+    impl fmt::Display for Verdict {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(match self {
+                Self::Allow => "allow",
+                Self::Review => "review",
+                Self::Deny => "deny",
+            })
+        }
+    }
+
+    /// Selects the licenses a [PolicyRule] applies to.
+    ///
+    /// Serialized as a single key object, e.g. `{"spdx": "MIT"}`, `{"category": "Copyleft Limited"}` or
+    /// `{"expression": "GPL-2.0-only WITH Classpath-exception-2.0"}`.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[serde(rename_all = "lowercase")]
+    pub enum LicenseSelector {
+        /// An SPDX identifier (case-insensitive), matching the license with or without an exception, or the exception itself.
+        Spdx(String),
+        /// A ScanCode category, only matches licenses whose metadata is known.
+        Category(
+            #[serde(serialize_with = "serialize_category", deserialize_with = "deserialize_category")] LicenseCategory,
+        ),
+        /// Matches every license of the expression (case-insensitive, like [Spdx](Self::Spdx)), a license with an
+        /// exception (`WITH`) only matches that exact combination.
+        Expression(SpdxExpression),
+    }
+
+    impl LicenseSelector {
+        pub fn spdx(id: &str) -> Self {
+            Self::Spdx(id.to_string())
+        }
+
+        pub fn category(category: LicenseCategory) -> Self {
+            Self::Category(category)
+        }
+
+        /// Parses the expression, failing with [DetectionError::InvalidExpression].
+        pub fn expression(expression: &str) -> Result<Self, DetectionError> {
+            Ok(Self::Expression(expression.parse()?))
+        }
+
+        fn matches(&self, term: &Term) -> bool {
+            match self {
+                Self::Spdx(id) => {
+                    term.license.to_string().eq_ignore_ascii_case(id)
+                        || term.exception.as_ref().is_some_and(|e| e.eq_ignore_ascii_case(id))
+                }
+                Self::Category(category) => term.metadata.and_then(|m| m.category.as_ref()) == Some(category),
+                Self::Expression(expression) => {
+                    let license = term.license.to_string();
+                    expression_terms(expression).iter().any(|(selected, exception)| {
+                        selected.to_string().eq_ignore_ascii_case(&license)
+                            && match (exception, &term.exception) {
+                                (Some(selected), Some(exception)) => selected.eq_ignore_ascii_case(exception),
+                                (None, None) => true,
+                                _ => false,
+                            }
+                    })
+                }
+            }
+        }
+    }
+
+    impl fmt::Display for LicenseSelector {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Spdx(id) => write!(f, "spdx {}", id),
+                Self::Category(category) => write!(f, "category {}", category),
+                Self::Expression(expression) => write!(f, "expression {}", expression),
+            }
+        }
+    }
+
+    fn serialize_category<S: Serializer>(category: &LicenseCategory, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(category)
+    }
+
+    fn deserialize_category<'de, D: Deserializer<'de>>(deserializer: D) -> Result<LicenseCategory, D::Error> {
+        Ok(LicenseCategory::parse(&String::deserialize(deserializer)?))
+    }
+
+    /// Assigns a verdict to the licenses matched by the selector.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct PolicyRule {
+        pub verdict: Verdict,
+        #[serde(flatten)]
+        pub selector: LicenseSelector,
+        /// Explains the rule, reported when the rule decides the verdict of a license.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub reason: Option<String>,
+    }
+
+    impl PolicyRule {
+        pub fn new(verdict: Verdict, selector: LicenseSelector) -> Self {
+            Self {
+                verdict,
+                selector,
+                reason: None,
+            }
+        }
+
+        pub fn with_reason(mut self, reason: &str) -> Self {
+            self.reason = Some(reason.to_string());
+            self
+        }
+    }
+
+    /// Why a license received its verdict.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    pub struct PolicyReason {
+        /// The license (and exception) as written in the SPDX expression.
+        pub license: String,
+        pub verdict: Verdict,
+        /// The rule that decided the verdict, `None` when no rule applied.
+        pub rule: Option<PolicyRule>,
+        pub message: String,
+    }
+
+    /// The result of evaluating a detection result against a [Policy].
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    pub struct PolicyDecision {
+        pub verdict: Verdict,
+        /// The evaluated expression, `None` when no license was detected.
+        pub expression: Option<SpdxExpression>,
+        /// The verdict of every license in the expression, in order of appearance.
+        pub reasons: Vec<PolicyReason>,
+    }
+
+    impl PolicyDecision {
+        pub fn is_allowed(&self) -> bool {
+            self.verdict == Verdict::Allow
+        }
+    }
+
+    /// A set of rules deciding whether the detected licenses are allowed, denied or need a manual review.
+    ///
+    /// Rules are tried in order and the first matching rule decides the verdict of a license, allowing specific rules
+    /// (e.g. a license with an exception) to precede broader ones (e.g. a category). The verdict of an expression is the
+    /// most severe verdict of an `AND` and the least severe verdict of an `OR`, as any one of its licenses can be chosen.
+    ///
+    /// Policies (de)serialize with serde, so they can be kept in a configuration file:
+    /// ```json
+    /// {
+    ///   "default": "review",
+    ///   "rules": [
+    ///     { "verdict": "allow", "expression": "GPL-2.0-only WITH Classpath-exception-2.0" },
+    ///     { "verdict": "deny", "category": "Copyleft", "reason": "copyleft licenses are not allowed in our products" },
+    ///     { "verdict": "allow", "spdx": "MIT" }
+    ///   ]
+    /// }
+    /// ```
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    pub struct Policy {
+        #[serde(default)]
+        pub rules: Vec<PolicyRule>,
+        /// The verdict of licenses not matched by any rule.
+        #[serde(default = "default_verdict")]
+        pub default: Verdict,
+        /// The verdict when no license was detected at all.
+        #[serde(default = "default_verdict")]
+        pub no_license: Verdict,
+        /// Matches below this confidence (0-100) are ignored.
+        #[serde(default)]
+        pub min_confidence: f32,
+    }
+
+    fn default_verdict() -> Verdict {
+        Verdict::Review
+    }
+
+    impl Default for Policy {
+        fn default() -> Self {
+            Self::new(Verdict::Review)
+        }
+    }
+
+    /// A license (with its optional exception) of an evaluated expression.
+    struct Term<'a> {
+        license: SpdxLicense,
+        exception: Option<String>,
+        metadata: Option<&'a LicenseMetadata>,
+    }
+
+    impl Policy {
+        /// Creates a policy without rules, giving every license the `default` verdict.
+        pub fn new(default: Verdict) -> Self {
+            Self {
+                rules: Vec::new(),
+                default,
+                no_license: Verdict::Review,
+                min_confidence: 0.0,
+            }
+        }
+
+        pub fn rule(mut self, rule: PolicyRule) -> Self {
+            self.rules.push(rule);
+            self
+        }
+
+        pub fn allow(self, selector: LicenseSelector) -> Self {
+            self.rule(PolicyRule::new(Verdict::Allow, selector))
+        }
+
+        pub fn review(self, selector: LicenseSelector) -> Self {
+            self.rule(PolicyRule::new(Verdict::Review, selector))
+        }
+
+        pub fn deny(self, selector: LicenseSelector) -> Self {
+            self.rule(PolicyRule::new(Verdict::Deny, selector))
+        }
+
+        /// Evaluates an SPDX expression, e.g. the declared license of a dependency.
+        ///
+        /// No metadata is known for the licenses of the expression, category rules therefore never match.
+        pub fn evaluate_expression(&self, expression: &SpdxExpression) -> PolicyDecision {
+            self.evaluate(Some(expression.clone()), &HashMap::new())
+        }
+
+        /// Evaluates the licenses found in a text (e.g. the top match of every span), see [SpdxMapping::expression_of_matches].
+        pub fn evaluate_matches(&self, matches: &[&LicenseMatch]) -> PolicyDecision {
+            self.evaluate_matches_with_mapping(matches, &SpdxMapping::default())
+        }
+
+        /// Same as [evaluate_matches](Policy::evaluate_matches) using a custom mapping from database entries to SPDX identifiers.
+        pub fn evaluate_matches_with_mapping(&self, matches: &[&LicenseMatch], mapping: &SpdxMapping) -> PolicyDecision {
+            let matches: Vec<&LicenseMatch> = matches.iter().copied().filter(|m| m.confidence >= self.min_confidence).collect();
+
+            let mut mapping = mapping.clone();
+            for m in matches.iter() {
+                if let Some(metadata) = &m.metadata {
+                    mapping.insert_metadata(&m.name, metadata);
+                }
+            }
+            let metadata: HashMap<String, &LicenseMetadata> = matches
+                .iter()
+                .filter_map(|m| Some((mapping.spdx_id(&m.name).to_lowercase(), m.metadata.as_ref()?)))
+                .collect();
+            let names: Vec<&str> = matches.iter().map(|m| m.name.as_str()).collect();
+            self.evaluate(mapping.expression(&names), &metadata)
+        }
+
+        /// Evaluates the result of [Pipeline::run](crate::Pipeline::run), i.e. the top match of its last run.
+        pub fn evaluate_pipeline(&self, results: &[Vec<LicenseMatch>]) -> PolicyDecision {
+            let top: Vec<&LicenseMatch> = results.last().and_then(|run| run.first()).into_iter().collect();
+            self.evaluate_matches(&top)
+        }
+
+        fn evaluate(&self, expression: Option<SpdxExpression>, metadata: &HashMap<String, &LicenseMetadata>) -> PolicyDecision {
+            let Some(expression) = expression else {
+                return PolicyDecision {
+                    verdict: self.no_license,
+                    expression: None,
+                    reasons: vec![PolicyReason {
+                        license: String::new(),
+                        verdict: self.no_license,
+                        rule: None,
+                        message: "no license was detected".to_string(),
+                    }],
+                };
+            };
+
+            let mut reasons = Vec::new();
+            let verdict = self.evaluate_node(&expression, metadata, &mut reasons);
+            PolicyDecision {
+                verdict,
+                expression: Some(expression),
+                reasons,
+            }
+        }
+
+        fn evaluate_node(
+            &self,
+            expression: &SpdxExpression,
+            metadata: &HashMap<String, &LicenseMetadata>,
+            reasons: &mut Vec<PolicyReason>,
+        ) -> Verdict {
+            let term = |license: &SpdxLicense, exception: Option<&String>| Term {
+                license: license.clone(),
+                exception: exception.cloned(),
+                metadata: metadata.get(&license.id.to_lowercase()).copied(),
+            };
+            match expression {
+                SpdxExpression::License(license) => self.evaluate_term(&term(license, None), reasons),
+                SpdxExpression::With(license, exception) => self.evaluate_term(&term(license, Some(exception)), reasons),
+                SpdxExpression::And(inner) => inner
+                    .iter()
+                    .map(|e| self.evaluate_node(e, metadata, reasons))
+                    .max()
+                    .unwrap_or(self.default),
+                SpdxExpression::Or(inner) => inner
+                    .iter()
+                    .map(|e| self.evaluate_node(e, metadata, reasons))
+                    .min()
+                    .unwrap_or(self.default),
+            }
+        }
+
+        fn evaluate_term(&self, term: &Term, reasons: &mut Vec<PolicyReason>) -> Verdict {
+            let license = match &term.exception {
+                Some(exception) => format!("{} WITH {}", term.license, exception),
+                None => term.license.to_string(),
+            };
+            let reason = match self.rules.iter().find(|rule| rule.selector.matches(term)) {
+                Some(rule) => PolicyReason {
+                    message: match &rule.reason {
+                        Some(reason) => reason.clone(),
+                        None => format!("{} is {} by rule '{}'", license, past_tense(rule.verdict), rule.selector),
+                    },
+                    license,
+                    verdict: rule.verdict,
+                    rule: Some(rule.clone()),
+                },
+                None => PolicyReason {
+                    message: format!("no rule matches {}, using the default verdict", license),
+                    license,
+                    verdict: self.default,
+                    rule: None,
+                },
+            };
+            let verdict = reason.verdict;
+            reasons.push(reason);
+            verdict
+        }
+    }
+
+    fn past_tense(verdict: Verdict) -> &'static str {
+        match verdict {
+            Verdict::Allow => "allowed",
+            Verdict::Review => "up for review",
+            Verdict::Deny => "denied",
+        }
+    }
+
+    /// Returns every license of the expression together with its exception.
+    fn expression_terms(expression: &SpdxExpression) -> Vec<(&SpdxLicense, Option<&String>)> {
+        match expression {
+            SpdxExpression::License(license) => vec![(license, None)],
+            SpdxExpression::With(license, exception) => vec![(license, Some(exception))],
+            SpdxExpression::And(inner) | SpdxExpression::Or(inner) => inner.iter().flat_map(expression_terms).collect(),
+        }
+    }
+}
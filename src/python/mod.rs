@@ -0,0 +1,398 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+/// Python bindings generated by pyo3, see `pyproject.toml` and the README for building the wheel.
+///
+/// Matching releases the GIL, so detectors can be shared between Python threads.
+pub mod python {
+    use pyo3::{
+        exceptions::{PyFileNotFoundError, PyOSError, PyTypeError, PyValueError},
+        prelude::*,
+        types::PyBytes,
+    };
+
+    use crate::{
+        detecting::{
+            fuzzy_implementation::fuzzy_implementation::FuzzyDetection,
+            gaoya_implementation::gaoya_implementation::GaoyaDetection,
+        },
+        strip_license, BuiltinSegment, DetectionError, LicenseListActions, LicenseMatch, LicenseMetadata, Pipeline, SegmentDefinition,
+        UsingDefinition,
+    };
+
+    impl From<DetectionError> for PyErr {
+        fn from(error: DetectionError) -> Self {
+            match error {
+                DetectionError::FileNotFound(_) => PyFileNotFoundError::new_err(error.to_string()),
+                DetectionError::Io(_) => PyOSError::new_err(error.to_string()),
+                _ => PyValueError::new_err(error.to_string()),
+            }
+        }
+    }
+
+    /// The ScanCode metadata of a license, see [LicenseMetadata].
+    #[pyclass(name = "LicenseMetadata", module = "whichlicense_detection", frozen, get_all, skip_from_py_object)]
+    #[derive(Debug, Clone)]
+    pub struct PyLicenseMetadata {
+        pub key: Option<String>,
+        pub short_name: Option<String>,
+        pub name: Option<String>,
+        pub category: Option<String>,
+        pub owner: Option<String>,
+        pub spdx_license_key: Option<String>,
+        pub other_spdx_license_keys: Vec<String>,
+        pub is_exception: bool,
+        pub is_deprecated: bool,
+        pub osi_license_key: Option<String>,
+        pub homepage_url: Option<String>,
+        pub osi_url: Option<String>,
+        pub text_urls: Vec<String>,
+        pub other_urls: Vec<String>,
+    }
+
+    #[pymethods]
+    impl PyLicenseMetadata {
+        fn is_osi_approved(&self) -> bool {
+            self.osi_license_key.is_some() || self.osi_url.is_some()
+        }
+
+        fn __repr__(&self) -> String {
+            format!("{:?}", self)
+        }
+    }
+
+    impl From<LicenseMetadata> for PyLicenseMetadata {
+        fn from(metadata: LicenseMetadata) -> Self {
+            Self {
+                key: metadata.key,
+                short_name: metadata.short_name,
+                name: metadata.name,
+                category: metadata.category.map(|category| category.to_string()),
+                owner: metadata.owner,
+                spdx_license_key: metadata.spdx_license_key,
+                other_spdx_license_keys: metadata.other_spdx_license_keys,
+                is_exception: metadata.is_exception,
+                is_deprecated: metadata.is_deprecated,
+                osi_license_key: metadata.osi_license_key,
+                homepage_url: metadata.homepage_url,
+                osi_url: metadata.osi_url,
+                text_urls: metadata.text_urls,
+                other_urls: metadata.other_urls,
+            }
+        }
+    }
+
+    /// A matched license, see [LicenseMatch].
+    #[pyclass(name = "LicenseMatch", module = "whichlicense_detection", frozen, get_all, skip_from_py_object)]
+    #[derive(Debug, Clone)]
+    pub struct PyLicenseMatch {
+        pub name: String,
+        pub confidence: f32,
+        pub metadata: Option<PyLicenseMetadata>,
+    }
+
+    #[pymethods]
+    impl PyLicenseMatch {
+        fn __repr__(&self) -> String {
+            format!("LicenseMatch(name='{}', confidence={})", self.name, self.confidence)
+        }
+    }
+
+    impl From<LicenseMatch> for PyLicenseMatch {
+        fn from(license_match: LicenseMatch) -> Self {
+            Self {
+                name: license_match.name,
+                confidence: license_match.confidence,
+                metadata: license_match.metadata.map(PyLicenseMetadata::from),
+            }
+        }
+    }
+
+    fn to_py_matches(matches: Vec<LicenseMatch>) -> Vec<PyLicenseMatch> {
+        matches.into_iter().map(PyLicenseMatch::from).collect()
+    }
+
+    /// Implements the methods of a detector class (which wraps its detector in `inner`) next to the class specific `items`,
+    /// pyo3 only allows a single `#[pymethods]` block per class.
+    macro_rules! detector_methods {
+        ($class:ty { $($items:tt)* }) => {
+            #[pymethods]
+            impl $class {
+                $($items)*
+
+                /// Returns the matches of the text, best match first.
+                fn match_by_plain_text(&self, py: Python<'_>, text: &str) -> Vec<PyLicenseMatch> {
+                    let inner = &self.inner;
+                    to_py_matches(py.detach(|| inner.match_by_plain_text(text)))
+                }
+
+                fn add_plain(&mut self, name: &str, text: &str) {
+                    self.inner.add_plain(name, text);
+                }
+
+                fn remove(&mut self, name: &str) {
+                    self.inner.remove(name);
+                }
+
+                /// Returns the sorted names of the known licenses.
+                fn license_names(&self) -> Vec<String> {
+                    let mut names: Vec<String> =
+                        self.inner.get_license_list().into_iter().map(|(name, _)| name).collect();
+                    names.sort_unstable();
+                    names
+                }
+
+                /// Replaces the known licenses with the ones in the database, raises `ValueError` on an invalid database.
+                ///
+                /// The known licenses are removed first, the detector is left without licenses when the database is invalid.
+                fn load_from_memory(&mut self, data: &[u8]) -> PyResult<()> {
+                    for (name, _) in self.inner.get_license_list() {
+                        self.inner.remove(&name);
+                    }
+                    Ok(self.inner.try_load_from_memory(data)?)
+                }
+
+                fn save_to_memory<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+                    Ok(PyBytes::new(py, &self.inner.try_save_to_memory()?))
+                }
+
+                fn load_from_file(&mut self, path: &str) -> PyResult<()> {
+                    Ok(self.inner.try_load_from_file(path)?)
+                }
+
+                fn save_to_file(&self, path: &str) -> PyResult<()> {
+                    Ok(self.inner.try_save_to_file(path)?)
+                }
+
+                #[getter]
+                fn min_confidence(&self) -> u8 {
+                    self.inner.min_confidence
+                }
+
+                #[setter]
+                fn set_min_confidence(&mut self, min_confidence: u8) {
+                    self.inner.min_confidence = min_confidence;
+                }
+
+                fn __len__(&self) -> usize {
+                    self.inner.get_license_list().len()
+                }
+            }
+        };
+    }
+
+    /// See [FuzzyDetection].
+    #[pyclass(name = "FuzzyDetection", module = "whichlicense_detection")]
+    pub struct PyFuzzyDetection {
+        inner: FuzzyDetection,
+    }
+
+    detector_methods!(PyFuzzyDetection {
+        #[new]
+        #[pyo3(signature = (*, min_confidence = 50, exit_on_exact_match = false, store_reference_texts = false))]
+        fn new(min_confidence: u8, exit_on_exact_match: bool, store_reference_texts: bool) -> PyResult<Self> {
+            Ok(Self {
+                inner: FuzzyDetection::builder()
+                    .min_confidence(min_confidence)
+                    .exit_on_exact_match(exit_on_exact_match)
+                    .store_reference_texts(store_reference_texts)
+                    .build()?,
+            })
+        }
+
+        /// Creates a detector loaded with the database embedded in the library.
+        #[cfg(feature = "embedded-database")]
+        #[staticmethod]
+        fn with_default_database() -> Self {
+            Self {
+                inner: FuzzyDetection::with_default_database(),
+            }
+        }
+    });
+
+    /// See [GaoyaDetection].
+    #[pyclass(name = "GaoyaDetection", module = "whichlicense_detection")]
+    pub struct PyGaoyaDetection {
+        inner: GaoyaDetection,
+    }
+
+    detector_methods!(PyGaoyaDetection {
+        #[new]
+        #[pyo3(signature = (
+            *,
+            bands = 42,
+            band_width = 3,
+            threshold = 0.5,
+            shingle_size = 50,
+            min_confidence = 50,
+            store_reference_texts = false
+        ))]
+        fn new(
+            bands: usize,
+            band_width: usize,
+            threshold: f64,
+            shingle_size: usize,
+            min_confidence: u8,
+            store_reference_texts: bool,
+        ) -> PyResult<Self> {
+            Ok(Self {
+                inner: GaoyaDetection::builder()
+                    .bands(bands)
+                    .band_width(band_width)
+                    .threshold(threshold)
+                    .shingle_size(shingle_size)
+                    .min_confidence(min_confidence)
+                    .store_reference_texts(store_reference_texts)
+                    .build()?,
+            })
+        }
+
+        /// Creates a detector with the parameters of, and loaded with, the database embedded in the library.
+        #[cfg(feature = "embedded-database")]
+        #[staticmethod]
+        fn with_default_database() -> Self {
+            Self {
+                inner: GaoyaDetection::with_default_database(),
+            }
+        }
+    });
+
+    fn using_definition(regex: Option<String>, text: Option<String>) -> PyResult<UsingDefinition> {
+        match (regex, text) {
+            (Some(regex), None) => Ok(UsingDefinition::Regex(regex)),
+            (None, Some(text)) => Ok(UsingDefinition::Text(text)),
+            _ => Err(PyValueError::new_err("exactly one of `regex` and `text` must be given")),
+        }
+    }
+
+    /// A pipeline segment, see [SegmentDefinition]. Created with `Segment.remove`, `Segment.replace`, `Segment.batch` and `Segment.builtin`.
+    #[pyclass(name = "Segment", module = "whichlicense_detection", frozen, from_py_object)]
+    #[derive(Debug, Clone)]
+    pub struct PySegment {
+        definition: SegmentDefinition,
+    }
+
+    #[pymethods]
+    impl PySegment {
+        /// Removes every occurrence of the regex or text from the running license.
+        #[staticmethod]
+        #[pyo3(signature = (*, regex = None, text = None))]
+        fn remove(regex: Option<String>, text: Option<String>) -> PyResult<Self> {
+            Ok(Self {
+                definition: SegmentDefinition::Remove {
+                    using: using_definition(regex, text)?,
+                },
+            })
+        }
+
+        /// Replaces every occurrence of the regex or text in the running license.
+        #[staticmethod]
+        #[pyo3(signature = (replacement, *, regex = None, text = None))]
+        fn replace(replacement: String, regex: Option<String>, text: Option<String>) -> PyResult<Self> {
+            Ok(Self {
+                definition: SegmentDefinition::Replace {
+                    using: using_definition(regex, text)?,
+                    replacement,
+                },
+            })
+        }
+
+        /// Executes the segments one after the other before testing against the algorithm.
+        #[staticmethod]
+        fn batch(segments: Vec<PySegment>) -> Self {
+            Self {
+                definition: SegmentDefinition::Batch {
+                    segments: segments.into_iter().map(|segment| segment.definition).collect(),
+                },
+            }
+        }
+
+        /// Executes a built-in segment by name, e.g. `"strip_copyright_lines"`.
+        #[staticmethod]
+        fn builtin(name: &str) -> PyResult<Self> {
+            let builtin = BuiltinSegment::from_name(name)
+                .ok_or_else(|| PyValueError::new_err(format!("unknown built-in segment '{}'", name)))?;
+            Ok(Self {
+                definition: SegmentDefinition::Builtin { name: builtin },
+            })
+        }
+
+        fn __repr__(&self) -> String {
+            format!("{:?}", self.definition)
+        }
+    }
+
+    /// See [Pipeline].
+    #[pyclass(name = "Pipeline", module = "whichlicense_detection", frozen)]
+    pub struct PyPipeline {
+        inner: Pipeline,
+    }
+
+    #[pymethods]
+    impl PyPipeline {
+        /// Raises `ValueError` when a segment contains an invalid regex.
+        #[new]
+        fn new(segments: Vec<PySegment>) -> PyResult<Self> {
+            let definitions: Vec<SegmentDefinition> = segments.into_iter().map(|segment| segment.definition).collect();
+            Ok(Self {
+                inner: Pipeline::from_definitions(&definitions)?,
+            })
+        }
+
+        /// Runs the pipeline on the text using a `FuzzyDetection` or `GaoyaDetection`, returning the matches of every run.
+        fn run(
+            &self,
+            py: Python<'_>,
+            detector: &Bound<'_, PyAny>,
+            text: &str,
+            desired_confidence: f32,
+        ) -> PyResult<Vec<Vec<PyLicenseMatch>>> {
+            let pipeline = &self.inner;
+            let runs = if let Ok(fuzzy) = detector.cast::<PyFuzzyDetection>() {
+                let fuzzy = fuzzy.borrow();
+                let inner = &fuzzy.inner;
+                py.detach(|| pipeline.run(inner, text, desired_confidence))
+            } else if let Ok(gaoya) = detector.cast::<PyGaoyaDetection>() {
+                let gaoya = gaoya.borrow();
+                let inner = &gaoya.inner;
+                py.detach(|| pipeline.run(inner, text, desired_confidence))
+            } else {
+                return Err(PyTypeError::new_err("expected a FuzzyDetection or GaoyaDetection"));
+            };
+            Ok(runs.into_iter().map(to_py_matches).collect())
+        }
+    }
+
+    /// Normalizes a license text the way the embedded databases were built, see [strip_license].
+    #[pyfunction(name = "strip_license")]
+    fn strip_license_py(text: &str) -> String {
+        strip_license(text)
+    }
+
+    #[pymodule]
+    #[pyo3(name = "whichlicense_detection")]
+    fn python_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+        module.add_class::<PyLicenseMetadata>()?;
+        module.add_class::<PyLicenseMatch>()?;
+        module.add_class::<PyFuzzyDetection>()?;
+        module.add_class::<PyGaoyaDetection>()?;
+        module.add_class::<PySegment>()?;
+        module.add_class::<PyPipeline>()?;
+        module.add_function(wrap_pyfunction!(strip_license_py, module)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,348 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+pub mod scanning {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use ignore::{overrides::OverrideBuilder, WalkBuilder};
+    use serde::{Deserialize, Serialize};
+
+    use crate::{DetectionError, LicenseListActions, LicenseMatch};
+
+    /// File names (without extension, case insensitive) that usually contain a license.
+    const LICENSE_STEMS: [&str; 5] = ["license", "licence", "copying", "copyright", "unlicense"];
+
+    /// Extensions of source files whose leading comment is checked for a license header.
+    const SOURCE_EXTENSIONS: [&str; 38] = [
+        "rs", "c", "h", "cc", "cpp", "cxx", "hh", "hpp", "hxx", "cs", "java", "kt", "kts", "scala", "groovy", "go",
+        "swift", "m", "mm", "js", "jsx", "mjs", "cjs", "ts", "tsx", "py", "rb", "pl", "pm", "php", "sh", "bash", "zsh",
+        "lua", "hs", "sql", "r", "dart",
+    ];
+
+    /// Line comment markers of source files whose extension has no entry in [line_comment_markers].
+    const LINE_MARKERS: [&str; 4] = ["//", "#", "--", ";"];
+
+    /// Why a file was picked by the scanner.
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CandidateKind {
+        /// A dedicated license file such as `LICENSE`, `COPYING` or `MIT-LICENSE.txt`.
+        License,
+        /// A `NOTICE` file.
+        Notice,
+        /// A `README` file, which often mentions or contains the license.
+        Readme,
+        /// The leading comment of a source file.
+        SourceHeader,
+    }
+
+    /// Why a candidate file was not run through the algorithm.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub enum SkipReason {
+        /// The file is larger than [ScanOptions::max_file_size], contains the file size in bytes.
+        TooLarge(u64),
+        /// The file contains NUL bytes and is most likely not text.
+        Binary,
+        /// The file could not be read.
+        Unreadable(String),
+    }
+
+    /// The matches found in a single file.
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct FileReport {
+        /// The path of the file relative to the scanned directory.
+        pub path: PathBuf,
+        pub kind: CandidateKind,
+        /// Sorted as returned by [LicenseListActions::match_by_plain_text].
+        pub matches: Vec<LicenseMatch>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct SkippedFile {
+        /// The path of the file relative to the scanned directory.
+        pub path: PathBuf,
+        pub reason: SkipReason,
+    }
+
+    /// The result of [scan_directory], both lists are sorted by path.
+    #[derive(Serialize, Deserialize, Debug, Clone, Default)]
+    pub struct ScanReport {
+        pub files: Vec<FileReport>,
+        pub skipped: Vec<SkippedFile>,
+    }
+
+    impl ScanReport {
+        /// Returns the reports of the files in which at least one license was detected.
+        pub fn detected(&self) -> impl Iterator<Item = &FileReport> {
+            self.files.iter().filter(|f| !f.matches.is_empty())
+        }
+    }
+
+    /// Configures which files [scan_directory] visits.
+    #[derive(Debug, Clone)]
+    pub struct ScanOptions {
+        /// Gitignore-style patterns (e.g. `target/`, `*.min.js`) of files and directories that are not scanned.
+        pub excludes: Vec<String>,
+        /// Whether `.gitignore`, `.ignore` and `.git/info/exclude` files are honoured. Defaults to true.
+        pub respect_gitignore: bool,
+        /// Candidate files larger than this (in bytes) are skipped. Defaults to 1 MiB.
+        pub max_file_size: u64,
+        /// Whether the leading comment of source files is scanned as well. Defaults to true.
+        pub scan_source_headers: bool,
+        /// The maximum number of lines read from the start of a source file when looking for its header. Defaults to 60.
+        pub max_header_lines: usize,
+    }
+
+    impl Default for ScanOptions {
+        fn default() -> Self {
+            Self {
+                excludes: vec![],
+                respect_gitignore: true,
+                max_file_size: 1024 * 1024,
+                scan_source_headers: true,
+                max_header_lines: 60,
+            }
+        }
+    }
+
+    /// Determines whether a file is a candidate for license detection based on its name.
+    ///
+    /// Files named after a license, notice or readme file (e.g. `LICENSE`, `LICENSE.MIT`, `NOTICE.txt`) are candidates unless they have a source extension.
+    ///
+    /// Returns [CandidateKind::SourceHeader] for any recognized source file, whether it has a header is only known after reading it.
+    pub fn classify_path(path: &Path) -> Option<CandidateKind> {
+        let file_name = path.file_name()?.to_string_lossy().to_lowercase();
+        // the stem is everything before the first dot so that e.g. `LICENSE.MIT` and `COPYING.LESSER` are recognized.
+        let (stem, extension) = match file_name.split_once('.') {
+            Some((stem, extension)) => (stem, extension.rsplit('.').next().unwrap_or(extension)),
+            None => (file_name.as_str(), ""),
+        };
+
+        if !SOURCE_EXTENSIONS.contains(&extension) {
+            let matches_stem = |name: &str| {
+                stem == name
+                    || stem.strip_prefix(name).is_some_and(|rest| rest.starts_with(['-', '_']))
+                    || stem.strip_suffix(name).is_some_and(|rest| rest.ends_with(['-', '_']))
+            };
+            if LICENSE_STEMS.iter().any(|name| matches_stem(name)) {
+                return Some(CandidateKind::License);
+            }
+            if matches_stem("notice") {
+                return Some(CandidateKind::Notice);
+            }
+            if matches_stem("readme") {
+                return Some(CandidateKind::Readme);
+            }
+        }
+
+        if SOURCE_EXTENSIONS.contains(&extension) {
+            return Some(CandidateKind::SourceHeader);
+        }
+        None
+    }
+
+    /// Returns the line comment markers of the language with the given (lower case) extension.
+    ///
+    /// Extensions that are not in [SOURCE_EXTENSIONS] get every marker (`//`, `#`, `--`, `;`).
+    fn line_comment_markers(extension: &str) -> &'static [&'static str] {
+        match extension {
+            "rs" | "c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" | "cs" | "java" | "kt" | "kts" | "scala"
+            | "groovy" | "go" | "swift" | "m" | "mm" | "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "dart" => &["//"],
+            "py" | "rb" | "pl" | "pm" | "sh" | "bash" | "zsh" | "r" => &["#"],
+            "php" => &["//", "#"],
+            "lua" | "hs" | "sql" => &["--"],
+            _ => &LINE_MARKERS,
+        }
+    }
+
+    /// Returns the comment at the start of a source file, skipping a leading shebang and blank lines.
+    ///
+    /// Line comments (chosen by the `extension` of the file, e.g. `//` for `rs` and `#` for `py`) and block comments
+    /// (`/* */`, `<!-- -->`, `"""`) are recognized, the comment ends at the first line of code.
+    /// Returns `None` if the file does not start with a comment.
+    pub fn leading_comment(source: &str, extension: &str, max_lines: usize) -> Option<String> {
+        let line_markers = line_comment_markers(&extension.to_lowercase());
+        const BLOCK_MARKERS: [(&str, &str); 3] = [("/*", "*/"), ("<!--", "-->"), ("\"\"\"", "\"\"\"")];
+
+        let mut comment = String::new();
+        let mut block_end: Option<&str> = None;
+
+        for (i, line) in source.lines().take(max_lines).enumerate() {
+            let trimmed = line.trim();
+            if let Some(end) = block_end {
+                comment.push_str(line);
+                comment.push('\n');
+                if trimmed.contains(end) {
+                    block_end = None;
+                }
+                continue;
+            }
+            if trimmed.is_empty() || (i == 0 && trimmed.starts_with("#!")) {
+                continue;
+            }
+            if let Some((start, end)) = BLOCK_MARKERS.iter().find(|(start, _)| trimmed.starts_with(start)) {
+                comment.push_str(line);
+                comment.push('\n');
+                if !trimmed[start.len()..].contains(end) {
+                    block_end = Some(end);
+                }
+                continue;
+            }
+            if line_markers.iter().any(|marker| trimmed.starts_with(marker)) {
+                comment.push_str(line);
+                comment.push('\n');
+                continue;
+            }
+            break;
+        }
+
+        if comment.is_empty() {
+            None
+        } else {
+            Some(comment)
+        }
+    }
+
+    /// Walks the directory tree below `root` and runs the algorithm on every candidate file (see [classify_path]).
+    ///
+    /// License, notice and readme files are always reported, even if nothing was detected in them.
+    /// Source files are only reported when their leading comment (see [leading_comment]) matched a license.
+    /// Hidden files and directories are not visited.
+    ///
+    /// # Errors
+    /// Returns an error if `root` can not be read or one of the exclude patterns is invalid.
+    /// Files that can not be read during the walk are listed in [ScanReport::skipped] instead.
+    ///
+    /// ```
+    /// use whichlicense_detection::*;
+    /// use whichlicense_detection::detecting::fuzzy_implementation::fuzzy_implementation::FuzzyDetection;
+    ///
+    /// let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    /// fuzzy.add_plain("apache-2.0", &std::fs::read_to_string("./LICENSE").unwrap());
+    ///
+    /// let options = ScanOptions { excludes: vec!["target/".to_string()], ..Default::default() };
+    /// let report = scan_directory(std::path::Path::new("."), &fuzzy, &options).unwrap();
+    /// assert!(report.detected().any(|f| f.path.as_os_str() == "LICENSE"));
+    /// ```
+    pub fn scan_directory<T: Serialize>(
+        root: &Path,
+        alg: &dyn LicenseListActions<T>,
+        options: &ScanOptions,
+    ) -> Result<ScanReport, DetectionError> {
+        // fail early on a missing or unreadable root, the walker would otherwise only report it as a skipped entry.
+        fs::read_dir(root)?;
+
+        let mut overrides = OverrideBuilder::new(root);
+        for pattern in options.excludes.iter() {
+            overrides
+                .add(&format!("!{}", pattern))
+                .map_err(|e| DetectionError::InvalidParameter(format!("invalid exclude pattern '{}': {}", pattern, e)))?;
+        }
+        let overrides = overrides
+            .build()
+            .map_err(|e| DetectionError::InvalidParameter(e.to_string()))?;
+
+        let walker = WalkBuilder::new(root)
+            .overrides(overrides)
+            .git_ignore(options.respect_gitignore)
+            .git_exclude(options.respect_gitignore)
+            .ignore(options.respect_gitignore)
+            .git_global(false)
+            .parents(false)
+            .require_git(false)
+            .build();
+
+        let mut report = ScanReport::default();
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    if let Some(path) = error_path(&e) {
+                        report.skipped.push(SkippedFile {
+                            path: relative_path(root, &path),
+                            reason: SkipReason::Unreadable(e.to_string()),
+                        });
+                    }
+                    continue;
+                }
+            };
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let kind = match classify_path(entry.path()) {
+                Some(CandidateKind::SourceHeader) if !options.scan_source_headers => continue,
+                Some(kind) => kind,
+                None => continue,
+            };
+            let path = relative_path(root, entry.path());
+
+            let text = match read_candidate(entry.path(), options.max_file_size) {
+                Ok(text) => text,
+                Err(reason) => {
+                    report.skipped.push(SkippedFile { path, reason });
+                    continue;
+                }
+            };
+
+            if kind == CandidateKind::SourceHeader {
+                let extension = entry.path().extension().unwrap_or_default().to_string_lossy();
+                let Some(header) = leading_comment(&text, &extension, options.max_header_lines) else {
+                    continue;
+                };
+                let matches = alg.match_by_plain_text(&header);
+                if !matches.is_empty() {
+                    report.files.push(FileReport { path, kind, matches });
+                }
+            } else {
+                let matches = alg.match_by_plain_text(&text);
+                report.files.push(FileReport { path, kind, matches });
+            }
+        }
+
+        report.files.sort_by(|a, b| a.path.cmp(&b.path));
+        report.skipped.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(report)
+    }
+
+    fn read_candidate(path: &Path, max_file_size: u64) -> Result<String, SkipReason> {
+        let size = fs::metadata(path)
+            .map_err(|e| SkipReason::Unreadable(e.to_string()))?
+            .len();
+        if size > max_file_size {
+            return Err(SkipReason::TooLarge(size));
+        }
+        let raw = fs::read(path).map_err(|e| SkipReason::Unreadable(e.to_string()))?;
+        if raw.contains(&0) {
+            return Err(SkipReason::Binary);
+        }
+        Ok(String::from_utf8_lossy(&raw).into_owned())
+    }
+
+    fn relative_path(root: &Path, path: &Path) -> PathBuf {
+        path.strip_prefix(root).unwrap_or(path).to_path_buf()
+    }
+
+    fn error_path(e: &ignore::Error) -> Option<PathBuf> {
+        match e {
+            ignore::Error::WithPath { path, .. } => Some(path.clone()),
+            ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => error_path(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,206 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+pub mod segmenting {
+    use std::ops::Range;
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::{LicenseListActions, LicenseMatch};
+
+    /// A paragraph of the original text, the unit [detect_spans] grows its windows by.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct TextBlock {
+        /// The bytes of the block in the original text, without the indentation of its first line and the trailing line break.
+        pub byte_range: Range<usize>,
+        /// The first line of the block (1-based).
+        pub start_line: usize,
+        /// The last line of the block (1-based, inclusive).
+        pub end_line: usize,
+    }
+
+    /// A license detected in a part of a larger text.
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct LicenseSpan {
+        /// The matches of the span, sorted as returned by [LicenseListActions::match_by_plain_text] so the first one is the detected license.
+        /// Spans returned by [detect_spans] always have at least one match.
+        pub matches: Vec<LicenseMatch>,
+        /// The bytes of the span in the original text, `&text[span.byte_range.clone()]` is the text that was matched.
+        pub byte_range: Range<usize>,
+        /// The first line of the span (1-based).
+        pub start_line: usize,
+        /// The last line of the span (1-based, inclusive).
+        pub end_line: usize,
+    }
+
+    impl LicenseSpan {
+        /// The best match of the span.
+        ///
+        /// > Panics when [LicenseSpan::matches] is empty, which is never the case for spans returned by [detect_spans].
+        pub fn license(&self) -> &LicenseMatch {
+            &self.matches[0]
+        }
+    }
+
+    /// Configures how [detect_spans] searches a text.
+    #[derive(Debug, Clone)]
+    pub struct SegmentOptions {
+        /// The top match of a span needs at least this confidence (0-100). Defaults to 80.
+        ///
+        /// This is applied on top of the minimum confidence of the algorithm, parts of a license often match another license with a lower confidence.
+        pub min_confidence: f32,
+        /// The maximum number of blocks a single span can consist of. Defaults to 100.
+        pub max_blocks: usize,
+        /// Windows shorter than this (in bytes) are not matched but grown further,
+        /// very short texts produce unreliable matches with every algorithm. Defaults to 100.
+        pub min_span_length: usize,
+        /// Stop growing a window after this many consecutive blocks did not reach the best confidence found so far,
+        /// or did not match anything at all while nothing was found yet. Defaults to 20.
+        pub patience: usize,
+    }
+
+    impl Default for SegmentOptions {
+        fn default() -> Self {
+            Self {
+                min_confidence: 80.0,
+                max_blocks: 100,
+                min_span_length: 100,
+                patience: 20,
+            }
+        }
+    }
+
+    /// Returns true for lines that only consist of separator characters, e.g. `-----` or `=====`.
+    fn is_separator(line: &str) -> bool {
+        let line = line.trim();
+        line.len() >= 3 && line.chars().all(|c| matches!(c, '-' | '=' | '*' | '_' | '#' | '~'))
+    }
+
+    /// Splits a text into blocks on its structural boundaries, blank lines and separator lines (e.g. `-----`).
+    ///
+    /// The boundaries themselves are not part of any block.
+    pub fn split_blocks(text: &str) -> Vec<TextBlock> {
+        let mut blocks: Vec<TextBlock> = Vec::new();
+        let mut current: Option<TextBlock> = None;
+        let mut offset = 0;
+
+        for (i, line) in text.split_inclusive('\n').enumerate() {
+            let line_start = offset;
+            offset += line.len();
+            let content = line.trim_end_matches(['\r', '\n']);
+
+            if content.trim().is_empty() || is_separator(content) {
+                blocks.extend(current.take());
+                continue;
+            }
+
+            let line_end = line_start + content.len();
+            match current.as_mut() {
+                Some(block) => {
+                    block.byte_range.end = line_end;
+                    block.end_line = i + 1;
+                }
+                None => {
+                    current = Some(TextBlock {
+                        byte_range: line_start + content.len() - content.trim_start().len()..line_end,
+                        start_line: i + 1,
+                        end_line: i + 1,
+                    })
+                }
+            }
+        }
+        blocks.extend(current);
+        blocks
+    }
+
+    /// Detects every license in a text that may contain several licenses, such as a dual license `LICENSE` file or a third-party notices file.
+    ///
+    /// The text is split into blocks (see [split_blocks]), then starting at the first block a window is grown one block at a time
+    /// and matched against the algorithm. The largest window with the best top match becomes a span, minus any leading blocks
+    /// that do not lower its confidence, after which the search continues at the block following it. When no window reaches [SegmentOptions::min_confidence] the start block is skipped.
+    ///
+    /// Returns the spans in the order they appear in the text, spans never overlap.
+    /// > The same license repeated directly after itself matches as well as a single copy and is reported as one span.
+    pub fn detect_spans<T: Serialize>(alg: &dyn LicenseListActions<T>, text: &str, options: &SegmentOptions) -> Vec<LicenseSpan> {
+        let blocks = split_blocks(text);
+        let mut spans: Vec<LicenseSpan> = Vec::new();
+
+        let mut start = 0;
+        while start < blocks.len() {
+            // (index of the last block, matches) of the best window starting at `start`.
+            let mut best: Option<(usize, Vec<LicenseMatch>)> = None;
+            let mut misses = 0;
+
+            for end in start..blocks.len().min(start + options.max_blocks) {
+                let window = blocks[start].byte_range.start..blocks[end].byte_range.end;
+                if window.len() < options.min_span_length {
+                    continue;
+                }
+
+                let matches = alg.match_by_plain_text(&text[window]);
+                let confidence = matches.first().map_or(0.0, |m| m.confidence);
+                let best_confidence = best.as_ref().and_then(|(_, m)| m.first()).map_or(0.0, |m| m.confidence);
+
+                // prefer the larger window on equal confidence, so a license is consumed as a whole.
+                if !matches.is_empty() && confidence >= options.min_confidence && confidence >= best_confidence {
+                    best = Some((end, matches));
+                    misses = 0;
+                } else if best.is_none() && !matches.is_empty() {
+                    // a window that matches something below the minimum confidence may still grow into a full license.
+                    misses = 0;
+                } else {
+                    misses += 1;
+                    if misses >= options.patience {
+                        break;
+                    }
+                }
+            }
+
+            match best {
+                Some((end, mut matches)) => {
+                    // drop leading blocks (e.g. a heading) that do not contribute to the match.
+                    let mut first = start;
+                    while first < end {
+                        let window = blocks[first + 1].byte_range.start..blocks[end].byte_range.end;
+                        if window.len() < options.min_span_length {
+                            break;
+                        }
+                        let trimmed = alg.match_by_plain_text(&text[window]);
+                        match trimmed.first() {
+                            Some(top) if top.name == matches[0].name && top.confidence >= matches[0].confidence => {
+                                matches = trimmed;
+                                first += 1;
+                            }
+                            _ => break,
+                        }
+                    }
+
+                    spans.push(LicenseSpan {
+                        matches,
+                        byte_range: blocks[first].byte_range.start..blocks[end].byte_range.end,
+                        start_line: blocks[first].start_line,
+                        end_line: blocks[end].end_line,
+                    });
+                    start = end + 1;
+                }
+                None => start += 1,
+            }
+        }
+
+        spans
+    }
+}
@@ -0,0 +1,478 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+pub mod spdx {
+    use std::{collections::HashMap, fmt, str::FromStr};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::{DetectionError, LicenseMatch, LicenseMetadata};
+
+    /// ScanCode license keys and the SPDX identifier they correspond to, see [SpdxMapping].
+    const SCANCODE_TO_SPDX: [(&str, &str); 62] = [
+        ("0bsd", "0BSD"),
+        ("afl-3.0", "AFL-3.0"),
+        ("agpl-3.0", "AGPL-3.0-only"),
+        ("agpl-3.0-plus", "AGPL-3.0-or-later"),
+        ("apache-1.0", "Apache-1.0"),
+        ("apache-1.1", "Apache-1.1"),
+        ("apache-2.0", "Apache-2.0"),
+        ("artistic-2.0", "Artistic-2.0"),
+        ("boost-1.0", "BSL-1.0"),
+        ("bsd-new", "BSD-3-Clause"),
+        ("bsd-original", "BSD-4-Clause"),
+        ("bsd-simplified", "BSD-2-Clause"),
+        ("bsd-zero", "0BSD"),
+        ("cc-by-3.0", "CC-BY-3.0"),
+        ("cc-by-4.0", "CC-BY-4.0"),
+        ("cc-by-sa-3.0", "CC-BY-SA-3.0"),
+        ("cc-by-sa-4.0", "CC-BY-SA-4.0"),
+        ("cc0-1.0", "CC0-1.0"),
+        ("cddl-1.0", "CDDL-1.0"),
+        ("cddl-1.1", "CDDL-1.1"),
+        ("epl-1.0", "EPL-1.0"),
+        ("epl-2.0", "EPL-2.0"),
+        ("eupl-1.1", "EUPL-1.1"),
+        ("eupl-1.2", "EUPL-1.2"),
+        ("gpl-1.0", "GPL-1.0-only"),
+        ("gpl-1.0-plus", "GPL-1.0-or-later"),
+        ("gpl-2.0", "GPL-2.0-only"),
+        ("gpl-2.0-plus", "GPL-2.0-or-later"),
+        ("gpl-3.0", "GPL-3.0-only"),
+        ("gpl-3.0-plus", "GPL-3.0-or-later"),
+        ("isc", "ISC"),
+        ("lgpl-2.0", "LGPL-2.0-only"),
+        ("lgpl-2.0-plus", "LGPL-2.0-or-later"),
+        ("lgpl-2.1", "LGPL-2.1-only"),
+        ("lgpl-2.1-plus", "LGPL-2.1-or-later"),
+        ("lgpl-3.0", "LGPL-3.0-only"),
+        ("lgpl-3.0-plus", "LGPL-3.0-or-later"),
+        ("mit", "MIT"),
+        ("mit-0", "MIT-0"),
+        ("mpl-1.0", "MPL-1.0"),
+        ("mpl-1.1", "MPL-1.1"),
+        ("mpl-2.0", "MPL-2.0"),
+        ("ms-pl", "MS-PL"),
+        ("ms-rl", "MS-RL"),
+        ("ofl-1.1", "OFL-1.1"),
+        ("openssl-ssleay", "OpenSSL"),
+        ("php-3.01", "PHP-3.01"),
+        ("postgresql", "PostgreSQL"),
+        ("python", "Python-2.0"),
+        ("unicode", "Unicode-DFS-2015"),
+        ("unlicense", "Unlicense"),
+        ("upl-1.0", "UPL-1.0"),
+        ("w3c", "W3C"),
+        ("wtfpl-2.0", "WTFPL"),
+        ("x11", "X11"),
+        ("zlib", "Zlib"),
+        ("zpl-2.1", "ZPL-2.1"),
+        // exceptions
+        ("autoconf-exception-3.0", "Autoconf-exception-3.0"),
+        ("bison-exception-2.2", "Bison-exception-2.2"),
+        ("classpath-exception-2.0", "Classpath-exception-2.0"),
+        ("gcc-exception-3.1", "GCC-exception-3.1"),
+        ("llvm-exception", "LLVM-exception"),
+    ];
+
+    /// A single license identifier, e.g. `MIT`, `GPL-2.0+` or `LicenseRef-scancode-foo`.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub struct SpdxLicense {
+        pub id: String,
+        /// Whether the identifier was followed by `+` (this version or any later version).
+        pub or_later: bool,
+    }
+
+    impl SpdxLicense {
+        pub fn new(id: &str) -> Self {
+            Self {
+                id: id.to_string(),
+                or_later: false,
+            }
+        }
+    }
+
+    impl fmt::Display for SpdxLicense {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}{}", self.id, if self.or_later { "+" } else { "" })
+        }
+    }
+
+    /// An SPDX license expression as defined in annex D of the SPDX specification.
+    ///
+    /// Parsed with [FromStr] and written with [Display](fmt::Display), both (de)serialize as the expression string.
+    /// As the specification requires, the operators `AND`, `OR` and `WITH` are only recognized in uppercase.
+    /// ```
+    /// use whichlicense_detection::SpdxExpression;
+    ///
+    /// let expression: SpdxExpression = "MIT AND (Apache-2.0 OR GPL-2.0-or-later WITH Classpath-exception-2.0)".parse().unwrap();
+    /// assert_eq!(expression.to_string(), "MIT AND (Apache-2.0 OR GPL-2.0-or-later WITH Classpath-exception-2.0)");
+    /// ```
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub enum SpdxExpression {
+        License(SpdxLicense),
+        /// A license with an exception (e.g. `GPL-2.0-only WITH Classpath-exception-2.0`).
+        With(SpdxLicense, String),
+        /// All of the expressions apply.
+        And(Vec<SpdxExpression>),
+        /// Any one of the expressions can be chosen.
+        Or(Vec<SpdxExpression>),
+    }
+
+    impl SpdxExpression {
+        /// Combines expressions with `AND`, flattening nested `AND` expressions. Returns `None` for an empty list.
+        pub fn and(expressions: Vec<SpdxExpression>) -> Option<SpdxExpression> {
+            Self::combine(expressions, true)
+        }
+
+        /// Combines expressions with `OR`, flattening nested `OR` expressions. Returns `None` for an empty list.
+        pub fn or(expressions: Vec<SpdxExpression>) -> Option<SpdxExpression> {
+            Self::combine(expressions, false)
+        }
+
+        fn combine(expressions: Vec<SpdxExpression>, and: bool) -> Option<SpdxExpression> {
+            let mut flattened: Vec<SpdxExpression> = Vec::new();
+            for expression in expressions {
+                let inner = match expression {
+                    Self::And(inner) if and => inner,
+                    Self::Or(inner) if !and => inner,
+                    other => vec![other],
+                };
+                for e in inner {
+                    if !flattened.contains(&e) {
+                        flattened.push(e);
+                    }
+                }
+            }
+            match flattened.len() {
+                0 => None,
+                1 => flattened.pop(),
+                _ if and => Some(Self::And(flattened)),
+                _ => Some(Self::Or(flattened)),
+            }
+        }
+
+        /// Returns every license of the expression, in order of appearance.
+        pub fn licenses(&self) -> Vec<&SpdxLicense> {
+            match self {
+                Self::License(license) | Self::With(license, _) => vec![license],
+                Self::And(inner) | Self::Or(inner) => inner.iter().flat_map(|e| e.licenses()).collect(),
+            }
+        }
+
+        /// Operator precedence, higher binds tighter.
+        fn precedence(&self) -> u8 {
+            match self {
+                Self::Or(_) => 0,
+                Self::And(_) => 1,
+                Self::License(_) | Self::With(..) => 2,
+            }
+        }
+    }
+
+    impl fmt::Display for SpdxExpression {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::License(license) => write!(f, "{}", license),
+                Self::With(license, exception) => write!(f, "{} WITH {}", license, exception),
+                Self::And(inner) | Self::Or(inner) => {
+                    let operator = if matches!(self, Self::And(_)) { " AND " } else { " OR " };
+                    for (i, e) in inner.iter().enumerate() {
+                        if i > 0 {
+                            f.write_str(operator)?;
+                        }
+                        if e.precedence() <= self.precedence() {
+                            write!(f, "({})", e)?;
+                        } else {
+                            write!(f, "{}", e)?;
+                        }
+                    }
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    impl FromStr for SpdxExpression {
+        type Err = DetectionError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let tokens = tokenize(s)?;
+            let mut parser = Parser { tokens, position: 0 };
+            let expression = parser.parse_or()?;
+            match parser.tokens.get(parser.position) {
+                None => Ok(expression),
+                Some(token) => Err(invalid(s, &format!("unexpected '{}'", token))),
+            }
+        }
+    }
+
+    impl Serialize for SpdxExpression {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for SpdxExpression {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let s = String::deserialize(deserializer)?;
+            s.parse().map_err(serde::de::Error::custom)
+        }
+    }
+
+    fn invalid(expression: &str, reason: &str) -> DetectionError {
+        DetectionError::InvalidExpression(format!("'{}': {}", expression, reason))
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Token {
+        Open,
+        Close,
+        And,
+        Or,
+        With,
+        Id(String),
+    }
+
+    impl fmt::Display for Token {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Open => write!(f, "("),
+                Self::Close => write!(f, ")"),
+                Self::And => write!(f, "AND"),
+                Self::Or => write!(f, "OR"),
+                Self::With => write!(f, "WITH"),
+                Self::Id(id) => write!(f, "{}", id),
+            }
+        }
+    }
+
+    fn tokenize(s: &str) -> Result<Vec<Token>, DetectionError> {
+        let mut tokens = Vec::new();
+        let mut chars = s.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            match c {
+                '(' => tokens.push(Token::Open),
+                ')' => tokens.push(Token::Close),
+                c if c.is_whitespace() => {}
+                c if c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | ':' | '+') => {
+                    let mut end = start + c.len_utf8();
+                    while let Some((i, c)) = chars.peek() {
+                        if !(c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | ':' | '+')) {
+                            break;
+                        }
+                        end = i + c.len_utf8();
+                        chars.next();
+                    }
+                    tokens.push(match &s[start..end] {
+                        "AND" => Token::And,
+                        "OR" => Token::Or,
+                        "WITH" => Token::With,
+                        word => Token::Id(word.to_string()),
+                    });
+                }
+                c => return Err(invalid(s, &format!("unexpected character '{}'", c))),
+            }
+        }
+        if tokens.is_empty() {
+            return Err(invalid(s, "empty expression"));
+        }
+        Ok(tokens)
+    }
+
+    /// Recursive descent parser, `WITH` binds tighter than `AND` which binds tighter than `OR`.
+    struct Parser {
+        tokens: Vec<Token>,
+        position: usize,
+    }
+
+    impl Parser {
+        fn next(&mut self) -> Option<Token> {
+            let token = self.tokens.get(self.position).cloned();
+            self.position += 1;
+            token
+        }
+
+        fn eat(&mut self, expected: Token) -> bool {
+            if self.tokens.get(self.position) == Some(&expected) {
+                self.position += 1;
+                return true;
+            }
+            false
+        }
+
+        fn error(&self, reason: &str) -> DetectionError {
+            let expression: Vec<String> = self.tokens.iter().map(|t| t.to_string()).collect();
+            invalid(&expression.join(" "), reason)
+        }
+
+        fn parse_or(&mut self) -> Result<SpdxExpression, DetectionError> {
+            let mut operands = vec![self.parse_and()?];
+            while self.eat(Token::Or) {
+                operands.push(self.parse_and()?);
+            }
+            Ok(if operands.len() == 1 { operands.remove(0) } else { SpdxExpression::Or(operands) })
+        }
+
+        fn parse_and(&mut self) -> Result<SpdxExpression, DetectionError> {
+            let mut operands = vec![self.parse_primary()?];
+            while self.eat(Token::And) {
+                operands.push(self.parse_primary()?);
+            }
+            Ok(if operands.len() == 1 { operands.remove(0) } else { SpdxExpression::And(operands) })
+        }
+
+        fn parse_primary(&mut self) -> Result<SpdxExpression, DetectionError> {
+            match self.next() {
+                Some(Token::Open) => {
+                    let expression = self.parse_or()?;
+                    if !self.eat(Token::Close) {
+                        return Err(self.error("missing closing parenthesis"));
+                    }
+                    Ok(expression)
+                }
+                Some(Token::Id(id)) => {
+                    let license = parse_license(&id).ok_or_else(|| self.error(&format!("invalid license identifier '{}'", id)))?;
+                    if !self.eat(Token::With) {
+                        return Ok(SpdxExpression::License(license));
+                    }
+                    match self.next() {
+                        Some(Token::Id(exception)) if !exception.contains('+') => Ok(SpdxExpression::With(license, exception)),
+                        _ => Err(self.error("expected an exception identifier after WITH")),
+                    }
+                }
+                Some(token) => Err(self.error(&format!("expected a license identifier, found '{}'", token))),
+                None => Err(self.error("unexpected end of expression")),
+            }
+        }
+    }
+
+    fn parse_license(id: &str) -> Option<SpdxLicense> {
+        let (id, or_later) = match id.strip_suffix('+') {
+            Some(id) => (id, true),
+            None => (id, false),
+        };
+        if id.is_empty() || id.contains('+') {
+            return None;
+        }
+        Some(SpdxLicense {
+            id: id.to_string(),
+            or_later,
+        })
+    }
+
+    /// Maps the names of database entries (ScanCode license keys, optionally with the `.LICENSE` extension) to SPDX identifiers.
+    ///
+    /// The default mapping knows the most common licenses and exceptions, any other key is mapped to
+    /// `LicenseRef-scancode-<key>` which is how ScanCode itself refers to licenses without an SPDX identifier. Characters
+    /// SPDX does not allow in identifiers (anything but letters, digits, `.` and `-`) are replaced with `-`.
+    #[derive(Debug, Clone)]
+    pub struct SpdxMapping {
+        ids: HashMap<String, String>,
+        exceptions: HashMap<String, bool>,
+    }
+
+    impl Default for SpdxMapping {
+        fn default() -> Self {
+            let mut mapping = Self::empty();
+            for (key, id) in SCANCODE_TO_SPDX.iter() {
+                mapping.insert(key, id, key.contains("exception"));
+            }
+            mapping
+        }
+    }
+
+    impl SpdxMapping {
+        /// A mapping without any built-in entries, every key maps to `LicenseRef-scancode-<key>`.
+        pub fn empty() -> Self {
+            Self {
+                ids: HashMap::new(),
+                exceptions: HashMap::new(),
+            }
+        }
+
+        /// Adds or replaces the SPDX identifier of a key, `is_exception` marks identifiers that are used after `WITH`.
+        pub fn insert(&mut self, key: &str, spdx_id: &str, is_exception: bool) {
+            let key = scancode_key(key).to_lowercase();
+            self.ids.insert(key.clone(), spdx_id.to_string());
+            self.exceptions.insert(key, is_exception);
+        }
+
+        /// Adds the SPDX identifier found in the metadata of a license, metadata without an SPDX identifier is ignored.
+        pub fn insert_metadata(&mut self, name: &str, metadata: &LicenseMetadata) {
+            if let Some(spdx_id) = &metadata.spdx_license_key {
+                self.insert(name, spdx_id, metadata.is_exception);
+            }
+        }
+
+        /// Returns the SPDX identifier of a database entry.
+        pub fn spdx_id(&self, name: &str) -> String {
+            let key = scancode_key(name).to_lowercase();
+            match self.ids.get(&key) {
+                Some(id) => id.clone(),
+                None => format!(
+                    "LicenseRef-scancode-{}",
+                    key.replace(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-')), "-")
+                ),
+            }
+        }
+
+        /// Returns whether a database entry is a license exception (e.g. `classpath-exception-2.0`).
+        pub fn is_exception(&self, name: &str) -> bool {
+            let key = scancode_key(name).to_lowercase();
+            self.exceptions.get(&key).copied().unwrap_or(false)
+        }
+
+        /// Combines the licenses found in a text (e.g. the top match of every span) into a single `AND` expression.
+        ///
+        /// Exceptions are attached with `WITH` to the license preceding them, duplicate licenses are only included once.
+        /// Returns `None` when no names are given.
+        pub fn expression<S: AsRef<str>>(&self, names: &[S]) -> Option<SpdxExpression> {
+            let mut expressions: Vec<SpdxExpression> = Vec::new();
+            for name in names.iter().map(|n| n.as_ref()) {
+                let id = self.spdx_id(name);
+                if self.is_exception(name) {
+                    if let Some(SpdxExpression::License(license)) = expressions.last() {
+                        let license = license.clone();
+                        *expressions.last_mut().unwrap() = SpdxExpression::With(license, id);
+                        continue;
+                    }
+                    // an exception without a license to attach it to, keep it visible as a license of its own.
+                }
+                expressions.push(SpdxExpression::License(SpdxLicense::new(&id)));
+            }
+            SpdxExpression::and(expressions)
+        }
+
+        /// Same as [expression](SpdxMapping::expression) but prefers the SPDX identifiers found in the metadata of the matches.
+        pub fn expression_of_matches(&self, matches: &[&LicenseMatch]) -> Option<SpdxExpression> {
+            let mut mapping = self.clone();
+            for m in matches.iter() {
+                if let Some(metadata) = &m.metadata {
+                    mapping.insert_metadata(&m.name, metadata);
+                }
+            }
+            let names: Vec<&str> = matches.iter().map(|m| m.name.as_str()).collect();
+            mapping.expression(&names)
+        }
+    }
+
+    /// Returns the ScanCode key of a database entry name, stripping the `.LICENSE` extension added by `load_licenses_from_folder`.
+    pub fn scancode_key(name: &str) -> &str {
+        name.strip_suffix(".LICENSE").unwrap_or(name)
+    }
+}
@@ -0,0 +1,148 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+/// JavaScript bindings generated by wasm-bindgen, see the README for a usage example.
+///
+/// Results are returned as plain JS objects (e.g. `{ name, confidence, metadata }` for a match) and errors are thrown as JS `Error`s.
+pub mod wasm {
+    use serde::Serialize;
+    use wasm_bindgen::prelude::*;
+
+    use crate::{
+        detecting::dynamic::dynamic::{Algorithm, AnyDetector},
+        strip_license, DetectionError, Pipeline, SegmentDefinition,
+    };
+
+    fn parse_algorithm(algorithm: &str) -> Result<Algorithm, JsError> {
+        match algorithm {
+            "fuzzy" => Ok(Algorithm::Fuzzy),
+            "gaoya" => Ok(Algorithm::Gaoya),
+            "block-hash" => Ok(Algorithm::BlockHash),
+            _ => Err(JsError::new(&format!(
+                "unknown algorithm '{}', expected 'fuzzy', 'gaoya' or 'block-hash'",
+                algorithm
+            ))),
+        }
+    }
+
+    fn to_js_error(error: DetectionError) -> JsError {
+        JsError::new(&error.to_string())
+    }
+
+    fn to_js_value<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, JsError> {
+        // maps become plain objects instead of JS Maps.
+        value
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// A license detector using the `"fuzzy"`, `"gaoya"` or `"block-hash"` algorithm.
+    #[wasm_bindgen(js_name = Detector)]
+    pub struct WasmDetector {
+        inner: AnyDetector,
+    }
+
+    #[wasm_bindgen(js_class = Detector)]
+    impl WasmDetector {
+        /// Creates a detector without any licenses.
+        #[wasm_bindgen(constructor)]
+        pub fn new(algorithm: &str) -> Result<WasmDetector, JsError> {
+            Ok(Self {
+                inner: AnyDetector::new(parse_algorithm(algorithm)?).map_err(to_js_error)?,
+            })
+        }
+
+        /// Creates a detector from a database, the algorithm and its parameters are taken from the database's header.
+        #[wasm_bindgen(js_name = fromDatabase)]
+        pub fn from_database(data: &[u8]) -> Result<WasmDetector, JsError> {
+            Ok(Self {
+                inner: AnyDetector::load(data).map_err(to_js_error)?,
+            })
+        }
+
+        /// Creates a detector using the database embedded in the module.
+        #[cfg(feature = "embedded-database")]
+        #[wasm_bindgen(js_name = withDefaultDatabase)]
+        pub fn with_default_database(algorithm: &str) -> Result<WasmDetector, JsError> {
+            Ok(Self {
+                inner: AnyDetector::with_default_database(parse_algorithm(algorithm)?).map_err(to_js_error)?,
+            })
+        }
+
+        /// Replaces the licenses of this detector with the ones in the database.
+        ///
+        /// The current licenses are removed first, the detector is left without licenses when the database is invalid.
+        #[wasm_bindgen(js_name = loadFromMemory)]
+        pub fn load_from_memory(&mut self, data: &[u8]) -> Result<(), JsError> {
+            self.inner.clear();
+            self.inner.try_load_from_memory(data).map_err(to_js_error)
+        }
+
+        /// Serializes the licenses of this detector into a database.
+        #[wasm_bindgen(js_name = saveToMemory)]
+        pub fn save_to_memory(&self) -> Result<Vec<u8>, JsError> {
+            self.inner.try_save_to_memory().map_err(to_js_error)
+        }
+
+        #[wasm_bindgen(js_name = addLicense)]
+        pub fn add_license(&mut self, name: &str, text: &str) {
+            self.inner.add_plain(name, text);
+        }
+
+        #[wasm_bindgen(js_name = removeLicense)]
+        pub fn remove_license(&mut self, name: &str) {
+            self.inner.remove(name);
+        }
+
+        /// Sets the confidence (0-100) below which matches are left out, larger values throw.
+        #[wasm_bindgen(js_name = setMinConfidence)]
+        pub fn set_min_confidence(&mut self, min_confidence: u8) -> Result<(), JsError> {
+            self.inner
+                .set_min_confidence(min_confidence)
+                .map_err(|e| JsError::new(&e.to_string()))
+        }
+
+        /// Returns the sorted names of the licenses known to this detector.
+        #[wasm_bindgen(js_name = licenseNames)]
+        pub fn license_names(&self) -> Vec<String> {
+            self.inner.license_names()
+        }
+
+        /// Returns an array of `{ name, confidence, metadata }` objects, best match first.
+        #[wasm_bindgen(js_name = matchByPlainText)]
+        pub fn match_by_plain_text(&self, text: &str) -> Result<JsValue, JsError> {
+            to_js_value(&self.inner.match_by_plain_text(text))
+        }
+
+        /// Runs a pipeline described by an array of segment definitions (see `SegmentDefinition`) on the text.
+        ///
+        /// Returns an array with the matches of every run, see `Pipeline::run`.
+        #[wasm_bindgen(js_name = runPipeline)]
+        pub fn run_pipeline(&self, segments: JsValue, text: &str, desired_confidence: f32) -> Result<JsValue, JsError> {
+            let definitions: Vec<SegmentDefinition> =
+                serde_wasm_bindgen::from_value(segments).map_err(|e| JsError::new(&e.to_string()))?;
+            let pipeline = Pipeline::from_definitions(&definitions).map_err(to_js_error)?;
+            to_js_value(&self.inner.run_pipeline(&pipeline, text, desired_confidence))
+        }
+    }
+
+    /// Normalizes a license text the way the detectors do before hashing it, see [strip_license].
+    #[wasm_bindgen(js_name = stripLicense)]
+    pub fn strip_license_js(text: &str) -> String {
+        strip_license(text)
+    }
+}
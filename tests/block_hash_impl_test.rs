@@ -0,0 +1,184 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+use std::fs;
+
+use whichlicense_detection::{
+    custom_fuzzy::custom_fuzzyhash::{calc_max_uncommon_blocks, compare_hashes, create_hash, fuzzy_hash},
+    detecting::block_hash_implementation::block_hash_implementation::BlockHashDetection,
+    *,
+};
+
+const MIT: &str = "Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the \"Software\"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.";
+
+fn detector() -> BlockHashDetection {
+    let mut block_hash = BlockHashDetection::builder().build().unwrap();
+    block_hash.add_plain("apache-2.0.LICENSE", &fs::read_to_string("./LICENSE").unwrap());
+    block_hash.add_plain("mit.LICENSE", MIT);
+    block_hash
+}
+
+#[test]
+fn it_hashes_blocks(){
+    assert_eq!(fuzzy_hash(b"abcdefgh", 3, 3).len(), 3);
+    assert_eq!(fuzzy_hash(b"", 3, 3), Vec::<i64>::new());
+    // only the first `hash_length` bytes of a block count.
+    assert_eq!(create_hash(b"abcx", 3), create_hash(b"abcy", 3));
+    assert_ne!(create_hash(b"abcx", 4), create_hash(b"abcy", 4));
+
+    // long blocks wrap around instead of overflowing.
+    let long = vec![255u8; 1024];
+    assert_eq!(fuzzy_hash(&long, 1024, 1024).len(), 1);
+}
+
+#[test]
+fn it_compares_hashes(){
+    assert_eq!(compare_hashes(&[1, 2, 3, 4], &[1, 2, 3, 4], 1.0), (4, 4, 1.0));
+    assert_eq!(compare_hashes(&[1, 2, 3, 4], &[1, 9, 3, 4], 0.5), (3, 4, 0.75));
+    // the missing blocks of the shorter hash count as uncommon, once.
+    assert_eq!(compare_hashes(&[1, 2, 3, 4], &[1, 2], 0.0), (2, 4, 0.5));
+    assert_eq!(compare_hashes(&[], &[], 0.0), (0, 0, 0.0));
+}
+
+#[test]
+fn it_exits_early_below_min_confidence(){
+    assert_eq!(calc_max_uncommon_blocks(0.75, 4), 1);
+    assert_eq!(calc_max_uncommon_blocks(0.0, 4), 4);
+    assert_eq!(compare_hashes(&[1, 2, 3, 4], &[1, 9, 9, 4], 0.75), (-1, 4, -1.0));
+    assert_eq!(compare_hashes(&[1, 2, 3, 4], &[1], 0.75), (-1, 4, -1.0));
+}
+
+#[test]
+fn it_finds_exact_match(){
+    let block_hash = detector();
+    let matches = block_hash.match_by_plain_text(&fs::read_to_string("./LICENSE").unwrap());
+
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].name, "apache-2.0.LICENSE");
+    assert_eq!(matches[0].confidence, 100.0);
+}
+
+#[test]
+fn it_detects_a_similar_license(){
+    let block_hash = detector();
+    // a change that keeps the length of the text only affects the blocks it is in.
+    let similar = MIT.replace("free of charge", "free of chorge");
+    let matches = block_hash.match_by_plain_text(&similar);
+
+    assert_eq!(matches[0].name, "mit.LICENSE");
+    assert!(matches[0].confidence > 90.0 && matches[0].confidence < 100.0);
+}
+
+#[test]
+fn it_fails_on_unknown(){
+    let block_hash = detector();
+    let matches = block_hash.match_by_plain_text("This is not a license. lorem ipsum dolor sit amet, consectetur adipiscing elit.");
+    assert!(matches.is_empty(), "Found a match for an unknown license!");
+}
+
+#[test]
+fn it_applies_min_confidence(){
+    let mut block_hash = detector();
+    let similar = MIT.replace("free of charge", "free of chorge");
+    block_hash.min_confidence = 100;
+    assert!(block_hash.match_by_plain_text(&similar).is_empty());
+
+    block_hash.min_confidence = 0;
+    let matches = block_hash.match_by_plain_text(&similar);
+    assert_eq!(matches.len(), 2);
+    assert!(matches.windows(2).all(|w| w[0].confidence >= w[1].confidence));
+}
+
+#[test]
+fn it_saves_and_loads_from_memory(){
+    let block_hash = detector();
+    let buffer = block_hash.try_save_to_memory().unwrap();
+
+    let header = read_database_header(&buffer).unwrap();
+    assert_eq!(header.algorithm, AlgorithmKind::BlockHash);
+    assert_eq!(header.parameters, AlgorithmParameters::BlockHash { block_size: 8, hash_length: 8 });
+    assert_eq!(header.license_count, 2);
+
+    let mut loaded = BlockHashDetection::builder().build().unwrap();
+    loaded.try_load_from_memory(&buffer).unwrap();
+    assert_eq!(loaded.get_license_list(), block_hash.get_license_list());
+    assert_eq!(loaded.match_by_plain_text(MIT)[0].name, "mit.LICENSE");
+}
+
+#[test]
+fn it_saves_and_loads_from_file(){
+    let path = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("block_hash_db");
+    let path = path.to_str().unwrap();
+    detector().try_save_to_file(path).unwrap();
+
+    let mut loaded = BlockHashDetection::builder().build().unwrap();
+    loaded.try_load_from_file(path).unwrap();
+    assert_eq!(loaded.licenses.len(), 2);
+}
+
+#[test]
+fn it_rejects_database_with_other_parameters(){
+    let buffer = detector().try_save_to_memory().unwrap();
+
+    let mut block_hash = BlockHashDetection::builder().block_size(16).hash_length(16).build().unwrap();
+    let res = block_hash.try_load_from_memory(&buffer);
+    assert!(matches!(res, Err(DetectionError::IncompatibleFormat(_))));
+    assert!(block_hash.licenses.is_empty());
+
+    let mut gaoya = detecting::gaoya_implementation::gaoya_implementation::GaoyaDetection::builder().build().unwrap();
+    assert!(matches!(gaoya.try_load_from_memory(&buffer), Err(DetectionError::IncompatibleFormat(_))));
+}
+
+#[test]
+fn remove_works(){
+    let mut block_hash = detector();
+    block_hash.remove("mit.LICENSE");
+
+    assert_eq!(block_hash.licenses.len(), 1);
+    assert!(block_hash.match_by_plain_text(MIT).is_empty());
+}
+
+#[test]
+fn it_rejects_invalid_parameters(){
+    assert!(matches!(BlockHashDetection::builder().block_size(0).build(), Err(DetectionError::InvalidParameter(_))));
+    assert!(matches!(BlockHashDetection::builder().hash_length(0).build(), Err(DetectionError::InvalidParameter(_))));
+    assert!(matches!(BlockHashDetection::builder().block_size(4).hash_length(8).build(), Err(DetectionError::InvalidParameter(_))));
+    assert!(matches!(BlockHashDetection::builder().min_confidence(101).build(), Err(DetectionError::InvalidParameter(_))));
+}
+
+#[test]
+fn it_matches_exact_texts_by_digest(){
+    let mut block_hash = detector();
+    block_hash.add_plain("mit-modified.LICENSE", &MIT.replace("free of charge", "free of chorge"));
+
+    let matches = block_hash.match_by_plain_text(MIT);
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].name, "mit.LICENSE");
+
+    let mut loaded = BlockHashDetection::builder().build().unwrap();
+    loaded.try_load_from_memory(&block_hash.try_save_to_memory().unwrap()).unwrap();
+    assert_eq!(loaded.digests.len(), 3);
+    assert!(loaded.licenses.iter().all(|l| l.digest.is_some()));
+}
@@ -0,0 +1,120 @@
+/*
+ *   Copyright (c) 2023 Duart Snel
+ *   All rights reserved.
+
+ *   Licensed under the Apache License, Version 2.0 (the "License");
+ *   you may not use this file except in compliance with the License.
+ *   You may obtain a copy of the License at
+
+ *   http://www.apache.org/licenses/LICENSE-2.0
+
+ *   Unless required by applicable law or agreed to in writing, software
+ *   distributed under the License is distributed on an "AS IS" BASIS,
+ *   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *   See the License for the specific language governing permissions and
+ *   limitations under the License.
+ */
+
+use std::sync::{Arc, Mutex};
+
+use whichlicense_detection::{detecting::fuzzy_implementation::fuzzy_implementation::FuzzyDetection, *};
+
+/// Runs the segment in a pipeline and returns the text it produced.
+fn apply(segment: Segment, text: &str) -> String {
+    let alg = FuzzyDetection::builder().build().unwrap();
+    let captured = Arc::new(Mutex::new(String::new()));
+    let sink = Arc::clone(&captured);
+    let pipeline = Pipeline::new(vec![Segment::Batch(vec![
+        segment,
+        Segment::Custom(Box::new(move |text, _| {
+            *sink.lock().unwrap() = text.to_string();
+            text.to_string()
+        })),
+    ])]);
+    pipeline.run(&alg, text, 101.0);
+    let result = captured.lock().unwrap().clone();
+    result
+}
+
+#[test]
+fn it_strips_copyright_lines() {
+    let text = "Copyright (c) 2020 Jane Doe\n// Copyright 2021 John Doe\n(c) ACME\n© 2022 Someone\nPermission is granted.\nThe copyright notice stays.";
+    assert_eq!(
+        apply(Segment::strip_copyright_lines(), text),
+        "Permission is granted.\nThe copyright notice stays."
+    );
+}
+
+#[test]
+fn it_strips_comment_markers() {
+    let text = "/*\n * Licensed under MIT.\n */\n// line comment\n# hash comment\n;; lisp comment\n-- sql comment\nREM batch comment\ncode(); /* trailing */";
+    assert_eq!(
+        apply(Segment::strip_comment_markers(), text),
+        "\nLicensed under MIT.\n\nline comment\nhash comment\nlisp comment\nsql comment\nbatch comment\ncode(); /* trailing */"
+    );
+    assert_eq!(apply(Segment::strip_comment_markers(), "REMARKS stay"), "REMARKS stay");
+    assert_eq!(apply(Segment::strip_comment_markers(), "/* single line */\nx = 1; /* a */ y = 2; /* b */"), "single line\nx = 1; /* a */ y = 2; /* b */");
+}
+
+#[test]
+fn it_strips_all_rights_reserved() {
+    assert_eq!(
+        apply(Segment::strip_all_rights_reserved(), "Copyright ACME. All Rights\nReserved. Use freely."),
+        "Copyright ACME.  Use freely."
+    );
+}
+
+#[test]
+fn it_removes_urls() {
+    assert_eq!(
+        apply(Segment::remove_urls(), "See https://example.com/license.html. Or (www.example.org/x), ftp://host/file!"),
+        "See . Or (), !"
+    );
+}
+
+#[test]
+fn it_removes_bullets() {
+    let text = "- first\n* second\n+ third\n• fourth\n1. fifth\n2) sixth\n(a) seventh\niv) eighth\n1.2 ninth\nnot-a-bullet\n-1 stays";
+    assert_eq!(
+        apply(Segment::remove_bullets(), text),
+        "first\nsecond\nthird\nfourth\nfifth\nsixth\nseventh\neighth\nninth\nnot-a-bullet\n-1 stays"
+    );
+}
+
+#[test]
+fn it_collapses_whitespace() {
+    assert_eq!(apply(Segment::collapse_whitespace(), "  a \n\n\t b  c \r\n"), "a b c");
+}
+
+#[test]
+fn it_strips_markup() {
+    let html = "<html><body><!-- note --><h1 class=\"t\">MIT</h1><p>Permission &amp; rights &lt;year&gt;<br/></p></body></html>";
+    assert_eq!(apply(Segment::strip_markup(), html), "\nMIT\n\nPermission & rights <year>\n\n");
+
+    let markdown = "# MIT License\n\n> quoted **bold** and *emphasis* with `code` and [a link](https://example.com)\n\n---\n```\nfenced\n```";
+    assert_eq!(
+        apply(Segment::strip_markup(), markdown),
+        "MIT License\n\nquoted bold and emphasis with code and a link\n\n\n\nfenced\n"
+    );
+    assert_eq!(apply(Segment::strip_markup(), "Copyright <year> <owner>"), "Copyright <year> <owner>");
+}
+
+#[test]
+fn it_replaces_year_and_holder() {
+    let text = "Copyright (c) 2019-2023, ACME Inc.\nCopyright [yyyy] [name of copyright owner]\nCopyright (C) <year>  <name of author>\nReleased in 2007.";
+    assert_eq!(
+        apply(Segment::replace_year_and_holder(), text),
+        "Copyright (c) <year>, <copyright holders>\nCopyright <year> <copyright holders>\nCopyright (C) <year>  <copyright holders>\nReleased in 2007."
+    );
+}
+
+#[test]
+fn it_resolves_builtins_by_name() {
+    for builtin in BuiltinSegment::ALL {
+        assert_eq!(BuiltinSegment::from_name(builtin.name()), Some(*builtin));
+        let definition: SegmentDefinition =
+            serde_json::from_str(&format!(r#"{{"type": "builtin", "name": "{}"}}"#, builtin)).unwrap();
+        assert_eq!(definition, SegmentDefinition::Builtin { name: *builtin });
+    }
+    assert_eq!(BuiltinSegment::from_name("nope"), None);
+}
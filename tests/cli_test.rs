@@ -0,0 +1,336 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+#![cfg(feature = "cli")]
+
+use std::{fs, io::Write, path::PathBuf, process::{Command, Output, Stdio}};
+
+const MIT: &str = "MIT License
+
+Copyright (c) <year> <copyright holders>
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the \"Software\"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED \"AS IS\", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+";
+
+fn run(args: &[&str], stdin: Option<&str>) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_whichlicense_detection"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    if let Some(input) = stdin {
+        child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    }
+    child.wait_with_output().unwrap()
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+#[test]
+fn it_detects_a_file(){
+    let out = run(&["detect", "./LICENSE"], None);
+    assert_eq!(out.status.code(), Some(0));
+    assert!(String::from_utf8(out.stdout).unwrap().starts_with("apache-2.0.LICENSE\t100.00"));
+}
+
+#[test]
+fn it_detects_from_stdin_as_json(){
+    let license = fs::read_to_string("./LICENSE").unwrap();
+    let out = run(&["detect", "-", "--algorithm", "fuzzy", "--format", "json", "--limit", "1"], Some(&license));
+    assert_eq!(out.status.code(), Some(0));
+
+    let matches: Vec<serde_json::Value> = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0]["name"], "apache-2.0.LICENSE");
+}
+
+#[test]
+fn it_exits_with_1_when_nothing_is_detected(){
+    let out = run(&["detect", "-", "--algorithm", "fuzzy"], Some("This is not a license. lorem ipsum dolor sit amet, consectetur adipiscing elit."));
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+}
+
+#[test]
+fn it_exits_with_2_on_errors(){
+    let out = run(&["detect", "./this_file_does_not_exist"], None);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8(out.stderr).unwrap().contains("not found"));
+}
+
+#[test]
+fn it_validates_options_of_the_embedded_database(){
+    let out = run(&["detect", "./LICENSE", "--min-confidence", "200"], None);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8(out.stderr).unwrap().contains("min_confidence"));
+
+    let out = run(&["detect", "./LICENSE", "--threshold", "1.5"], None);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8(out.stderr).unwrap().contains("threshold"));
+}
+
+#[test]
+fn it_builds_lists_and_inspects_a_database(){
+    let folder = temp_path("cli_licenses");
+    fs::create_dir_all(&folder).unwrap();
+    fs::write(folder.join("apache-2.0.LICENSE"), fs::read_to_string("./LICENSE").unwrap()).unwrap();
+    fs::write(folder.join("test.LICENSE"), "---\nkey: test\n---\nThis is a test license, it is used to test the command line.").unwrap();
+    let db = temp_path("cli_fuzzy_db");
+
+    let out = run(&["build-db", folder.to_str().unwrap(), "--algorithm", "fuzzy", "--output", db.to_str().unwrap()], None);
+    assert_eq!(out.status.code(), Some(0));
+
+    let out = run(&["list", "--database", db.to_str().unwrap()], None);
+    assert_eq!(String::from_utf8(out.stdout).unwrap(), "apache-2.0.LICENSE\ntest.LICENSE\n");
+
+    let out = run(&["inspect", db.to_str().unwrap(), "--format", "json"], None);
+    let header: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(header["algorithm"], "Fuzzy");
+    assert_eq!(header["license_count"], 2);
+    assert_eq!(header["normalization"], "normalize_spdx");
+
+    let out = run(&["detect", "./LICENSE", "--database", db.to_str().unwrap()], None);
+    assert_eq!(out.status.code(), Some(0));
+    assert!(String::from_utf8(out.stdout).unwrap().starts_with("apache-2.0.LICENSE\t100.00"));
+}
+
+#[test]
+fn it_builds_a_block_hash_database(){
+    let folder = temp_path("cli_block_hash_licenses");
+    fs::create_dir_all(&folder).unwrap();
+    fs::write(folder.join("apache-2.0.LICENSE"), fs::read_to_string("./LICENSE").unwrap()).unwrap();
+    fs::write(folder.join("mit.LICENSE"), MIT).unwrap();
+    let db = temp_path("cli_block_hash_db");
+
+    let out = run(&["build-db", folder.to_str().unwrap(), "--algorithm", "block-hash", "--block-size", "16", "--hash-length", "12", "--output", db.to_str().unwrap()], None);
+    assert_eq!(out.status.code(), Some(0));
+
+    let out = run(&["inspect", db.to_str().unwrap()], None);
+    let inspected = String::from_utf8(out.stdout).unwrap();
+    assert!(inspected.contains("algorithm:      block-hash\n"));
+    assert!(inspected.contains("block size:     16\n"));
+    assert!(inspected.contains("hash length:    12\n"));
+
+    let out = run(&["detect", "./LICENSE", "--database", db.to_str().unwrap()], None);
+    assert_eq!(out.status.code(), Some(0));
+    assert!(String::from_utf8(out.stdout).unwrap().starts_with("apache-2.0.LICENSE\t100.00"));
+
+    // there is no embedded block hash database to fall back on.
+    let out = run(&["detect", "./LICENSE", "--algorithm", "block-hash"], None);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8(out.stderr).unwrap().contains("--database"));
+}
+
+#[test]
+fn it_builds_a_database_of_word_shingles(){
+    let folder = temp_path("cli_tokens_licenses");
+    fs::create_dir_all(&folder).unwrap();
+    fs::write(folder.join("apache-2.0.LICENSE"), fs::read_to_string("./LICENSE").unwrap()).unwrap();
+    fs::write(folder.join("mit.LICENSE"), MIT).unwrap();
+    let db = temp_path("cli_tokens_db");
+
+    let out = run(&["build-db", folder.to_str().unwrap(), "--normalization", "tokens", "--shingling", "words", "--shingle-size", "5", "--output", db.to_str().unwrap()], None);
+    assert_eq!(out.status.code(), Some(0));
+
+    let out = run(&["inspect", db.to_str().unwrap()], None);
+    let inspected = String::from_utf8(out.stdout).unwrap();
+    assert!(inspected.contains("shingling:      words\n"));
+    assert!(inspected.contains("normalization:  normalize_tokens\n"));
+
+    let out = run(&["detect", "-", "--database", db.to_str().unwrap(), "--limit", "1"], Some(&MIT.replace("MIT License", "")));
+    assert_eq!(out.status.code(), Some(0));
+    assert!(String::from_utf8(out.stdout).unwrap().starts_with("mit.LICENSE\t"));
+}
+
+#[test]
+fn it_scans_a_directory(){
+    let root = temp_path("cli_scan");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("docs")).unwrap();
+    fs::copy("./LICENSE", root.join("LICENSE")).unwrap();
+    fs::copy("./LICENSE", root.join("docs/COPYING")).unwrap();
+
+    let out = run(&["scan", root.to_str().unwrap(), "--exclude", "docs/"], None);
+    assert_eq!(out.status.code(), Some(0));
+    assert_eq!(String::from_utf8(out.stdout).unwrap(), "LICENSE\tapache-2.0.LICENSE\t100.00\n");
+
+    fs::remove_file(root.join("LICENSE")).unwrap();
+    let out = run(&["scan", root.to_str().unwrap(), "--exclude", "docs/"], None);
+    assert_eq!(out.status.code(), Some(1));
+}
+
+#[test]
+fn it_detects_license_spans(){
+    let input = format!("Notices\n\n{}\n-----\n\n{}", MIT, fs::read_to_string("./LICENSE").unwrap());
+    let out = run(&["detect", "-", "--spans", "--limit", "1", "--format", "json"], Some(&input));
+    assert_eq!(out.status.code(), Some(0));
+
+    let spans: Vec<serde_json::Value> = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(spans.len(), 2);
+    assert_eq!(spans[0]["matches"][0]["name"], "mit.LICENSE");
+    assert_eq!(spans[1]["matches"][0]["name"], "apache-2.0.LICENSE");
+    assert!(spans[0]["end_line"].as_u64() < spans[1]["start_line"].as_u64());
+}
+
+#[test]
+fn it_diffs_against_reference_texts(){
+    let folder = temp_path("cli_diff_licenses");
+    fs::create_dir_all(&folder).unwrap();
+    fs::write(folder.join("apache-2.0.LICENSE"), fs::read_to_string("./LICENSE").unwrap()).unwrap();
+    let db = temp_path("cli_diff_db");
+
+    let out = run(&["build-db", folder.to_str().unwrap(), "--reference-texts", "--output", db.to_str().unwrap()], None);
+    assert_eq!(out.status.code(), Some(0));
+
+    let input = fs::read_to_string("./LICENSE").unwrap().replace("Apache License", "Apache Licence");
+    let out = run(&["diff", "-", "--database", db.to_str().unwrap(), "--format", "html"], Some(&input));
+    assert_eq!(out.status.code(), Some(0));
+    let html = String::from_utf8(out.stdout).unwrap();
+    assert!(html.starts_with("<pre>"));
+    assert!(html.contains("<del>License\n"));
+    assert!(html.contains("<ins>Licence\n"));
+
+    // the embedded databases do not contain reference texts.
+    let out = run(&["diff", "./LICENSE"], None);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8(out.stderr).unwrap().contains("no reference text"));
+}
+
+#[test]
+fn it_outputs_spdx_expressions(){
+    let out = run(&["detect", "./LICENSE", "--spdx"], None);
+    assert_eq!(out.status.code(), Some(0));
+    assert_eq!(String::from_utf8(out.stdout).unwrap(), "Apache-2.0\n");
+
+    let input = format!("{}\n-----\n\n{}", MIT, fs::read_to_string("./LICENSE").unwrap());
+    let out = run(&["detect", "-", "--spans", "--spdx", "--format", "json"], Some(&input));
+    let res: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(res["expression"], "MIT AND Apache-2.0");
+}
+
+#[test]
+fn it_evaluates_a_policy(){
+    let policy = temp_path("policy.json");
+    fs::write(
+        &policy,
+        r#"{"default": "review", "rules": [{"verdict": "deny", "spdx": "Apache-2.0", "reason": "no Apache code"}, {"verdict": "allow", "spdx": "MIT"}]}"#,
+    )
+    .unwrap();
+    let policy = policy.to_str().unwrap();
+
+    let out = run(&["detect", "./LICENSE", "--policy", policy], None);
+    assert_eq!(out.status.code(), Some(3));
+    assert_eq!(String::from_utf8(out.stdout).unwrap(), "deny\tApache-2.0\n  deny: no Apache code\n");
+
+    let out = run(&["detect", "-", "--policy", policy, "--format", "json"], Some(MIT));
+    assert_eq!(out.status.code(), Some(0));
+    let decision: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(decision["verdict"], "allow");
+    assert_eq!(decision["expression"], "MIT");
+
+    fs::write(temp_path("invalid_policy.json"), r#"{"rules": [{"verdict": "maybe", "spdx": "MIT"}]}"#).unwrap();
+    let out = run(&["detect", "./LICENSE", "--policy", temp_path("invalid_policy.json").to_str().unwrap()], None);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn it_converts_and_runs_a_pipeline(){
+    let toml = temp_path("pipeline.toml");
+    fs::write(
+        &toml,
+        "[[segments]]\ntype = \"builtin\"\nname = \"strip_spdx_heading\"\n\n[[segments]]\ntype = \"remove\"\nregex = \"(?i)SPDX-License-Identifier:.*\"\n",
+    )
+    .unwrap();
+
+    let out = run(&["pipeline", toml.to_str().unwrap(), "--to", "yaml"], None);
+    assert_eq!(out.status.code(), Some(0));
+    let yaml = temp_path("pipeline.yaml");
+    fs::write(&yaml, &out.stdout).unwrap();
+
+    let out = run(&["pipeline", yaml.to_str().unwrap(), "--to", "toml"], None);
+    assert_eq!(out.status.code(), Some(0));
+    assert_eq!(out.stdout, fs::read(&toml).unwrap());
+
+    let out = run(&["detect", "-", "--pipeline", yaml.to_str().unwrap(), "--limit", "1"], Some(MIT));
+    assert_eq!(out.status.code(), Some(0));
+    assert!(String::from_utf8(out.stdout).unwrap().starts_with("mit.LICENSE\t"));
+}
+
+#[test]
+fn it_rejects_invalid_pipelines(){
+    let invalid_regex = temp_path("invalid_regex_pipeline.json");
+    fs::write(&invalid_regex, r#"{"segments": [{"type": "remove", "regex": "("}]}"#).unwrap();
+    let out = run(&["pipeline", invalid_regex.to_str().unwrap()], None);
+    assert_eq!(out.status.code(), Some(2));
+
+    let unknown_builtin = temp_path("unknown_builtin_pipeline.json");
+    fs::write(&unknown_builtin, r#"{"segments": [{"type": "builtin", "name": "nope"}]}"#).unwrap();
+    let out = run(&["detect", "./LICENSE", "--pipeline", unknown_builtin.to_str().unwrap()], None);
+    assert_eq!(out.status.code(), Some(2));
+
+    let unknown_format = temp_path("pipeline.txt");
+    fs::write(&unknown_format, "").unwrap();
+    let out = run(&["pipeline", unknown_format.to_str().unwrap()], None);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn it_explains_a_pipeline(){
+    let pipeline = temp_path("explained_pipeline.json");
+    fs::write(
+        &pipeline,
+        r#"{"segments": [{"type": "builtin", "name": "strip_comment_markers"}, {"type": "builtin", "name": "collapse_whitespace"}]}"#,
+    )
+    .unwrap();
+    let commented: String = MIT.lines().map(|line| format!("// {}\n", line)).collect();
+
+    let out = run(&["detect", "-", "--pipeline", pipeline.to_str().unwrap(), "--explain", "--desired-confidence", "101", "--format", "json"], Some(&commented));
+    assert_eq!(out.status.code(), Some(0));
+    let report: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(report["steps"].as_array().unwrap().len(), 3);
+    assert_eq!(report["steps"][1]["segment_name"], "strip_comment_markers");
+    assert_eq!(report["short_circuited"], false);
+    assert_eq!(report["best"]["name"], "mit.LICENSE");
+
+    let out = run(&["detect", "-", "--pipeline", pipeline.to_str().unwrap(), "--explain"], Some(&commented));
+    assert_eq!(out.status.code(), Some(0));
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.lines().any(|line| line.starts_with("best\t")));
+
+    let out = run(&["detect", "./LICENSE", "--explain"], None);
+    assert_eq!(out.status.code(), Some(2));
+}
@@ -0,0 +1,118 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+use std::fs;
+
+use whichlicense_detection::{
+    detecting::{
+        fuzzy_implementation::fuzzy_implementation::FuzzyDetection,
+        gaoya_implementation::gaoya_implementation::GaoyaDetection,
+    },
+    diff_texts, DetectionError, DiffChunk, LicenseListActions,
+};
+
+#[test]
+fn it_diffs_words(){
+    let diff = diff_texts("test", "Copyright (c) Jane Doe\nAll rights reserved.", "Copyright  (c) John Doe\nAll rights reserved. Do not sell.");
+    assert_eq!(
+        diff.chunks,
+        vec![
+            DiffChunk::Equal("Copyright  (c) ".to_string()),
+            DiffChunk::Removed("Jane ".to_string()),
+            DiffChunk::Added("John ".to_string()),
+            DiffChunk::Equal("Doe\nAll rights reserved. ".to_string()),
+            DiffChunk::Added("Do not sell.".to_string()),
+        ]
+    );
+    assert!(!diff.is_identical());
+    assert_eq!(diff.changed_words(), (4, 1));
+
+    let diff = diff_texts("test", "a  b\nc", "a b c");
+    assert!(diff.is_identical());
+    assert_eq!(diff.changed_words(), (0, 0));
+}
+
+#[test]
+fn it_renders_the_diff(){
+    let diff = diff_texts("test", "use <this> software", "use <that> software");
+    assert_eq!(diff.to_html(), "use <del>&lt;this&gt; </del><ins>&lt;that&gt; </ins>software");
+    assert_eq!(diff.to_terminal(), "use \x1b[31;9m<this> \x1b[0m\x1b[32m<that> \x1b[0msoftware");
+}
+
+#[test]
+fn it_diffs_a_match_against_its_reference_text(){
+    let license = fs::read_to_string("./LICENSE").unwrap();
+    let modified = license.replace(
+        "WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.",
+        "",
+    );
+
+    let mut fuzzy = FuzzyDetection::builder().store_reference_texts(true).build().unwrap();
+    fuzzy.add_plain("apache-2.0", &license);
+    let mut gaoya = GaoyaDetection::builder().store_reference_texts(true).build().unwrap();
+    gaoya.add_plain("apache-2.0", &license);
+
+    let algorithms: [&dyn Fn(&str) -> whichlicense_detection::LicenseDiff; 2] = [
+        &|input| fuzzy.diff_match(&fuzzy.match_by_plain_text(input)[0], input).unwrap(),
+        &|input| gaoya.diff_match(&gaoya.match_by_plain_text(input)[0], input).unwrap(),
+    ];
+    for diff_match in algorithms.iter() {
+        let diff = diff_match(&modified);
+        assert_eq!(diff.license, "apache-2.0");
+        let removed: Vec<&DiffChunk> = diff.chunks.iter().filter(|c| !matches!(c, DiffChunk::Equal(_))).collect();
+        assert_eq!(
+            removed,
+            vec![&DiffChunk::Removed("WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.\n   ".to_string())]
+        );
+        assert!(diff_match(&license).is_identical());
+    }
+}
+
+#[test]
+fn it_saves_and_loads_reference_texts(){
+    let license = fs::read_to_string("./LICENSE").unwrap();
+    let mut gaoya = GaoyaDetection::builder().store_reference_texts(true).build().unwrap();
+    gaoya.add_plain("apache-2.0", &license);
+    gaoya.add_plain("test_license", "This is a test license");
+    gaoya.remove("test_license");
+    let raw = gaoya.save_to_memory();
+
+    let mut loaded = GaoyaDetection::builder().build().unwrap();
+    loaded.try_load_from_memory(&raw).unwrap();
+    assert_eq!(loaded.get_reference_text("apache-2.0"), Some(license.as_str()));
+    assert_eq!(loaded.get_reference_text("test_license"), None);
+
+    let mut fuzzy = FuzzyDetection::builder().store_reference_texts(true).build().unwrap();
+    fuzzy.add_plain("apache-2.0", &license);
+    let mut loaded = FuzzyDetection::builder().build().unwrap();
+    loaded.try_load_from_memory(&fuzzy.save_to_memory()).unwrap();
+    assert_eq!(loaded.get_reference_text("apache-2.0"), Some(license.as_str()));
+}
+
+#[test]
+fn it_fails_without_reference_text(){
+    let license = fs::read_to_string("./LICENSE").unwrap();
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    fuzzy.add_plain("apache-2.0", &license);
+
+    let top = fuzzy.match_by_plain_text(&license).remove(0);
+    assert!(fuzzy.get_reference_text("apache-2.0").is_none());
+    assert!(matches!(
+        fuzzy.diff_match(&top, &license),
+        Err(DetectionError::MissingReferenceText(name)) if name == "apache-2.0"
+    ));
+}
@@ -0,0 +1,49 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+#![cfg(feature = "embedded-database")]
+
+use std::fs;
+use whichlicense_detection::{*, detecting::{fuzzy_implementation::fuzzy_implementation::FuzzyDetection, gaoya_implementation::gaoya_implementation::GaoyaDetection}};
+
+#[test]
+fn fuzzy_detects_with_default_database(){
+    let fuzzy = FuzzyDetection::with_default_database();
+    assert!(!fuzzy.licenses.is_empty());
+
+    let license = fs::read_to_string("./LICENSE").unwrap();
+    let matches = fuzzy.match_by_plain_text(&license);
+    assert_eq!(matches[0].name, "apache-2.0.LICENSE");
+    assert_eq!(matches[0].confidence, 100.0);
+}
+
+#[test]
+fn gaoya_detects_with_default_database(){
+    let gaoya = GaoyaDetection::with_default_database();
+    assert!(gaoya.index.size() > 0);
+
+    let license = fs::read_to_string("./LICENSE").unwrap();
+    let matches = gaoya.match_by_plain_text(&license);
+    assert_eq!(matches[0].name, "apache-2.0.LICENSE");
+    assert_eq!(matches[0].confidence, 100.0);
+}
+
+#[test]
+fn embedded_databases_match_the_shipped_files(){
+    assert_eq!(EMBEDDED_FUZZY_DATABASE, &fs::read("./licenses/fuzzy_db").unwrap()[..]);
+    assert_eq!(EMBEDDED_GAOYA_DATABASE, &fs::read("./licenses/gaoya_db").unwrap()[..]);
+}
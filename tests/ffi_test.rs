@@ -0,0 +1,146 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+#![cfg(feature = "ffi")]
+
+use std::{
+    ffi::{CStr, CString},
+    fs, ptr, slice,
+};
+
+use whichlicense_detection::ffi::ffi::*;
+
+fn last_error() -> String {
+    unsafe { CStr::from_ptr(wld_last_error_message()).to_str().unwrap().to_string() }
+}
+
+fn all_matches(detector: *const WldDetector, text: &str) -> Vec<(String, f32)> {
+    let text = CString::new(text).unwrap();
+    let mut matches = WldMatches { matches: ptr::null_mut(), len: 0 };
+    unsafe {
+        assert_eq!(wld_detector_match(detector, text.as_ptr(), &mut matches), WldStatus::Ok);
+        let all = slice::from_raw_parts(matches.matches, matches.len)
+            .iter()
+            .map(|m| (CStr::from_ptr(m.name).to_str().unwrap().to_string(), m.confidence))
+            .collect();
+        wld_matches_free(&mut matches);
+        assert!(matches.matches.is_null());
+        all
+    }
+}
+
+fn top_match(detector: *const WldDetector, text: &str) -> Option<(String, f32)> {
+    all_matches(detector, text).into_iter().next()
+}
+
+#[test]
+fn it_adds_matches_saves_and_loads(){
+    let license = CString::new(fs::read_to_string("./LICENSE").unwrap()).unwrap();
+    let name = CString::new("apache-2.0").unwrap();
+
+    for algorithm in [WldAlgorithm::Fuzzy, WldAlgorithm::Gaoya, WldAlgorithm::BlockHash] {
+        unsafe {
+            let mut detector: *mut WldDetector = ptr::null_mut();
+            assert_eq!(wld_detector_new(algorithm as u32, &mut detector), WldStatus::Ok);
+            assert_eq!(wld_detector_add_license(detector, name.as_ptr(), license.as_ptr()), WldStatus::Ok);
+            // adding a license with the same name replaces it.
+            assert_eq!(wld_detector_add_license(detector, name.as_ptr(), license.as_ptr()), WldStatus::Ok);
+            assert_eq!(all_matches(detector, license.to_str().unwrap()), vec![("apache-2.0".to_string(), 100.0)]);
+
+            let mut data: *mut u8 = ptr::null_mut();
+            let mut len = 0;
+            assert_eq!(wld_detector_save(detector, &mut data, &mut len), WldStatus::Ok);
+            let mut loaded: *mut WldDetector = ptr::null_mut();
+            assert_eq!(wld_detector_load(data, len, &mut loaded), WldStatus::Ok);
+            wld_bytes_free(data, len);
+            assert_eq!(top_match(loaded, license.to_str().unwrap()).unwrap().0, "apache-2.0");
+
+            assert_eq!(wld_detector_set_min_confidence(loaded, 100), WldStatus::Ok);
+            assert_eq!(top_match(loaded, "This is not the license you are looking for."), None);
+            assert_eq!(wld_detector_remove_license(loaded, name.as_ptr()), WldStatus::Ok);
+            assert_eq!(top_match(loaded, license.to_str().unwrap()), None);
+
+            wld_detector_free(detector);
+            wld_detector_free(loaded);
+        }
+    }
+}
+
+#[test]
+fn it_reports_errors(){
+    unsafe {
+        let mut detector: *mut WldDetector = ptr::null_mut();
+        let garbage = b"not a database";
+        assert_eq!(wld_detector_load(garbage.as_ptr(), garbage.len(), &mut detector), WldStatus::IncompatibleFormat);
+        assert!(detector.is_null());
+        assert!(!last_error().is_empty());
+        let truncated = b"WLDB\x03";
+        assert_eq!(wld_detector_load(truncated.as_ptr(), truncated.len(), &mut detector), WldStatus::CorruptDatabase);
+
+        assert_eq!(wld_detector_new(WldAlgorithm::Fuzzy as u32, ptr::null_mut()), WldStatus::NullPointer);
+        assert_eq!(last_error(), "out is NULL");
+        assert_eq!(wld_detector_new(42, &mut detector), WldStatus::InvalidParameter);
+        assert_eq!(last_error(), "unknown algorithm 42");
+        assert!(detector.is_null());
+
+        assert_eq!(wld_detector_new(WldAlgorithm::Fuzzy as u32, &mut detector), WldStatus::Ok);
+        let invalid = [0xffu8, 0xfe, 0];
+        let name = CString::new("test").unwrap();
+        assert_eq!(
+            wld_detector_add_license(detector, name.as_ptr(), invalid.as_ptr() as *const _),
+            WldStatus::InvalidUtf8
+        );
+        assert_eq!(wld_detector_add_license(detector, name.as_ptr(), ptr::null()), WldStatus::NullPointer);
+        assert_eq!(wld_detector_match(ptr::null(), name.as_ptr(), &mut WldMatches { matches: ptr::null_mut(), len: 0 }), WldStatus::NullPointer);
+        assert_eq!(wld_detector_set_min_confidence(detector, 101), WldStatus::InvalidParameter);
+        assert_eq!(last_error(), "invalid parameter: min_confidence must be between 0 and 100, got 101");
+        wld_detector_free(detector);
+        wld_detector_free(ptr::null_mut());
+
+        assert_eq!(CStr::from_ptr(wld_status_description(WldStatus::Io as u32)).to_str().unwrap(), "I/O error");
+        assert_eq!(CStr::from_ptr(wld_status_description(1000)).to_str().unwrap(), "unknown status");
+    }
+}
+
+#[test]
+fn it_strips_licenses(){
+    let text = CString::new("Copyright (c) 2023\n\nPermission  is   granted").unwrap();
+    unsafe {
+        let mut out = ptr::null_mut();
+        assert_eq!(wld_strip_license(text.as_ptr(), &mut out), WldStatus::Ok);
+        assert_eq!(
+            CStr::from_ptr(out).to_str().unwrap(),
+            whichlicense_detection::strip_license(text.to_str().unwrap())
+        );
+        wld_string_free(out);
+    }
+}
+
+#[cfg(feature = "embedded-database")]
+#[test]
+fn it_uses_the_embedded_database(){
+    unsafe {
+        let mut detector: *mut WldDetector = ptr::null_mut();
+        assert_eq!(wld_detector_with_default_database(WldAlgorithm::Gaoya as u32, &mut detector), WldStatus::Ok);
+        assert_eq!(top_match(detector, &fs::read_to_string("./LICENSE").unwrap()).unwrap().0, "apache-2.0.LICENSE");
+        wld_detector_free(detector);
+
+        detector = ptr::null_mut();
+        assert_eq!(wld_detector_with_default_database(WldAlgorithm::BlockHash as u32, &mut detector), WldStatus::InvalidParameter);
+        assert!(detector.is_null());
+    }
+}
@@ -15,18 +15,15 @@
 *   limitations under the License.
 */
 
+// the original assertions compare lengths to zero.
+#![allow(clippy::len_zero)]
+
 use std::{fs::File, io::{Read, BufReader}, path::Path};
 use whichlicense_detection::{*, detecting::fuzzy_implementation::fuzzy_implementation::{FuzzyDetection}};
 
 #[test]
 fn it_finds_exact_match() {
-    let mut fuzzy = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
 
     for l in load_licenses_from_folder("./licenses/RAW"){
         fuzzy.add_plain(&l.name, &l.text);
@@ -232,12 +229,7 @@ fn it_detects_with_over_90_confidence_with_similar_license() {
     See the License for the specific language governing permissions and
     limitations under the License.";
 
-    let mut fuzzy = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
 
     for l in load_licenses_from_folder("./licenses/RAW"){
         fuzzy.add_plain(&l.name, &strip_spdx_heading(&l.text));
@@ -253,12 +245,7 @@ fn it_detects_with_over_90_confidence_with_similar_license() {
 #[test]
 fn it_fails_on_unknown(){
     let unknown_license = "This is not a license. lorem ipsum dolor sit amet, consectetur adipiscing elit.";
-    let mut fuzzy = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
 
     for l in load_licenses_from_folder("./licenses/RAW"){
         fuzzy.add_plain(&l.name, &strip_spdx_heading(&l.text));
@@ -270,12 +257,7 @@ fn it_fails_on_unknown(){
 
 #[test]
 fn it_filters_on_min_confidence(){
-    let mut fuzzy = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
 
     for l in load_licenses_from_folder("./licenses/RAW"){
         fuzzy.add_plain(&l.name, &strip_spdx_heading(&l.text));
@@ -301,12 +283,7 @@ fn it_filters_on_min_confidence(){
 
 #[test]
 fn add_plain_works(){
-    let mut fuzzy = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
     fuzzy.add_plain("test_license", "This is a test license");
 
     assert_eq!(fuzzy.licenses[0].name, String::from("test_license"));
@@ -316,12 +293,7 @@ fn add_plain_works(){
 
 #[test]
 fn it_saves_to_file(){
-    let mut fuzzy = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
     fuzzy.add_plain("test_license", "This is a test license");
 
     fuzzy.save_to_file("./test_db");
@@ -332,24 +304,14 @@ fn it_saves_to_file(){
 
 #[test]
 fn it_saves_to_memory(){
-    let mut fuzzy = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
     fuzzy.add_plain("test_license", "This is a test license");
 
     let buffer = fuzzy.save_to_memory();
 
     assert!(buffer.len() > 0);
 
-    let mut f2 = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut f2 = FuzzyDetection::builder().build().unwrap();
     f2.load_from_memory(&buffer);
 
     assert!(f2.licenses.len() == 1);
@@ -360,23 +322,13 @@ fn it_saves_to_memory(){
 
 #[test]
 fn it_loads_from_saved_file(){
-    let mut old = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut old = FuzzyDetection::builder().build().unwrap();
     old.add_plain("test_license", "This is a test license");
     old.save_to_file("./test_db_2");
 
 
 
-    let mut fuzzy = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };    
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();    
     fuzzy.load_from_file("./test_db_2");
 
     assert!(fuzzy.licenses.len() == 1);
@@ -386,19 +338,18 @@ fn it_loads_from_saved_file(){
 
 #[test]
 fn it_loads_from_memory(){
-    let mut fuzzy = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
 
-    let raw = bincode::serialize(
+    let raw = encode_database(
+        &fuzzy.get_database_header(1),
         &DiskData {
             licenses: vec![
                 LicenseEntry {
                     name: String::from("test_license"),
-                    hash: String::from("This is a test license")
+                    hash: String::from("This is a test license"),
+                    text: None,
+                    metadata: None,
+                    digest: None,
                 }
             ]
         }
@@ -412,12 +363,7 @@ fn it_loads_from_memory(){
 
 #[test]
 fn remove_works(){
-    let mut fuzzy = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
     fuzzy.add_plain("test_license", "This is a test license");
 
     fuzzy.remove("test_license");
@@ -427,16 +373,14 @@ fn remove_works(){
 
 #[test]
 fn it_hashes_from_inline_string(){
-    let mut fuzzy = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
     let res = fuzzy.hash_from_inline_string("This is a test license");
     fuzzy.licenses.push(LicenseEntry {
         name: String::from("test_license"),
         hash: res.clone(),
+        text: None,
+        metadata: None,
+        digest: None,
     });
 
     
@@ -448,12 +392,7 @@ fn it_hashes_from_inline_string(){
 
 #[test]
 fn it_changes_normalization_fn(){
-    let mut fuzzy = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: |x| x.to_string(),
-    };
+    let mut fuzzy = FuzzyDetection::builder().normalization_fn(|x| x.to_string()).build().unwrap();
 
     fuzzy.add_plain("test_license", "this is a test license");
     assert!(
@@ -470,12 +409,7 @@ fn it_changes_normalization_fn(){
 
 #[test]
 fn it_gets_license_list(){
-    let mut fuzzy = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: |x| x.to_string(),
-    };
+    let mut fuzzy = FuzzyDetection::builder().normalization_fn(|x| x.to_string()).build().unwrap();
 
     fuzzy.add_plain("test_license", "this is a test license");
     fuzzy.add_plain("test_license_2", "this is a test license 2");
@@ -485,4 +419,151 @@ fn it_gets_license_list(){
     assert!(list.len() == 2);
     assert!(list.iter().any(|x| x.0 == "test_license" && !x.1.is_empty()));
     assert!(list.iter().any(|x| x.0 == "test_license_2" && !x.1.is_empty()));
-}
\ No newline at end of file
+}
+#[test]
+fn it_errors_on_missing_file(){
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+
+    let res = fuzzy.try_load_from_file("./this_file_does_not_exist");
+    assert!(matches!(res, Err(DetectionError::FileNotFound(_))));
+}
+
+#[test]
+fn it_errors_on_corrupt_database(){
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    fuzzy.add_plain("test_license", "This is a test license");
+    let buffer = fuzzy.save_to_memory();
+
+    let mut f2 = FuzzyDetection::builder().build().unwrap();
+    let res = f2.try_load_from_memory(&buffer[..buffer.len() / 2]);
+    assert!(matches!(res, Err(DetectionError::CorruptDatabase(_))));
+    assert!(f2.licenses.is_empty());
+
+    // the infallible variant leaves the list untouched.
+    f2.load_from_memory(&buffer[..buffer.len() / 2]);
+    assert!(f2.licenses.is_empty());
+}
+
+#[test]
+fn it_rejects_database_without_header(){
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+
+    let raw = bincode::serialize(
+        &DiskData {
+            licenses: vec![
+                LicenseEntry {
+                    name: String::from("test_license"),
+                    hash: String::from("This is a test license"),
+                    text: None,
+                    metadata: None,
+                    digest: None,
+                }
+            ]
+        }
+    ).unwrap();
+
+    let res = fuzzy.try_load_from_memory(&raw);
+    assert!(matches!(res, Err(DetectionError::IncompatibleFormat(_))));
+}
+
+#[test]
+fn it_rejects_database_of_other_algorithm(){
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+
+    let raw = std::fs::read("./licenses/gaoya_db").unwrap();
+    let res = fuzzy.try_load_from_memory(&raw);
+    assert!(matches!(res, Err(DetectionError::IncompatibleFormat(_))));
+    assert!(fuzzy.licenses.is_empty());
+}
+
+#[test]
+fn it_rejects_database_with_other_normalization(){
+    let mut old = FuzzyDetection::builder().normalization_fn(|x| x.to_lowercase()).build().unwrap();
+    old.add_plain("test_license", "This is a test license");
+    let buffer = old.save_to_memory();
+
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    let res = fuzzy.try_load_from_memory(&buffer);
+    assert!(matches!(res, Err(DetectionError::IncompatibleFormat(_))));
+}
+
+#[test]
+fn it_writes_a_database_header(){
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    fuzzy.add_plain("test_license", "This is a test license");
+    fuzzy.add_plain("test_license_2", "This is a test license 2");
+
+    let header = read_database_header(&fuzzy.save_to_memory()).unwrap();
+    assert_eq!(header.format_version, DATABASE_FORMAT_VERSION);
+    assert_eq!(header.algorithm, AlgorithmKind::Fuzzy);
+    assert_eq!(header.parameters, AlgorithmParameters::Fuzzy);
+    assert_eq!(header.normalization, "strip_license");
+    assert_eq!(header.license_count, 2);
+}
+
+#[test]
+fn it_sorts_equal_confidences_by_name(){
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    fuzzy.add_plain("license_b", "This is a test license");
+    fuzzy.add_plain("license_a", "This is a test license");
+
+    let matches = fuzzy.match_by_plain_text("This is a test license");
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0].name, "license_a");
+    assert_eq!(matches[1].name, "license_b");
+}
+
+#[test]
+fn it_rejects_invalid_parameters(){
+    assert!(matches!(FuzzyDetection::builder().min_confidence(101).build(), Err(DetectionError::InvalidParameter(_))));
+    assert!(FuzzyDetection::builder().min_confidence(100).build().is_ok());
+}
+
+#[test]
+fn it_matches_exact_texts_by_digest(){
+    let license = std::fs::read_to_string("./LICENSE").unwrap();
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    fuzzy.add_plain("apache-2.0.LICENSE", &license);
+    fuzzy.add_plain("apache-2.0-modified.LICENSE", &license.replace("Apache License", "Apache Licence"));
+
+    // only the exact match is returned, the similar license is not compared.
+    let matches = fuzzy.match_by_plain_text(&license);
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].name, "apache-2.0.LICENSE");
+    assert_eq!(matches[0].confidence, 100.0);
+
+    // formatting removed by the normalization function does not prevent an exact match.
+    assert_eq!(fuzzy.match_by_plain_text(&license.replace('\n', "\n\n")).len(), 1);
+
+    let mut loaded = FuzzyDetection::builder().build().unwrap();
+    loaded.try_load_from_memory(&fuzzy.try_save_to_memory().unwrap()).unwrap();
+    assert_eq!(loaded.digests.len(), 2);
+    assert_eq!(loaded.match_by_plain_text(&license).len(), 1);
+
+    loaded.remove("apache-2.0.LICENSE");
+    assert!(loaded.match_exact(&strip_license(&license)).is_empty());
+}
+
+#[test]
+fn it_indexes_licenses_sharing_a_text(){
+    let mut index = DigestIndex::default();
+    let digest = normalized_digest("some license");
+    index.insert("b", digest);
+    index.insert("a", digest);
+    assert_eq!(index.lookup(&digest), ["b", "a"]);
+
+    // re-inserting a license replaces its previous digest.
+    index.insert("b", normalized_digest("another license"));
+    assert_eq!(index.lookup(&digest), ["a"]);
+    assert_eq!(index.get("b"), Some(normalized_digest("another license")));
+
+    index.remove("a");
+    assert!(index.lookup(&digest).is_empty());
+    assert_eq!(index.len(), 1);
+
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    fuzzy.add_plain("b.LICENSE", "This is a test license");
+    fuzzy.add_plain("a.LICENSE", "This is a test license");
+    let matches = fuzzy.match_by_plain_text("This is a test license");
+    assert_eq!(matches.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), ["a.LICENSE", "b.LICENSE"]);
+}
@@ -15,18 +15,16 @@
 *   limitations under the License.
 */
 
+// the original assertions compare lengths to zero.
+#![allow(clippy::len_zero)]
+
 use std::{fs::File, io::{Read, BufReader}, path::Path, vec};
-use gaoya::{minhash::{MinHashIndex, MinHasher32, MinHasher}, text::shingle_text};
+use gaoya::{minhash::MinHasher, text::shingle_text};
 use whichlicense_detection::{*, detecting::gaoya_implementation::gaoya_implementation::GaoyaDetection};
 
 #[test]
 fn it_finds_exact_match() {
-    let mut gaoya = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
 
     for l in load_licenses_from_folder("./licenses/RAW"){
         gaoya.add_plain(&l.name, &strip_spdx_heading(&l.text));
@@ -236,12 +234,7 @@ fn it_detects_with_over_90_confidence_with_similar_license() {
     See the License for the specific language governing permissions and
     limitations under the License.";
 
-    let mut gaoya = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
 
     for l in load_licenses_from_folder("./licenses/RAW"){
         gaoya.add_plain(&l.name, &strip_spdx_heading(&l.text));
@@ -257,12 +250,7 @@ fn it_detects_with_over_90_confidence_with_similar_license() {
 #[test]
 fn it_fails_on_unknown(){
     let unknown_license = "This is not a license. lorem ipsum dolor sit amet, consectetur adipiscing elit.";
-    let mut gaoya = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
 
     for l in load_licenses_from_folder("./licenses/RAW"){
         gaoya.add_plain(&l.name, &strip_spdx_heading(&l.text));
@@ -274,12 +262,7 @@ fn it_fails_on_unknown(){
 
 #[test]
 fn it_filters_on_min_confidence(){
-    let mut gaoya = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
 
     for l in load_licenses_from_folder("./licenses/RAW"){
         gaoya.add_plain(&l.name, &strip_spdx_heading(&l.text));
@@ -305,12 +288,7 @@ fn it_filters_on_min_confidence(){
 
 #[test]
 fn add_plain_works(){
-    let mut gaoya = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
     gaoya.add_plain("test_license", "This is a test license");
 
     assert!(gaoya.index.get_id_signature_map().contains_key("test_license"));
@@ -320,12 +298,7 @@ fn add_plain_works(){
 
 #[test]
 fn it_saves_to_file(){
-    let mut gaoya = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
     gaoya.add_plain("test_license", "This is a test license");
 
     gaoya.save_to_file("./test_db");
@@ -336,12 +309,7 @@ fn it_saves_to_file(){
 
 #[test]
 fn it_saves_to_memory(){
-    let mut gaoya = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
     gaoya.add_plain("test_license", "This is a test license");
 
     let buffer = gaoya.save_to_memory();
@@ -349,12 +317,7 @@ fn it_saves_to_memory(){
     assert!(buffer.len() > 0);
 
     // it loads back?
-    let mut g2 = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut g2 = GaoyaDetection::builder().build().unwrap();
 
     g2.load_from_memory(&buffer);
 
@@ -365,22 +328,12 @@ fn it_saves_to_memory(){
 
 #[test]
 fn it_loads_from_saved_file(){
-    let mut old = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut old = GaoyaDetection::builder().build().unwrap();
     old.add_plain("test_license", "This is a test license");
     old.save_to_file("./test_db");
 
 
-    let mut gaoya = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
     gaoya.load_from_file("./test_db");
 
     assert!(gaoya.index.get_id_signature_map().contains_key("test_license"));
@@ -390,12 +343,7 @@ fn it_loads_from_saved_file(){
 
 #[test]
 fn it_hashes_from_inline_string(){
-    let mut gaoya = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
     let res = gaoya.hash_from_inline_string("This is a test license");
     gaoya.index.insert(String::from("test_license"), res.clone());
 
@@ -408,24 +356,23 @@ fn it_hashes_from_inline_string(){
 
 #[test]
 fn it_loads_from_inline_string(){
-    let mut gaoya = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
 
     let signature = gaoya.min_hasher.create_signature(shingle_text(
-        &strip_license(&strip_spdx_heading(&"This is a test license")),
+        &strip_license(&strip_spdx_heading("This is a test license")),
         gaoya.shingle_text_size,
     ));
 
-    let raw = bincode::serialize(
+    let raw = encode_database(
+        &gaoya.get_database_header(1),
         &DiskData {
             licenses: vec![
                 LicenseEntry {
                     name: String::from("test_license"),
-                    hash: signature
+                    hash: signature,
+                    text: None,
+                    metadata: None,
+                    digest: None,
                 }
             ]
         }
@@ -440,12 +387,7 @@ fn it_loads_from_inline_string(){
 
 #[test]
 fn remove_works(){
-    let mut gaoya = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: DEFAULT_NORMALIZATION_FN,
-    };
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
     gaoya.add_plain("test_license", "This is a test license");
 
     gaoya.remove("test_license");
@@ -456,12 +398,7 @@ fn remove_works(){
 #[test]
 fn it_changes_normalization_fn(){
     // gaoya is less sensitive to casing and thus the tests need to be more drastic than say, fuzzy matching.
-    let mut gaoya = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: |x| x.to_string(),
-    };
+    let mut gaoya = GaoyaDetection::builder().normalization_fn(|x| x.to_string()).build().unwrap();
     gaoya.add_plain("test_license", "this is a test license");
     assert!(
         // should fail to match, normalization fn leaves text as is (with 'X').
@@ -477,12 +414,7 @@ fn it_changes_normalization_fn(){
 
 #[test]
 fn it_gets_license_list(){
-    let mut gaoya = GaoyaDetection {
-        index: MinHashIndex::new(42, 3, 0.5),
-        min_hasher: MinHasher32::new(42 * 3),
-        shingle_text_size: 50,
-        normalization_fn: |x| x.to_string(),
-    };
+    let mut gaoya = GaoyaDetection::builder().normalization_fn(|x| x.to_string()).build().unwrap();
 
     gaoya.add_plain("test_license", "This is a test license");
     gaoya.add_plain("test_license_2", "This is a test license 2");
@@ -492,4 +424,164 @@ fn it_gets_license_list(){
     assert!(list.len() == 2);
     assert!(list.iter().any(|x| x.0 == "test_license" && !x.1.is_empty()));
     assert!(list.iter().any(|x| x.0 == "test_license_2" && !x.1.is_empty()));
-}
\ No newline at end of file
+}
+#[test]
+fn it_errors_on_missing_file(){
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
+
+    let res = gaoya.try_load_from_file("./this_file_does_not_exist");
+    assert!(matches!(res, Err(DetectionError::FileNotFound(_))));
+}
+
+#[test]
+fn it_errors_on_corrupt_database(){
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
+    gaoya.add_plain("test_license", "This is a test license");
+    let buffer = gaoya.save_to_memory();
+
+    let mut g2 = GaoyaDetection::builder().build().unwrap();
+    let res = g2.try_load_from_memory(&buffer[..buffer.len() / 2]);
+    assert!(matches!(res, Err(DetectionError::CorruptDatabase(_))));
+    assert!(g2.index.size() == 0);
+}
+
+#[test]
+fn it_rejects_database_with_other_parameters(){
+    let mut old = GaoyaDetection::builder().build().unwrap();
+    old.add_plain("test_license", "This is a test license");
+    let buffer = old.save_to_memory();
+
+    let mut gaoya = GaoyaDetection::builder().shingle_size(20).build().unwrap();
+    let res = gaoya.try_load_from_memory(&buffer);
+    assert!(matches!(res, Err(DetectionError::IncompatibleFormat(_))));
+    assert!(gaoya.index.size() == 0);
+}
+
+#[test]
+fn it_loads_the_bundled_database(){
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
+    gaoya.try_load_from_file("./licenses/gaoya_db").unwrap();
+
+    let header = read_database_header(&std::fs::read("./licenses/gaoya_db").unwrap()).unwrap();
+    assert_eq!(header.algorithm, AlgorithmKind::Gaoya);
+    assert_eq!(header.license_count as usize, gaoya.index.size());
+}
+
+#[test]
+fn it_sorts_matches_by_confidence(){
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
+    gaoya.load_from_file("./licenses/gaoya_db");
+
+    let license = std::fs::read_to_string("./LICENSE").unwrap();
+    let matches = gaoya.match_by_plain_text(&license);
+
+    assert!(matches.len() > 1);
+    assert_eq!(matches[0].name, "apache-2.0.LICENSE");
+    assert!(matches.windows(2).all(|w| w[0].confidence >= w[1].confidence));
+}
+
+#[test]
+fn it_applies_min_confidence(){
+    let mut gaoya = GaoyaDetection::builder().min_confidence(80).build().unwrap();
+    gaoya.load_from_file("./licenses/gaoya_db");
+
+    let license = std::fs::read_to_string("./LICENSE").unwrap();
+    let matches = gaoya.match_by_plain_text(&license);
+
+    assert!(!matches.is_empty());
+    for m in matches.iter(){
+        assert!(m.confidence >= 80.0, "Confidence was lower than the supplied minimum confidence!");
+    }
+}
+
+#[test]
+fn it_builds_with_parameters(){
+    let gaoya = GaoyaDetection::builder()
+        .bands(21)
+        .band_width(6)
+        .threshold(0.8)
+        .shingle_size(30)
+        .min_confidence(70)
+        .build()
+        .unwrap();
+
+    assert_eq!(gaoya.num_bands, 21);
+    assert_eq!(gaoya.band_width, 6);
+    assert_eq!(gaoya.shingle_text_size, 30);
+    assert_eq!(gaoya.min_confidence, 70);
+    assert_eq!(gaoya.min_hasher.num_hashes(), 21 * 6);
+    assert_eq!(gaoya.index.num_hashes(), 21 * 6);
+}
+
+#[test]
+fn it_rejects_invalid_parameters(){
+    assert!(matches!(GaoyaDetection::builder().bands(0).build(), Err(DetectionError::InvalidParameter(_))));
+    assert!(matches!(GaoyaDetection::builder().band_width(0).build(), Err(DetectionError::InvalidParameter(_))));
+    assert!(matches!(GaoyaDetection::builder().threshold(0.0).build(), Err(DetectionError::InvalidParameter(_))));
+    assert!(matches!(GaoyaDetection::builder().threshold(1.5).build(), Err(DetectionError::InvalidParameter(_))));
+    assert!(matches!(GaoyaDetection::builder().shingle_size(0).build(), Err(DetectionError::InvalidParameter(_))));
+    assert!(matches!(GaoyaDetection::builder().min_confidence(101).build(), Err(DetectionError::InvalidParameter(_))));
+}
+
+#[test]
+fn it_matches_exact_texts_by_digest(){
+    let license = std::fs::read_to_string("./LICENSE").unwrap();
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
+    gaoya.add_plain("apache-2.0.LICENSE", &license);
+    gaoya.add_plain("apache-2.0-modified.LICENSE", &license.replace("Apache License", "Apache Licence"));
+    gaoya.set_metadata("apache-2.0.LICENSE", LicenseMetadata { spdx_license_key: Some("Apache-2.0".to_string()), ..Default::default() });
+
+    let matches = gaoya.match_by_plain_text(&license);
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].name, "apache-2.0.LICENSE");
+    assert_eq!(matches[0].confidence, 100.0);
+    assert_eq!(matches[0].metadata.as_ref().unwrap().spdx_license_key.as_deref(), Some("Apache-2.0"));
+
+    let mut loaded = GaoyaDetection::builder().build().unwrap();
+    loaded.try_load_from_memory(&gaoya.try_save_to_memory().unwrap()).unwrap();
+    assert_eq!(loaded.match_by_plain_text(&license).len(), 1);
+
+    // the embedded database is built from the license texts and thus has their digests.
+    #[cfg(feature = "embedded-database")]
+    {
+        let embedded = GaoyaDetection::with_default_database();
+        assert!(!embedded.digests.is_empty());
+    }
+}
+
+#[test]
+fn it_shingles_words(){
+    let first = "本软件按“原样”提供，不提供任何明示或暗示的担保，包括但不限于对适销性、特定用途适用性和非侵权性的担保。";
+    let second = "特此免费授予任何获得本软件副本和相关文档文件的人不受限制地处理本软件的权利。";
+
+    // every Chinese text normalizes to the same, empty, string with strip_license.
+    let mut chars = GaoyaDetection::builder().build().unwrap();
+    chars.add_plain("first", first);
+    chars.add_plain("second", second);
+    assert_eq!(chars.match_by_plain_text(first).len(), 2);
+
+    let mut words = GaoyaDetection::builder()
+        .normalizer(BuiltinSegment::NormalizeTokens)
+        .shingling(Shingling::Words)
+        .shingle_size(3)
+        .build()
+        .unwrap();
+    words.add_plain("first", first);
+    words.add_plain("second", second);
+    let matches = words.match_by_plain_text(&first.replace("明示", "明確"));
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].name, "first");
+    assert!(matches[0].confidence > 75.0 && matches[0].confidence < 100.0);
+
+    // texts shorter than a shingle still get a signature.
+    words.add_plain("short", "MIT");
+    assert_eq!(words.match_by_plain_text("mit")[0].name, "short");
+
+    let buffer = words.try_save_to_memory().unwrap();
+    let header = read_database_header(&buffer).unwrap();
+    assert_eq!(header.normalization, "normalize_tokens");
+    assert_eq!(header.parameters, AlgorithmParameters::Gaoya { num_bands: 42, band_width: 3, shingle_text_size: 3, shingling: Shingling::Words });
+
+    let mut characters = GaoyaDetection::builder().normalizer(BuiltinSegment::NormalizeTokens).shingle_size(3).build().unwrap();
+    assert!(matches!(characters.try_load_from_memory(&buffer), Err(DetectionError::IncompatibleFormat(_))));
+}
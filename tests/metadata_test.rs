@@ -0,0 +1,184 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+use std::{fs, path::PathBuf};
+
+use whichlicense_detection::{
+    detecting::{
+        fuzzy_implementation::fuzzy_implementation::FuzzyDetection,
+        gaoya_implementation::gaoya_implementation::GaoyaDetection,
+    },
+    load_licenses_from_folder, parse_license_heading, split_license_heading, LicenseCategory, LicenseListActions,
+    LicenseMetadata, RawLicense, SpdxMapping,
+};
+
+const GPL_HEADING: &str = "---
+key: gpl-2.0-plus
+short_name: GPL 2.0 or later
+name: GNU General Public License 2.0 or later
+category: Copyleft
+owner: Free Software Foundation (FSF)
+homepage_url: http://www.gnu.org/licenses/old-licenses/gpl-2.0-standalone.html
+notes: |
+    This is the last version of the GPL text as published by the FSF.
+    Second line of the notes.
+spdx_license_key: GPL-2.0-or-later
+other_spdx_license_keys:
+    - GPL-2.0+
+    - LicenseRef-GPL-2.0-or-later
+text_urls:
+- http://www.gnu.org/licenses/old-licenses/gpl-2.0.txt
+osi_license_key: GPL-2.0
+osi_url: 'http://opensource.org/licenses/gpl-license.php'
+other_urls:
+    - http://creativecommons.org/licenses/GPL/2.0/
+    - http://www.gnu.org/licenses/gpl-2.0.txt
+ignorable_copyrights:
+    - Copyright (c) 1989, 1991 Free Software Foundation, Inc.
+---
+";
+
+const EXCEPTION_HEADING: &str = "---
+key: classpath-exception-2.0
+short_name: Classpath exception to GPL 2.0 or later
+name: \"Classpath exception to GPL 2.0 or later\"
+category: Copyleft Limited
+owner: Oracle Corporation
+is_exception: yes
+spdx_license_key: Classpath-exception-2.0
+---
+";
+
+#[test]
+fn it_parses_the_heading(){
+    let text = format!("{}\nThe license text.\n", GPL_HEADING);
+    let (yaml, body) = split_license_heading(&text).unwrap();
+    assert!(yaml.starts_with("key: gpl-2.0-plus\n"));
+    assert_eq!(body, "\nThe license text.\n");
+
+    let metadata = parse_license_heading(&text).unwrap();
+    assert_eq!(metadata.key.as_deref(), Some("gpl-2.0-plus"));
+    assert_eq!(metadata.short_name.as_deref(), Some("GPL 2.0 or later"));
+    assert_eq!(metadata.category, Some(LicenseCategory::Copyleft));
+    assert_eq!(metadata.spdx_license_key.as_deref(), Some("GPL-2.0-or-later"));
+    assert_eq!(metadata.other_spdx_license_keys, vec!["GPL-2.0+", "LicenseRef-GPL-2.0-or-later"]);
+    assert_eq!(metadata.text_urls, vec!["http://www.gnu.org/licenses/old-licenses/gpl-2.0.txt"]);
+    assert_eq!(metadata.other_urls.len(), 2);
+    assert_eq!(metadata.osi_url.as_deref(), Some("http://opensource.org/licenses/gpl-license.php"));
+    assert!(metadata.is_osi_approved());
+    assert!(!metadata.is_exception);
+    assert!(!metadata.is_deprecated);
+
+    let metadata = parse_license_heading(EXCEPTION_HEADING).unwrap();
+    assert_eq!(metadata.name.as_deref(), Some("Classpath exception to GPL 2.0 or later"));
+    assert_eq!(metadata.category, Some(LicenseCategory::CopyleftLimited));
+    assert!(metadata.is_exception);
+    assert!(!metadata.is_osi_approved());
+}
+
+#[test]
+fn it_handles_missing_and_unknown_headings(){
+    assert!(split_license_heading("MIT License\n---\nfoo\n---\n").is_none());
+    assert!(split_license_heading("---\nkey: unterminated\n").is_none());
+
+    let metadata = parse_license_heading("---\ncategory: Something New\n---\n").unwrap();
+    assert_eq!(metadata.category, Some(LicenseCategory::Unknown("Something New".to_string())));
+    assert_eq!(LicenseCategory::parse("copyleft limited").to_string(), "Copyleft Limited");
+    assert_eq!(parse_license_heading("---\n---\n").unwrap(), LicenseMetadata::default());
+}
+
+#[test]
+fn it_loads_metadata_from_a_folder(){
+    let folder = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("metadata_licenses");
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    fs::write(folder.join("gpl-2.0-plus.LICENSE"), format!("{}\nThis program is free software.\n", GPL_HEADING)).unwrap();
+    fs::write(folder.join("plain.LICENSE"), "A license without a heading.\n").unwrap();
+
+    let mut licenses = load_licenses_from_folder(folder.to_str().unwrap());
+    licenses.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(licenses[0].metadata.as_ref().unwrap().key.as_deref(), Some("gpl-2.0-plus"));
+    assert_eq!(licenses[0].body(), "\nThis program is free software.\n");
+    assert!(licenses[1].metadata.is_none());
+    assert_eq!(licenses[1].body(), "A license without a heading.\n");
+}
+
+#[test]
+fn it_returns_metadata_with_matches(){
+    let license_text = fs::read_to_string("./LICENSE").unwrap();
+    let heading = "---\nkey: apache-2.0\ncategory: Permissive\nspdx_license_key: Apache-2.0\n---\n";
+    let license = RawLicense {
+        name: "apache-2.0.LICENSE".to_string(),
+        text: format!("{}{}", heading, license_text),
+        metadata: parse_license_heading(heading),
+    };
+
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    fuzzy.add_raw_license(&license);
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
+    gaoya.add_raw_license(&license);
+
+    let top = fuzzy.match_by_plain_text(&license_text).remove(0);
+    assert_eq!(top.confidence, 100.0);
+    assert_eq!(top.metadata.unwrap().category, Some(LicenseCategory::Permissive));
+    let top = gaoya.match_by_plain_text(&license_text).remove(0);
+    assert_eq!(top.metadata.unwrap().spdx_license_key.as_deref(), Some("Apache-2.0"));
+
+    // metadata of unknown licenses is ignored.
+    fuzzy.set_metadata("unknown", LicenseMetadata::default());
+    gaoya.set_metadata("unknown", LicenseMetadata::default());
+    assert!(fuzzy.get_metadata("unknown").is_none());
+    assert!(gaoya.get_metadata("unknown").is_none());
+}
+
+#[test]
+fn it_saves_and_loads_metadata(){
+    let metadata = parse_license_heading(GPL_HEADING).unwrap();
+
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
+    gaoya.add_plain("gpl-2.0-plus", "This program is free software; you can redistribute it and/or modify it.");
+    gaoya.set_metadata("gpl-2.0-plus", metadata.clone());
+    let mut loaded = GaoyaDetection::builder().build().unwrap();
+    loaded.try_load_from_memory(&gaoya.save_to_memory()).unwrap();
+    assert_eq!(loaded.get_metadata("gpl-2.0-plus"), Some(&metadata));
+    loaded.remove("gpl-2.0-plus");
+    assert!(loaded.get_metadata("gpl-2.0-plus").is_none());
+
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    fuzzy.add_plain("gpl-2.0-plus", "This program is free software; you can redistribute it and/or modify it.");
+    fuzzy.set_metadata("gpl-2.0-plus", metadata.clone());
+    let mut loaded = FuzzyDetection::builder().build().unwrap();
+    loaded.try_load_from_memory(&fuzzy.save_to_memory()).unwrap();
+    assert_eq!(loaded.get_metadata("gpl-2.0-plus"), Some(&metadata));
+}
+
+#[test]
+fn it_uses_metadata_for_spdx_expressions(){
+    let mut fuzzy = FuzzyDetection::builder().min_confidence(0).build().unwrap();
+    fuzzy.add_plain("custom-gpl", "This program is free software; you can redistribute it and/or modify it.");
+    fuzzy.set_metadata("custom-gpl", parse_license_heading(GPL_HEADING).unwrap());
+    fuzzy.add_plain("custom-exception", "As a special exception, the copyright holders give you permission to link.");
+    fuzzy.set_metadata("custom-exception", parse_license_heading(EXCEPTION_HEADING).unwrap());
+
+    let gpl = fuzzy.match_by_plain_text("This program is free software; you can redistribute it and/or modify it.").remove(0);
+    let exception = fuzzy.match_by_plain_text("As a special exception, the copyright holders give you permission to link.").remove(0);
+    assert_eq!(
+        SpdxMapping::default().expression_of_matches(&[&gpl, &exception]).unwrap().to_string(),
+        "GPL-2.0-or-later WITH Classpath-exception-2.0"
+    );
+    assert_eq!(SpdxMapping::default().spdx_id("custom-gpl"), "LicenseRef-scancode-custom-gpl");
+}
@@ -0,0 +1,197 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+use std::fs;
+
+use whichlicense_detection::{
+    align_normalized,
+    detecting::{
+        fuzzy_implementation::fuzzy_implementation::FuzzyDetection,
+        gaoya_implementation::gaoya_implementation::GaoyaDetection,
+    },
+    normalize_spdx, normalize_tokens, normalize_tokens_with_offsets, normalize_with_offsets, strip_license, strip_license_with_offsets,
+    tokenize_license, LicenseListActions,
+};
+
+#[test]
+fn it_strips_with_offsets(){
+    let original = "Héllo, World!";
+    let normalized = strip_license_with_offsets(original);
+    assert_eq!(normalized.text, strip_license(original));
+    assert_eq!(normalized.text, "HlloWorld");
+    assert_eq!(normalized.offsets.len(), normalized.text.len());
+
+    // every normalized byte maps back onto the same character.
+    for (i, c) in normalized.text.char_indices() {
+        assert_eq!(&original[normalized.offsets[i].clone()], c.to_string());
+    }
+    assert_eq!(normalized.original_range(4..9), Some(8..13));
+    assert_eq!(&original[normalized.original_range(4..9).unwrap()], "World");
+    assert_eq!(normalized.original_range(0..0), None);
+    assert_eq!(normalized.original_range(0..10), None);
+}
+
+#[test]
+fn it_aligns_custom_normalization(){
+    let original = "Copyright (C) 2023, The Authors";
+    let normalized = align_normalized(original, &original.to_lowercase().replace([' ', '(', ')', ','], ""));
+    assert_eq!(normalized.text, "copyrightc2023theauthors");
+    assert_eq!(&original[normalized.original_range(9..10).unwrap()], "C");
+    assert_eq!(&original[normalized.original_range(14..24).unwrap()], "The Authors");
+
+    // characters that do not exist in the original get an empty range and are skipped when mapping back.
+    let normalized = align_normalized("a b", "a_b");
+    assert_eq!(normalized.offsets, vec![0..1, 1..1, 2..3]);
+    assert_eq!(normalized.original_range(1..2), None);
+    assert_eq!(normalized.original_range(0..3), Some(0..3));
+
+    // a character missing from the original does not hold up the characters after it, even on large inputs.
+    let original = "ab".repeat(100_000);
+    let normalized = align_normalized(&original, &format!("{}b", "_".repeat(100_000)));
+    assert_eq!(normalized.offsets[100_000], 1..2);
+}
+
+#[test]
+fn it_uses_the_exact_offsets_of_strip_license(){
+    let original = "  MIT License.\n";
+    assert_eq!(normalize_with_offsets(original, strip_license), strip_license_with_offsets(original));
+
+    fn uppercase(l: &str) -> String {
+        l.to_uppercase()
+    }
+    let normalized = normalize_with_offsets("abc", uppercase);
+    assert_eq!(normalized.text, "ABC");
+    assert_eq!(normalized.offsets, vec![0..1, 1..2, 2..3]);
+}
+
+#[test]
+fn it_returns_detailed_matches(){
+    let license = fs::read_to_string("./LICENSE").unwrap();
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    fuzzy.add_plain("apache-2.0", &license);
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
+    gaoya.add_plain("apache-2.0", &license);
+
+    let input = format!("\n\n   {}   \n", license);
+    let detailed = [fuzzy.match_by_plain_text_detailed(&input), gaoya.match_by_plain_text_detailed(&input)];
+    for detailed in detailed.iter() {
+        assert_eq!(detailed.matches[0].name, "apache-2.0");
+        assert_eq!(detailed.normalized.text, strip_license(&license));
+
+        // the matched range starts at the first and ends at the last character that survived normalization.
+        let matched = &input[detailed.matched_range.clone().unwrap()];
+        assert!(matched.starts_with("Apache License"));
+        assert!(matched.ends_with("limitations under the License"));
+    }
+
+    assert!(fuzzy.match_by_plain_text_detailed(" \n ").matched_range.is_none());
+    assert!(detailed[0].deviations.is_empty());
+}
+
+#[test]
+fn it_returns_the_deviations_from_the_reference_text(){
+    let license = fs::read_to_string("./LICENSE").unwrap();
+    let mut fuzzy = FuzzyDetection::builder().store_reference_texts(true).build().unwrap();
+    fuzzy.add_plain("apache-2.0", &license);
+
+    let input = format!("/* header */\n{}", license.replacen("Apache License", "Apache Licence", 1).replacen("January ", "", 1));
+    let detailed = fuzzy.match_by_plain_text_detailed(&input);
+    assert_eq!(detailed.matches[0].name, "apache-2.0");
+
+    // comment markers vanish in normalization, the header and the changed words do not.
+    let deviations: Vec<&str> = detailed.deviations.iter().map(|range| &input[range.clone()]).collect();
+    assert_eq!(deviations, ["header", "Licence", ""]);
+    // the removed word is missing right after "Version 2.0,".
+    let removed = input.find("2.0,").unwrap() + "2.0,".len();
+    assert_eq!(detailed.deviations[2], removed..removed);
+
+    let matched = &input[detailed.matched_range.clone().unwrap()];
+    assert!(matched.starts_with("Apache Licence"));
+    assert!(matched.ends_with("limitations under the License."));
+}
+
+#[test]
+fn it_tokenizes_on_unicode_word_boundaries(){
+    assert_eq!(tokenize_license("MIT License"), ["mit", "license"]);
+    assert_eq!(strip_license("MIT License"), "MITLicense");
+
+    // punctuation is dropped, apostrophes and decimal points belong to their word.
+    assert_eq!(tokenize_license("\"AS IS\", licensor's version 2.0."), ["as", "is", "licensor's", "version", "2.0"]);
+
+    // non-ASCII letters are kept and case-folded, compatibility characters are NFKC normalized.
+    assert_eq!(tokenize_license("GEWÄHRLEISTUNG für Software"), ["gewährleistung", "für", "software"]);
+    assert_eq!(tokenize_license("ＭＩＴ ﬁle"), ["mit", "file"]);
+    assert_eq!(normalize_tokens("Ｅ\u{301}crit"), "écrit");
+
+    // every ideograph is a word of its own.
+    assert_eq!(normalize_tokens("本软件按“原样”提供"), "本 软 件 按 原 样 提 供");
+    assert_eq!(strip_license("本软件按“原样”提供"), "");
+}
+
+#[test]
+fn it_uses_the_exact_offsets_of_normalize_tokens(){
+    let original = "  MIT  Lizenz, für\n";
+    let normalized = normalize_tokens_with_offsets(original);
+    assert_eq!(normalized.text, normalize_tokens(original));
+    assert_eq!(normalized.text, "mit lizenz für");
+    assert_eq!(normalized.offsets.len(), normalized.text.len());
+    assert_eq!(normalize_with_offsets(original, normalize_tokens), normalized);
+
+    assert_eq!(&original[normalized.original_range(0..3).unwrap()], "MIT");
+    assert_eq!(&original[normalized.original_range(5..7).unwrap()], "Lizenz");
+    // the separating space maps to nothing.
+    assert_eq!(normalized.original_range(3..4), None);
+    assert_eq!(&original[normalized.original_range(4..15).unwrap()], "Lizenz, für");
+}
+
+#[test]
+fn it_follows_the_spdx_matching_guidelines(){
+    // whitespace, capitalization, punctuation, quotes and dashes.
+    assert_eq!(normalize_spdx("THE  \"Software\" -- is\n\tprovided ‘as is’"), "the software is provided as is");
+    assert_eq!(normalize_spdx("sub-license"), normalize_spdx("sublicense"));
+
+    // code comment indicators, bullets and numbering.
+    assert_eq!(normalize_spdx("/*\n * 1. Redistributions\n * (b) of source\n */"), "redistributions of source");
+    assert_eq!(normalize_spdx("# iv) Redistributions"), "redistributions");
+
+    // copyright notices, symbols and replaceable text.
+    assert_eq!(normalize_spdx("Copyright (c) 2023 Jane Doe\nAll rights reserved.\nPermission is granted"), "permission is granted");
+    assert_eq!(normalize_spdx("// © 2020-2023 ACME\nPermission is granted"), "permission is granted");
+    assert_eq!(normalize_spdx("notices by the © holder"), "notices by the copyright holder");
+    assert_eq!(normalize_spdx("Licensed to [name of copyright owner] in <year>"), "licensed to in");
+
+    // varietal spellings and protocols.
+    assert_eq!(normalize_spdx("The Licence is granted by the copyright owners"), "the license is granted by the copyright holders");
+    assert_eq!(normalize_spdx("Licenced whilst authorised, see https://example.com"), "licensed while authorized see http example.com");
+    // the owner of something other than a copyright is left alone.
+    assert_eq!(normalize_spdx("the owner"), "the owner");
+}
+
+#[test]
+fn it_matches_spdx_equivalent_texts_exactly(){
+    let license = fs::read_to_string("./LICENSE").unwrap();
+    let variant = format!("// Copyright 2023 ACME Corp.\n{}", license.replace("License", "Licence").replace("\n", "\n// "));
+
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    fuzzy.set_normalization_fn(normalize_spdx);
+    fuzzy.add_plain("apache-2.0", &license);
+
+    let matches = fuzzy.match_by_plain_text(&variant);
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].confidence, 100.0);
+    assert_eq!(fuzzy.match_exact(&normalize_spdx(&variant)).len(), 1);
+}
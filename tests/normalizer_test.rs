@@ -0,0 +1,115 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+use std::sync::Arc;
+
+use whichlicense_detection::{
+    detecting::fuzzy_implementation::fuzzy_implementation::FuzzyDetection, normalizer_by_id, read_database_header,
+    strip_license, BuiltinSegment, DetectionError, FnNormalizer, LicenseListActions, Normalizer, NormalizerChain,
+};
+
+fn without_stop_words(stop_words: Vec<String>) -> impl Normalizer {
+    FnNormalizer::new("without_stop_words", move |text: &str| {
+        let words: Vec<&str> = text
+            .split_whitespace()
+            .filter(|word| !stop_words.iter().any(|stop| stop.eq_ignore_ascii_case(word)))
+            .collect();
+        strip_license(&words.join(" "))
+    })
+}
+
+#[test]
+fn it_uses_a_configured_normalizer(){
+    let stop_words = vec!["the".to_string(), "any".to_string()];
+    let mut fuzzy = FuzzyDetection::builder().normalizer(without_stop_words(stop_words)).build().unwrap();
+    fuzzy.add_plain("test_license", "permission is hereby granted to the person obtaining a copy of this software");
+
+    let matches = fuzzy.match_by_plain_text("permission is hereby granted to any person obtaining a copy of this software");
+    assert_eq!(matches[0].name, "test_license");
+    assert_eq!(matches[0].confidence, 100.0);
+    assert_eq!(fuzzy.get_normalizer().id(), "without_stop_words");
+}
+
+#[test]
+fn it_chains_normalizers(){
+    let chain = NormalizerChain::new()
+        .then(BuiltinSegment::StripMarkup)
+        .then(BuiltinSegment::CollapseWhitespace)
+        .then(FnNormalizer::new("uppercase", |text: &str| text.to_uppercase()));
+
+    assert_eq!(chain.normalize("<p>MIT\n\n  <b>License</b></p>"), "MIT LICENSE");
+    assert_eq!(chain.id(), "strip_markup+collapse_whitespace+uppercase");
+    assert_eq!(NormalizerChain::new().normalize("as is"), "as is");
+}
+
+#[test]
+fn it_records_the_normalizer_id_in_the_database(){
+    let chain = || NormalizerChain::new().then(BuiltinSegment::StripMarkup).then(BuiltinSegment::NormalizeSpdx);
+    let mut fuzzy = FuzzyDetection::builder().normalizer(chain()).build().unwrap();
+    fuzzy.add_plain("test_license", "<p>This is a test licence</p>");
+    let raw = fuzzy.try_save_to_memory().unwrap();
+
+    assert_eq!(read_database_header(&raw).unwrap().normalization, "strip_markup+normalize_spdx");
+
+    let mut same = FuzzyDetection::builder().normalizer(chain()).build().unwrap();
+    same.try_load_from_memory(&raw).unwrap();
+    assert_eq!(same.match_by_plain_text("This is a test license")[0].confidence, 100.0);
+
+    let mut other = FuzzyDetection::builder().normalizer(BuiltinSegment::NormalizeSpdx).build().unwrap();
+    assert!(matches!(other.try_load_from_memory(&raw), Err(DetectionError::IncompatibleFormat(_))));
+}
+
+#[test]
+fn it_resolves_normalizers_by_id(){
+    for builtin in BuiltinSegment::ALL {
+        assert_eq!(normalizer_by_id(builtin.name()).unwrap().id(), builtin.name());
+    }
+
+    let chain = normalizer_by_id("strip_markup+normalize_tokens").unwrap();
+    assert_eq!(chain.id(), "strip_markup+normalize_tokens");
+    assert_eq!(chain.normalize("<b>MIT</b> License"), "mit license");
+
+    assert!(normalizer_by_id("custom").is_none());
+    assert!(normalizer_by_id("strip_markup+custom").is_none());
+}
+
+#[test]
+fn it_keeps_the_ids_of_normalization_fns(){
+    let fuzzy = FuzzyDetection::builder().build().unwrap();
+    assert_eq!(fuzzy.get_database_header(0).normalization, "strip_license");
+    let fuzzy = FuzzyDetection::builder().normalizer(BuiltinSegment::NormalizeSpdx).build().unwrap();
+    assert_eq!(fuzzy.get_database_header(0).normalization, "normalize_spdx");
+    // function pointers do not tell which function they are, not even the built-in ones.
+    let fuzzy = FuzzyDetection::builder().normalization_fn(strip_license).build().unwrap();
+    assert_eq!(fuzzy.get_database_header(0).normalization, "custom");
+
+    let mut fuzzy = FuzzyDetection::builder().normalization_fn(|x| x.to_lowercase()).build().unwrap();
+    assert_eq!(fuzzy.get_database_header(0).normalization, "custom");
+
+    fuzzy.set_normalizer(Arc::new(without_stop_words(vec![])));
+    assert_eq!(fuzzy.get_database_header(0).normalization, "without_stop_words");
+}
+
+#[test]
+fn it_maps_offsets_through_a_normalizer(){
+    let mut fuzzy = FuzzyDetection::builder().normalizer(BuiltinSegment::NormalizeTokens).build().unwrap();
+    fuzzy.add_plain("test_license", "Licensed under the MIT License");
+
+    let detailed = fuzzy.match_by_plain_text_detailed("// Licensed under the MIT License.");
+    assert_eq!(detailed.normalized.text, "licensed under the mit license");
+    assert_eq!(detailed.matched_range, Some(3..33));
+}
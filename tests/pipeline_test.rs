@@ -15,16 +15,14 @@
  *   limitations under the License.
  */
 
+// the original assertions read the first match with `get(0)`.
+#![allow(clippy::get_first)]
+
 use regex::Regex;
 use whichlicense_detection::{*, detecting::fuzzy_implementation::fuzzy_implementation::FuzzyDetection};
 
 fn create_testing_algorithm()-> FuzzyDetection {
-    let mut fuzzy = FuzzyDetection {
-        licenses: vec![],
-        min_confidence: 50,
-        exit_on_exact_match: false,
-        normalization_fn: |x| x.to_string(),
-    };
+    let mut fuzzy = FuzzyDetection::builder().normalization_fn(|x| x.to_string()).build().unwrap();
 
     fuzzy.add_plain("test_license_1", "this is a test license");
     fuzzy.add_plain("test_license_2", "this is a different test license");
@@ -200,4 +198,141 @@ fn it_executes_batch(){
 
     assert!(results.last().unwrap().get(0).unwrap().confidence == 100.0);
     assert!(results.last().unwrap().get(0).unwrap().name == "test_license_5");
-}
\ No newline at end of file
+}
+#[test]
+fn it_builds_a_pipeline_from_definitions() {
+    let alg = create_testing_algorithm();
+
+    let definitions: Vec<SegmentDefinition> = serde_json::from_str(
+        r#"[
+            { "type": "remove", "text": "-" },
+            { "type": "batch", "segments": [
+                { "type": "replace", "regex": "[xyz]+", "replacement": "" }
+            ]}
+        ]"#,
+    )
+    .unwrap();
+    assert_eq!(
+        definitions[1],
+        SegmentDefinition::Batch {
+            segments: vec![SegmentDefinition::Replace {
+                using: UsingDefinition::Regex("[xyz]+".to_string()),
+                replacement: String::new(),
+            }]
+        }
+    );
+
+    let pipeline = Pipeline::from_definitions(&definitions).unwrap();
+    let res = pipeline.run(&alg, "Hello, wo-rld!xyz", 100.0);
+    assert_eq!(res.len(), 3);
+    assert_eq!(res[2][0].name, "test_license_5");
+    assert_eq!(res[2][0].confidence, 100.0);
+}
+
+#[test]
+fn it_rejects_invalid_definitions() {
+    let definitions = vec![SegmentDefinition::Remove {
+        using: UsingDefinition::Regex("(".to_string()),
+    }];
+    assert!(matches!(
+        Pipeline::from_definitions(&definitions),
+        Err(DetectionError::InvalidParameter(_))
+    ));
+    assert!(serde_json::from_str::<SegmentDefinition>(r#"{ "type": "custom" }"#).is_err());
+}
+
+#[test]
+fn it_serializes_a_pipeline_back_to_definitions() {
+    let pipeline = Pipeline::new(vec![
+        Segment::Remove(Using::Regex(Regex::new(r"(?i)copyright.*").unwrap())),
+        Segment::Batch(vec![
+            Segment::Replace(Using::Text("\t".to_string()), " ".to_string()),
+            Segment::Builtin(BuiltinSegment::StripLicense),
+        ]),
+    ]);
+
+    let definition = pipeline.definition().unwrap();
+    let json = serde_json::to_string(&definition).unwrap();
+    assert_eq!(
+        json,
+        r#"{"segments":[{"type":"remove","regex":"(?i)copyright.*"},{"type":"batch","segments":[{"type":"replace","text":"\t","replacement":" "},{"type":"builtin","name":"strip_license"}]}]}"#
+    );
+    let parsed: PipelineDefinition = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, definition);
+    assert_eq!(parsed.build().unwrap().definition().unwrap(), definition);
+
+    let custom = Pipeline::new(vec![Segment::Batch(vec![Segment::Custom(Box::new(|s, _| s.to_string()))])]);
+    assert!(matches!(custom.definition(), Err(DetectionError::InvalidParameter(_))));
+}
+
+#[test]
+fn it_runs_builtin_segments() {
+    let alg = create_testing_algorithm();
+
+    let definition: PipelineDefinition =
+        serde_json::from_str(r#"{"segments": [{"type": "builtin", "name": "strip_spdx_heading"}]}"#).unwrap();
+    let res = definition.build().unwrap().run(&alg, "---\nkey: test\n---\nHello, world!", 100.0);
+    assert_eq!(res.len(), 2);
+    assert_eq!(res[1][0].name, "test_license_5");
+    assert_eq!(res[1][0].confidence, 100.0);
+
+    assert!(serde_json::from_str::<PipelineDefinition>(r#"{"segments": [{"type": "builtin", "name": "nope"}]}"#).is_err());
+    assert_eq!(BuiltinSegment::StripSpdxHeading.to_string(), "strip_spdx_heading");
+}
+
+#[test]
+fn it_reports_every_step() {
+    let alg = create_testing_algorithm();
+
+    let pipeline = Pipeline::new(vec![
+        Segment::Remove(Using::Text("-".to_string())),
+        Segment::Replace(Using::Regex(Regex::new(r"x+").unwrap()), "".to_string()),
+        Segment::Builtin(BuiltinSegment::CollapseWhitespace),
+    ]);
+    let report = pipeline.run_with_report(&alg, "Hello, wo-rld!xxx", 100.0);
+
+    assert_eq!(report.steps.len(), 3);
+    assert!(report.short_circuited);
+    assert_eq!(report.steps[0].segment_index, None);
+    assert_eq!(report.steps[0].segment_name, None);
+    assert_eq!((report.steps[0].length_before, report.steps[0].length_after), (17, 17));
+    assert_eq!(report.steps[1].segment_name, Some("remove"));
+    assert_eq!((report.steps[1].length_before, report.steps[1].length_after), (17, 16));
+    assert_eq!(report.steps[2].segment_index, Some(1));
+    assert_eq!(report.steps[2].segment_name, Some("replace"));
+    assert_eq!(report.steps[2].length_after, 13);
+    assert_eq!(report.steps[2].top_confidence(), 100.0);
+
+    let best = report.best.as_ref().unwrap();
+    assert_eq!(best.step, 2);
+    assert_eq!(best.license_match.name, "test_license_5");
+    assert_eq!(report.final_matches()[0].name, "test_license_5");
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["best"]["step"], 2);
+    assert_eq!(json["best"]["name"], "test_license_5");
+    assert_eq!(json["steps"][1]["segment_name"], "remove");
+    assert!(json["steps"][1]["duration_ms"].is_number());
+}
+
+#[test]
+fn it_reports_the_best_match_over_all_steps() {
+    let alg = create_testing_algorithm();
+
+    // the second segment makes the text worse, the best match stays the one of the first segment.
+    let pipeline = Pipeline::new(vec![
+        Segment::Remove(Using::Text("-".to_string())),
+        Segment::Replace(Using::Text("world".to_string()), "moon and stars".to_string()),
+    ]);
+    let report = pipeline.run_with_report(&alg, "Hello, wo-rld!", 101.0);
+
+    assert!(!report.short_circuited);
+    assert_eq!(report.steps.len(), 3);
+    assert_eq!(report.best.as_ref().unwrap().step, 1);
+    assert_eq!(report.best.as_ref().unwrap().license_match.confidence, 100.0);
+    assert!(report.final_matches().first().map_or(0.0, |m| m.confidence) < 100.0);
+    assert_eq!(report.desired_confidence, 101.0);
+
+    let runs = pipeline.run(&alg, "Hello, wo-rld!", 101.0);
+    assert_eq!(runs.len(), report.steps.len());
+}
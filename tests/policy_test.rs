@@ -0,0 +1,141 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+use std::fs;
+
+use whichlicense_detection::{
+    detecting::fuzzy_implementation::fuzzy_implementation::FuzzyDetection, LicenseCategory, LicenseListActions, LicenseMatch, LicenseMetadata, LicenseSelector, Pipeline, Policy, PolicyRule, SpdxExpression, Verdict,
+};
+
+fn parse(expression: &str) -> SpdxExpression {
+    expression.parse().unwrap()
+}
+
+fn license_match(name: &str, confidence: f32, category: Option<LicenseCategory>) -> LicenseMatch {
+    LicenseMatch {
+        name: name.to_string(),
+        confidence,
+        metadata: category.map(|category| LicenseMetadata {
+            category: Some(category),
+            ..Default::default()
+        }),
+    }
+}
+
+fn policy() -> Policy {
+    Policy::new(Verdict::Review)
+        .allow(LicenseSelector::expression("GPL-2.0-only WITH Classpath-exception-2.0").unwrap())
+        .rule(PolicyRule::new(Verdict::Deny, LicenseSelector::category(LicenseCategory::Copyleft)).with_reason("no copyleft"))
+        .deny(LicenseSelector::spdx("GPL-2.0-only"))
+        .allow(LicenseSelector::spdx("mit"))
+        .allow(LicenseSelector::spdx("Apache-2.0"))
+}
+
+#[test]
+fn it_evaluates_expressions(){
+    let policy = policy();
+    assert_eq!(policy.evaluate_expression(&parse("MIT")).verdict, Verdict::Allow);
+    assert_eq!(policy.evaluate_expression(&parse("MIT AND Apache-2.0")).verdict, Verdict::Allow);
+    assert_eq!(policy.evaluate_expression(&parse("MIT AND GPL-2.0-only")).verdict, Verdict::Deny);
+    assert_eq!(policy.evaluate_expression(&parse("MIT OR GPL-2.0-only")).verdict, Verdict::Allow);
+    assert_eq!(policy.evaluate_expression(&parse("GPL-2.0-only WITH Classpath-exception-2.0")).verdict, Verdict::Allow);
+    assert_eq!(policy.evaluate_expression(&parse("ISC OR GPL-2.0-only")).verdict, Verdict::Review);
+
+    let decision = policy.evaluate_expression(&parse("MIT AND ISC"));
+    assert_eq!(decision.verdict, Verdict::Review);
+    assert_eq!(decision.reasons.len(), 2);
+    assert_eq!(decision.reasons[0].message, "MIT is allowed by rule 'spdx mit'");
+    assert_eq!(decision.reasons[1].message, "no rule matches ISC, using the default verdict");
+    assert!(decision.reasons[1].rule.is_none());
+}
+
+#[test]
+fn it_ignores_the_case_of_expression_selectors(){
+    let policy = Policy::new(Verdict::Review)
+        .allow(LicenseSelector::expression("mit OR apache-2.0").unwrap())
+        .deny(LicenseSelector::expression("gpl-2.0-only WITH classpath-exception-2.0").unwrap());
+    assert_eq!(policy.evaluate_expression(&parse("MIT AND Apache-2.0")).verdict, Verdict::Allow);
+    assert_eq!(policy.evaluate_expression(&parse("GPL-2.0-only WITH Classpath-exception-2.0")).verdict, Verdict::Deny);
+    assert_eq!(policy.evaluate_expression(&parse("GPL-2.0-only")).verdict, Verdict::Review);
+}
+
+#[test]
+fn it_evaluates_matches_with_metadata(){
+    let policy = policy();
+    let gpl = license_match("gpl-3.0.LICENSE", 98.0, Some(LicenseCategory::Copyleft));
+    let mit = license_match("mit.LICENSE", 100.0, Some(LicenseCategory::Permissive));
+
+    let decision = policy.evaluate_matches(&[&mit, &gpl]);
+    assert_eq!(decision.verdict, Verdict::Deny);
+    assert_eq!(decision.expression.unwrap().to_string(), "MIT AND GPL-3.0-only");
+    assert_eq!(decision.reasons[1].license, "GPL-3.0-only");
+    assert_eq!(decision.reasons[1].message, "no copyleft");
+
+    // without metadata the category rule can not apply.
+    let gpl = license_match("gpl-3.0.LICENSE", 98.0, None);
+    assert_eq!(policy.evaluate_matches(&[&gpl]).verdict, Verdict::Review);
+}
+
+#[test]
+fn it_handles_missing_licenses(){
+    let mut policy = policy();
+    policy.no_license = Verdict::Deny;
+    policy.min_confidence = 90.0;
+
+    let decision = policy.evaluate_matches(&[]);
+    assert_eq!(decision.verdict, Verdict::Deny);
+    assert!(decision.expression.is_none());
+
+    let mit = license_match("mit.LICENSE", 60.0, None);
+    assert_eq!(policy.evaluate_matches(&[&mit]).verdict, Verdict::Deny);
+    assert_eq!(policy.evaluate_pipeline(&[vec![mit.clone()], vec![]]).verdict, Verdict::Deny);
+}
+
+#[test]
+fn it_evaluates_pipeline_results(){
+    let policy = policy();
+    let license = fs::read_to_string("./LICENSE").unwrap();
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    fuzzy.add_plain("apache-2.0.LICENSE", &license);
+    let results = Pipeline::new(vec![]).run(&fuzzy, &license, 100.0);
+    let decision = policy.evaluate_pipeline(&results);
+    assert!(decision.is_allowed());
+    assert_eq!(decision.expression.unwrap().to_string(), "Apache-2.0");
+}
+
+#[test]
+fn it_deserializes_policies(){
+    let policy: Policy = serde_json::from_str(
+        r#"{
+            "rules": [
+                { "verdict": "allow", "expression": "GPL-2.0-only WITH Classpath-exception-2.0" },
+                { "verdict": "deny", "category": "Copyleft", "reason": "no copyleft" },
+                { "verdict": "deny", "spdx": "GPL-2.0-only" },
+                { "verdict": "allow", "spdx": "mit" },
+                { "verdict": "allow", "spdx": "Apache-2.0" }
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(policy, self::policy());
+
+    let json = serde_json::to_value(&policy).unwrap();
+    assert_eq!(json["rules"][1]["category"], "Copyleft");
+    assert_eq!(json["default"], "review");
+    assert!(serde_json::from_str::<Policy>(r#"{"rules": [{"verdict": "allow", "expression": "MIT AND"}]}"#).is_err());
+}
+
@@ -0,0 +1,183 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+use std::{fs, path::{Path, PathBuf}};
+
+use whichlicense_detection::{
+    classify_path, detecting::fuzzy_implementation::fuzzy_implementation::FuzzyDetection, leading_comment,
+    scan_directory, CandidateKind, DetectionError, LicenseListActions, ScanOptions, SkipReason,
+};
+
+const APACHE_NOTICE: &str = r#"Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License."#;
+
+fn detector() -> FuzzyDetection {
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    fuzzy.add_plain("apache-2.0", &fs::read_to_string("./LICENSE").unwrap());
+    fuzzy.add_plain("apache-2.0-notice", APACHE_NOTICE);
+    fuzzy
+}
+
+/// Creates a fresh directory tree to scan, containing a license, a source file with a header and some noise.
+fn create_tree(name: &str) -> PathBuf {
+    let root = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("vendor/dep")).unwrap();
+    fs::create_dir_all(root.join("ignored")).unwrap();
+
+    fs::copy("./LICENSE", root.join("LICENSE")).unwrap();
+    fs::copy("./LICENSE", root.join("vendor/dep/LICENSE.txt")).unwrap();
+    fs::copy("./LICENSE", root.join("ignored/COPYING")).unwrap();
+    fs::write(root.join(".gitignore"), "ignored/\n").unwrap();
+    fs::write(root.join("README.md"), "# Example\nA project without a license in its readme.\n").unwrap();
+
+    let header: String = APACHE_NOTICE.lines().map(|l| format!("// {}\n", l)).collect();
+    fs::write(root.join("src/main.rs"), header + "\nfn main() {}\n").unwrap();
+    fs::write(root.join("src/lib.rs"), "pub fn no_header() {}\n").unwrap();
+    fs::write(root.join("src/data.bin"), [0u8, 1, 2, 3]).unwrap();
+    root
+}
+
+#[test]
+fn it_classifies_paths(){
+    assert_eq!(classify_path(Path::new("LICENSE")), Some(CandidateKind::License));
+    assert_eq!(classify_path(Path::new("a/LICENCE.md")), Some(CandidateKind::License));
+    assert_eq!(classify_path(Path::new("MIT-LICENSE.txt")), Some(CandidateKind::License));
+    assert_eq!(classify_path(Path::new("LICENSE-APACHE")), Some(CandidateKind::License));
+    assert_eq!(classify_path(Path::new("COPYING")), Some(CandidateKind::License));
+    assert_eq!(classify_path(Path::new("NOTICE")), Some(CandidateKind::Notice));
+    assert_eq!(classify_path(Path::new("readme.rst")), Some(CandidateKind::Readme));
+    assert_eq!(classify_path(Path::new("LICENSE.MIT")), Some(CandidateKind::License));
+    assert_eq!(classify_path(Path::new("LICENSE.APACHE")), Some(CandidateKind::License));
+    assert_eq!(classify_path(Path::new("LICENSE.BSD")), Some(CandidateKind::License));
+    assert_eq!(classify_path(Path::new("COPYING.LESSER")), Some(CandidateKind::License));
+    assert_eq!(classify_path(Path::new("NOTICE.apache.txt")), Some(CandidateKind::Notice));
+    assert_eq!(classify_path(Path::new("src/license.rs")), Some(CandidateKind::SourceHeader));
+    assert_eq!(classify_path(Path::new("licenses.json")), None);
+    assert_eq!(classify_path(Path::new("image.png")), None);
+}
+
+#[test]
+fn it_extracts_the_leading_comment(){
+    let source = "#!/bin/sh\n\n# first\n# second\necho hi\n# not part of the header\n";
+    assert_eq!(leading_comment(source, "sh", 60).unwrap(), "# first\n# second\n");
+
+    let source = "/*\n * block\n */\nint main() {}\n";
+    assert_eq!(leading_comment(source, "c", 60).unwrap(), "/*\n * block\n */\n");
+
+    assert!(leading_comment("fn main() {}\n// trailing\n", "rs", 60).is_none());
+    assert_eq!(leading_comment("// a\n// b\n// c\n", "rs", 2).unwrap(), "// a\n// b\n");
+}
+
+#[test]
+fn it_picks_line_comment_markers_by_extension(){
+    let source = "// header\n#include <stdio.h>\n#include <stdlib.h>\nint main() {}\n";
+    assert_eq!(leading_comment(source, "c", 60).unwrap(), "// header\n");
+    assert!(leading_comment("#include <stdio.h>\n", "c", 60).is_none());
+
+    assert_eq!(leading_comment("# header\nimport os\n", "py", 60).unwrap(), "# header\n");
+    assert!(leading_comment("// not a comment\n", "py", 60).is_none());
+    assert_eq!(leading_comment("-- header\nSELECT 1;\n", "SQL", 60).unwrap(), "-- header\n");
+}
+
+#[test]
+fn it_scans_a_directory(){
+    let root = create_tree("scan_directory");
+    let report = scan_directory(&root, &detector(), &ScanOptions::default()).unwrap();
+
+    let paths: Vec<&Path> = report.files.iter().map(|f| f.path.as_path()).collect();
+    assert_eq!(
+        paths,
+        vec![Path::new("LICENSE"), Path::new("README.md"), Path::new("src/main.rs"), Path::new("vendor/dep/LICENSE.txt")]
+    );
+
+    assert_eq!(report.files[0].kind, CandidateKind::License);
+    assert_eq!(report.files[0].matches[0].name, "apache-2.0");
+    assert!(report.files[1].matches.is_empty());
+    assert_eq!(report.files[2].kind, CandidateKind::SourceHeader);
+    assert_eq!(report.files[2].matches[0].name, "apache-2.0-notice");
+    assert_eq!(report.detected().count(), 3);
+}
+
+#[test]
+fn it_respects_excludes_and_gitignore(){
+    let root = create_tree("scan_excludes");
+    let options = ScanOptions {
+        excludes: vec!["vendor/".to_string(), "*.rs".to_string()],
+        ..Default::default()
+    };
+    let report = scan_directory(&root, &detector(), &options).unwrap();
+    let paths: Vec<&Path> = report.files.iter().map(|f| f.path.as_path()).collect();
+    assert_eq!(paths, vec![Path::new("LICENSE"), Path::new("README.md")]);
+
+    let options = ScanOptions {
+        respect_gitignore: false,
+        scan_source_headers: false,
+        ..Default::default()
+    };
+    let report = scan_directory(&root, &detector(), &options).unwrap();
+    assert!(report.files.iter().any(|f| f.path == Path::new("ignored/COPYING")));
+    assert!(!report.files.iter().any(|f| f.kind == CandidateKind::SourceHeader));
+}
+
+#[test]
+fn it_skips_large_and_binary_files(){
+    let root = create_tree("scan_skips");
+    fs::write(root.join("NOTICE"), [b'a', 0, b'b']).unwrap();
+    let options = ScanOptions {
+        max_file_size: 1000,
+        ..Default::default()
+    };
+    let report = scan_directory(&root, &detector(), &options).unwrap();
+
+    let license_size = fs::metadata("./LICENSE").unwrap().len();
+    assert_eq!(report.skipped.len(), 3);
+    assert_eq!(report.skipped[0].path, Path::new("LICENSE"));
+    assert_eq!(report.skipped[0].reason, SkipReason::TooLarge(license_size));
+    assert_eq!(report.skipped[1].path, Path::new("NOTICE"));
+    assert_eq!(report.skipped[1].reason, SkipReason::Binary);
+    assert_eq!(report.skipped[2].path, Path::new("vendor/dep/LICENSE.txt"));
+}
+
+#[test]
+fn it_fails_on_invalid_input(){
+    let missing = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("scan_missing_directory");
+    assert!(matches!(
+        scan_directory(&missing, &detector(), &ScanOptions::default()),
+        Err(DetectionError::Io(_))
+    ));
+
+    let root = create_tree("scan_invalid_pattern");
+    let options = ScanOptions {
+        excludes: vec!["a{".to_string()],
+        ..Default::default()
+    };
+    assert!(matches!(
+        scan_directory(&root, &detector(), &options),
+        Err(DetectionError::InvalidParameter(_))
+    ));
+}
@@ -0,0 +1,121 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+use std::fs;
+
+use whichlicense_detection::{
+    detect_spans,
+    detecting::{
+        fuzzy_implementation::fuzzy_implementation::FuzzyDetection,
+        gaoya_implementation::gaoya_implementation::GaoyaDetection,
+    },
+    split_blocks, LicenseListActions, SegmentOptions,
+};
+
+const MIT: &str = "MIT License
+
+Copyright (c) <year> <copyright holders>
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the \"Software\"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED \"AS IS\", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+";
+
+/// An MIT license followed by the Apache 2.0 license, separated like a third-party notices file.
+fn dual_license() -> (String, String) {
+    let apache = fs::read_to_string("./LICENSE").unwrap();
+    let text = format!("Third party notices\n\n{}\n--------------------------------\n\n{}", MIT, apache);
+    (text, apache)
+}
+
+#[test]
+fn it_splits_blocks(){
+    let text = "first line\nsecond line\n\n  \n----\nthird\r\n\n";
+    let blocks = split_blocks(text);
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(&text[blocks[0].byte_range.clone()], "first line\nsecond line");
+    assert_eq!((blocks[0].start_line, blocks[0].end_line), (1, 2));
+    assert_eq!(&text[blocks[1].byte_range.clone()], "third");
+    assert_eq!((blocks[1].start_line, blocks[1].end_line), (6, 6));
+}
+
+#[test]
+fn it_detects_multiple_licenses_with_fuzzy(){
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    fuzzy.add_plain("mit", MIT);
+    fuzzy.add_plain("apache-2.0", &fs::read_to_string("./LICENSE").unwrap());
+
+    let (text, apache) = dual_license();
+    let spans = detect_spans(&fuzzy, &text, &SegmentOptions::default());
+
+    assert_eq!(spans.len(), 2);
+    assert_eq!(spans[0].license().name, "mit");
+    assert_eq!(&text[spans[0].byte_range.clone()], MIT.trim_end());
+    assert_eq!((spans[0].start_line, spans[0].end_line), (3, 23));
+    assert_eq!(spans[1].license().name, "apache-2.0");
+    assert_eq!(&text[spans[1].byte_range.clone()], apache.trim());
+    assert_eq!(spans[1].end_line, text.lines().count());
+}
+
+#[test]
+fn it_detects_multiple_licenses_with_gaoya(){
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
+    gaoya.add_plain("mit", MIT);
+    gaoya.add_plain("apache-2.0", &fs::read_to_string("./LICENSE").unwrap());
+
+    let (text, _) = dual_license();
+    let spans = detect_spans(&gaoya, &text, &SegmentOptions::default());
+    let names: Vec<&str> = spans.iter().map(|s| s.license().name.as_str()).collect();
+    assert_eq!(names, vec!["mit", "apache-2.0"]);
+    assert!(spans[0].end_line < spans[1].start_line);
+}
+
+#[test]
+fn it_returns_no_spans_without_licenses(){
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    fuzzy.add_plain("mit", MIT);
+
+    let text = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore.\n\n".repeat(5);
+    assert!(detect_spans(&fuzzy, &text, &SegmentOptions::default()).is_empty());
+    assert!(detect_spans(&fuzzy, "", &SegmentOptions::default()).is_empty());
+}
+
+#[test]
+fn it_skips_empty_matches_without_min_confidence(){
+    let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+    fuzzy.add_plain("mit", MIT);
+
+    let options = SegmentOptions { min_confidence: 0.0, ..SegmentOptions::default() };
+    let text = format!("{}\n\n{}", "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore.\n\n".repeat(5), MIT);
+    let spans = detect_spans(&fuzzy, &text, &options);
+    assert!(spans.iter().all(|s| !s.matches.is_empty()));
+    assert_eq!(spans.last().unwrap().license().name, "mit");
+}
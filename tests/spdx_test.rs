@@ -0,0 +1,140 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+use whichlicense_detection::{scancode_key, DetectionError, SpdxExpression, SpdxLicense, SpdxMapping};
+
+fn parse(expression: &str) -> SpdxExpression {
+    expression.parse().unwrap()
+}
+
+#[test]
+fn it_parses_expressions(){
+    assert_eq!(parse("MIT"), SpdxExpression::License(SpdxLicense::new("MIT")));
+    assert_eq!(
+        parse("GPL-2.0+"),
+        SpdxExpression::License(SpdxLicense { id: "GPL-2.0".to_string(), or_later: true })
+    );
+    assert_eq!(
+        parse("GPL-2.0-or-later WITH Classpath-exception-2.0"),
+        SpdxExpression::With(SpdxLicense::new("GPL-2.0-or-later"), "Classpath-exception-2.0".to_string())
+    );
+    assert_eq!(
+        parse("MIT AND Apache-2.0 OR ISC"),
+        SpdxExpression::Or(vec![
+            SpdxExpression::And(vec![parse("MIT"), parse("Apache-2.0")]),
+            parse("ISC"),
+        ])
+    );
+    assert_eq!(
+        parse("MIT AND (Apache-2.0 OR ISC)"),
+        SpdxExpression::And(vec![
+            parse("MIT"),
+            SpdxExpression::Or(vec![parse("Apache-2.0"), parse("ISC")]),
+        ])
+    );
+    assert_eq!(parse("DocumentRef-spdx-tool-1.2:LicenseRef-MIT-Style-2"), SpdxExpression::License(SpdxLicense::new("DocumentRef-spdx-tool-1.2:LicenseRef-MIT-Style-2")));
+}
+
+#[test]
+fn it_writes_expressions(){
+    for expression in [
+        "MIT",
+        "GPL-2.0+",
+        "MIT AND Apache-2.0",
+        "MIT OR Apache-2.0 AND ISC",
+        "(MIT OR Apache-2.0) AND ISC",
+        "GPL-2.0-only WITH Classpath-exception-2.0 OR MIT",
+        "(MIT AND ISC) AND Apache-2.0",
+    ] {
+        assert_eq!(parse(expression).to_string(), expression);
+    }
+    assert_eq!(parse("  ( MIT )AND(ISC)").to_string(), "MIT AND ISC");
+}
+
+#[test]
+fn it_rejects_invalid_expressions(){
+    for expression in ["", "MIT AND", "AND MIT", "(MIT", "MIT)", "MIT WITH", "MIT WITH (ISC)", "MIT ISC", "MIT/ISC", "GPL++", "MIT WITH GPL+", "MIT and ISC", "MIT or ISC", "GPL-2.0-only with Classpath-exception-2.0"] {
+        assert!(
+            matches!(expression.parse::<SpdxExpression>(), Err(DetectionError::InvalidExpression(_))),
+            "{} should be rejected",
+            expression
+        );
+    }
+}
+
+#[test]
+fn it_serializes_as_a_string(){
+    let expression = parse("MIT OR Apache-2.0");
+    let json = serde_json::to_string(&expression).unwrap();
+    assert_eq!(json, "\"MIT OR Apache-2.0\"");
+    assert_eq!(serde_json::from_str::<SpdxExpression>(&json).unwrap(), expression);
+    assert!(serde_json::from_str::<SpdxExpression>("\"MIT OR\"").is_err());
+}
+
+#[test]
+fn it_combines_expressions(){
+    assert_eq!(SpdxExpression::and(vec![]), None);
+    assert_eq!(SpdxExpression::and(vec![parse("MIT")]), Some(parse("MIT")));
+    assert_eq!(
+        SpdxExpression::and(vec![parse("MIT AND ISC"), parse("ISC"), parse("Apache-2.0 OR 0BSD")]).unwrap().to_string(),
+        "MIT AND ISC AND (Apache-2.0 OR 0BSD)"
+    );
+    assert_eq!(SpdxExpression::or(vec![parse("MIT"), parse("ISC")]).unwrap().to_string(), "MIT OR ISC");
+
+    let licenses: Vec<String> = parse("MIT AND (ISC OR GPL-2.0+ WITH Classpath-exception-2.0)").licenses().iter().map(|l| l.to_string()).collect();
+    assert_eq!(licenses, vec!["MIT", "ISC", "GPL-2.0+"]);
+}
+
+#[test]
+fn it_maps_database_entries_to_spdx(){
+    let mapping = SpdxMapping::default();
+    assert_eq!(scancode_key("apache-2.0.LICENSE"), "apache-2.0");
+    assert_eq!(mapping.spdx_id("apache-2.0.LICENSE"), "Apache-2.0");
+    assert_eq!(mapping.spdx_id("gpl-2.0-plus"), "GPL-2.0-or-later");
+    assert_eq!(mapping.spdx_id("bsd-new.LICENSE"), "BSD-3-Clause");
+    assert_eq!(mapping.spdx_id("some-custom.LICENSE"), "LicenseRef-scancode-some-custom");
+    assert!(mapping.is_exception("classpath-exception-2.0.LICENSE"));
+    assert!(!mapping.is_exception("mit.LICENSE"));
+
+    // ScanCode keys may contain characters SPDX identifiers do not allow.
+    let id = mapping.spdx_id("x11-xconsortium_veillard.LICENSE");
+    assert_eq!(id, "LicenseRef-scancode-x11-xconsortium-veillard");
+    assert_eq!(parse(&format!("{} AND MIT", id)).to_string(), format!("{} AND MIT", id));
+
+    let mut custom = SpdxMapping::empty();
+    assert_eq!(custom.spdx_id("mit"), "LicenseRef-scancode-mit");
+    custom.insert("mit.LICENSE", "MIT", false);
+    assert_eq!(custom.spdx_id("mit"), "MIT");
+}
+
+#[test]
+fn it_builds_an_expression_from_matches(){
+    let mapping = SpdxMapping::default();
+    assert_eq!(mapping.expression::<&str>(&[]), None);
+    assert_eq!(
+        mapping.expression(&["mit.LICENSE", "apache-2.0.LICENSE", "mit.LICENSE"]).unwrap().to_string(),
+        "MIT AND Apache-2.0"
+    );
+    assert_eq!(
+        mapping.expression(&["gpl-2.0-plus.LICENSE", "classpath-exception-2.0.LICENSE", "mit.LICENSE"]).unwrap().to_string(),
+        "GPL-2.0-or-later WITH Classpath-exception-2.0 AND MIT"
+    );
+    assert_eq!(
+        mapping.expression(&["classpath-exception-2.0.LICENSE"]).unwrap().to_string(),
+        "Classpath-exception-2.0"
+    );
+}
@@ -0,0 +1,154 @@
+/*
+*   Copyright (c) 2023 Duart Snel
+*   All rights reserved.
+
+*   Licensed under the Apache License, Version 2.0 (the "License");
+*   you may not use this file except in compliance with the License.
+*   You may obtain a copy of the License at
+
+*   http://www.apache.org/licenses/LICENSE-2.0
+
+*   Unless required by applicable law or agreed to in writing, software
+*   distributed under the License is distributed on an "AS IS" BASIS,
+*   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*   See the License for the specific language governing permissions and
+*   limitations under the License.
+*/
+
+use std::{
+    fs,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use whichlicense_detection::{
+    detecting::{
+        block_hash_implementation::block_hash_implementation::BlockHashDetection,
+        fuzzy_implementation::fuzzy_implementation::FuzzyDetection,
+        gaoya_implementation::gaoya_implementation::GaoyaDetection,
+    },
+    match_batch, match_batch_with, run_pipeline_batch, BatchOptions, LicenseListActions, Pipeline, Segment, Using,
+};
+
+const MIT: &str = "Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the \"Software\"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software.";
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+fn inputs() -> Vec<String> {
+    let apache = fs::read_to_string("./LICENSE").unwrap();
+    (0..40)
+        .map(|i| match i % 3 {
+            0 => apache.clone(),
+            1 => MIT.to_string(),
+            _ => format!("not a license {}", i),
+        })
+        .collect()
+}
+
+fn gaoya() -> GaoyaDetection {
+    let mut gaoya = GaoyaDetection::builder().build().unwrap();
+    gaoya.add_plain("apache-2.0", &fs::read_to_string("./LICENSE").unwrap());
+    gaoya.add_plain("mit", MIT);
+    gaoya
+}
+
+#[test]
+fn it_is_send_and_sync(){
+    assert_send_sync::<FuzzyDetection>();
+    assert_send_sync::<GaoyaDetection>();
+    assert_send_sync::<BlockHashDetection>();
+    assert_send_sync::<Pipeline>();
+}
+
+#[test]
+fn it_matches_in_input_order(){
+    let gaoya = gaoya();
+    let inputs = inputs();
+    let expected: Vec<_> = inputs.iter().map(|input| gaoya.match_by_plain_text(input)).collect();
+
+    for options in [
+        BatchOptions::default(),
+        BatchOptions { threads: 1, max_in_flight: 1 },
+        BatchOptions { threads: 8, max_in_flight: 3 },
+    ] {
+        let results = match_batch(&gaoya, inputs.iter(), &options);
+        assert_eq!(results.len(), inputs.len());
+        for (result, expected) in results.iter().zip(expected.iter()) {
+            let names: Vec<&str> = result.iter().map(|m| m.name.as_str()).collect();
+            let expected: Vec<&str> = expected.iter().map(|m| m.name.as_str()).collect();
+            assert_eq!(names, expected);
+        }
+    }
+    assert!(match_batch(&gaoya, Vec::<String>::new(), &BatchOptions::default()).is_empty());
+}
+
+#[test]
+fn it_bounds_the_inputs_in_flight(){
+    let gaoya = gaoya();
+    let produced = AtomicUsize::new(0);
+    let options = BatchOptions { threads: 4, max_in_flight: 5 };
+    let inputs = inputs().into_iter().inspect(|_| {
+        produced.fetch_add(1, Ordering::SeqCst);
+    });
+
+    let mut next = 0;
+    match_batch_with(&gaoya, inputs, &options, |index, _| {
+        assert_eq!(index, next);
+        next += 1;
+        // the feeder may hold one more input while waiting for room.
+        assert!(produced.load(Ordering::SeqCst) <= index + options.max_in_flight + 1);
+    });
+    assert_eq!(next, 40);
+}
+
+#[test]
+fn it_shares_detectors_between_threads(){
+    let fuzzy = Arc::new({
+        let mut fuzzy = FuzzyDetection::builder().build().unwrap();
+        fuzzy.add_plain("mit", MIT);
+        fuzzy
+    });
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let fuzzy = Arc::clone(&fuzzy);
+            thread::spawn(move || fuzzy.match_by_plain_text(MIT)[0].name.clone())
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), "mit");
+    }
+}
+
+#[test]
+fn it_runs_pipelines_in_parallel(){
+    let gaoya = gaoya();
+    let pipeline = Pipeline::new(vec![
+        Segment::Remove(Using::Text("NOISE".to_string())),
+        Segment::Custom(Box::new(|license, _| license.trim().to_string())),
+    ]);
+    let inputs: Vec<String> = inputs().into_iter().map(|input| format!("NOISE {} NOISE", input)).collect();
+
+    let results = run_pipeline_batch(&pipeline, &gaoya, inputs.iter(), 99.0, &BatchOptions::default());
+    assert_eq!(results.len(), inputs.len());
+    for (input, result) in inputs.iter().zip(results.iter()) {
+        let expected = pipeline.run(&gaoya, input, 99.0);
+        assert_eq!(result.len(), expected.len());
+        let top = |runs: &Vec<Vec<_>>| runs.last().unwrap().first().map(|m: &whichlicense_detection::LicenseMatch| m.name.clone());
+        assert_eq!(top(result), top(&expected));
+    }
+}
+
+#[test]
+#[should_panic]
+fn it_propagates_panics(){
+    let gaoya = gaoya();
+    let pipeline = Pipeline::new(vec![Segment::Custom(Box::new(|license, _| {
+        assert!(!license.contains("panic"));
+        license.to_string()
+    }))]);
+    let inputs = (0..100).map(|i| if i == 10 { "panic".to_string() } else { format!("input {}", i) });
+    run_pipeline_batch(&pipeline, &gaoya, inputs, 101.0, &BatchOptions { threads: 2, max_in_flight: 2 });
+}